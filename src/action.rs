@@ -0,0 +1,685 @@
+//! User actions shared by key bindings and startup sequences.
+//!
+//! Every way of poking stonktop ends up here, whether it's a frantic
+//! keypress or a carefully scripted `--on-start`.
+
+use crate::cli::{FilterType, SortField};
+use crate::models::{SortOrder, View, HOLDINGS_COLUMNS};
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Something the user can ask the app to do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Quit,
+    SelectUp,
+    SelectDown,
+    SelectTop,
+    SelectBottom,
+    PageUp,
+    PageDown,
+    CycleSort,
+    ReverseSort,
+    SortBy(SortOrder),
+    /// Sort by the nth (1-based) hotkey column of the active view
+    SortColumn(u8),
+    ToggleHoldings,
+    ToggleFundamentals,
+    ToggleFullVolume,
+    FasterRefresh,
+    SlowerRefresh,
+    ToggleHelp,
+    ToggleDetail,
+    Refresh,
+    NextGroup,
+    SetView(View),
+    SetFilter(Option<FilterType>),
+    EditReference,
+    EditNote,
+    ToggleFavorite,
+    CopySymbol,
+    CopyQuote,
+    ResetSession,
+    Search,
+}
+
+/// A key binding: the keys, what they do, and how the help overlay describes it.
+#[derive(Debug, Clone, Copy)]
+pub struct Binding {
+    /// Heading the binding is listed under
+    pub section: &'static str,
+    pub keys: &'static [(KeyCode, KeyModifiers)],
+    pub action: Action,
+    pub description: &'static str,
+}
+
+const fn key(c: char) -> (KeyCode, KeyModifiers) {
+    (KeyCode::Char(c), KeyModifiers::NONE)
+}
+
+const fn code(code: KeyCode) -> (KeyCode, KeyModifiers) {
+    (code, KeyModifiers::NONE)
+}
+
+const fn sort_column(
+    keys: &'static [(KeyCode, KeyModifiers)],
+    column: u8,
+    description: &'static str,
+) -> Binding {
+    Binding {
+        section: "Sorting",
+        keys,
+        action: Action::SortColumn(column),
+        description,
+    }
+}
+
+/// Only the holdings view has a ninth column to sort.
+const HOLDINGS_COLUMN: &str = "Sort by column (holdings view)";
+
+/// Every key binding, in help overlay order.
+///
+/// The single source of truth for [`Action::from_key`], the help overlay and
+/// `--keys`, so the three can't disagree about what `r` does.
+pub const BINDINGS: &[Binding] = &[
+    // Navigation
+    Binding {
+        section: "Navigation",
+        keys: &[code(KeyCode::Up), key('k')],
+        action: Action::SelectUp,
+        description: "Move up",
+    },
+    Binding {
+        section: "Navigation",
+        keys: &[code(KeyCode::Down), key('j')],
+        action: Action::SelectDown,
+        description: "Move down",
+    },
+    Binding {
+        section: "Navigation",
+        keys: &[key('g'), code(KeyCode::Home)],
+        action: Action::SelectTop,
+        description: "Go to top",
+    },
+    Binding {
+        section: "Navigation",
+        keys: &[key('G'), code(KeyCode::End)],
+        action: Action::SelectBottom,
+        description: "Go to bottom",
+    },
+    Binding {
+        section: "Navigation",
+        keys: &[code(KeyCode::PageUp)],
+        action: Action::PageUp,
+        description: "Page up",
+    },
+    Binding {
+        section: "Navigation",
+        keys: &[code(KeyCode::PageDown)],
+        action: Action::PageDown,
+        description: "Page down",
+    },
+    // Sorting
+    Binding {
+        section: "Sorting",
+        keys: &[key('s')],
+        action: Action::CycleSort,
+        description: "Cycle sort field",
+    },
+    Binding {
+        section: "Sorting",
+        keys: &[key('r')],
+        action: Action::ReverseSort,
+        description: "Reverse sort order",
+    },
+    sort_column(&[key('1')], 1, "Sort by column"),
+    sort_column(&[key('2')], 2, "Sort by column"),
+    sort_column(&[key('3')], 3, "Sort by column"),
+    sort_column(&[key('4')], 4, "Sort by column"),
+    sort_column(&[key('5')], 5, "Sort by column"),
+    sort_column(&[key('6')], 6, "Sort by column"),
+    sort_column(&[key('7')], 7, "Sort by column"),
+    sort_column(&[key('8')], 8, "Sort by biggest movers"),
+    sort_column(&[key('9')], 9, HOLDINGS_COLUMN),
+    // Display toggles
+    Binding {
+        section: "Display",
+        keys: &[key('H')],
+        action: Action::ToggleHoldings,
+        description: "Toggle holdings view",
+    },
+    Binding {
+        section: "Display",
+        keys: &[key('f')],
+        action: Action::ToggleFundamentals,
+        description: "Toggle fundamentals",
+    },
+    Binding {
+        section: "Display",
+        keys: &[key('#')],
+        action: Action::ToggleFullVolume,
+        description: "Toggle full volume numbers / K-M-B suffixes",
+    },
+    Binding {
+        section: "Display",
+        keys: &[key('e')],
+        action: Action::EditReference,
+        description: "Set reference price (REF% column)",
+    },
+    Binding {
+        section: "Display",
+        keys: &[key('n')],
+        action: Action::EditNote,
+        description: "Edit note for selected symbol",
+    },
+    Binding {
+        section: "Display",
+        keys: &[key('*')],
+        action: Action::ToggleFavorite,
+        description: "Pin/unpin selected symbol",
+    },
+    Binding {
+        section: "Display",
+        keys: &[code(KeyCode::Enter)],
+        action: Action::ToggleDetail,
+        description: "Details for selected symbol",
+    },
+    Binding {
+        section: "Display",
+        keys: &[key('/')],
+        action: Action::Search,
+        description: "Search symbols and names (empty clears)",
+    },
+    Binding {
+        section: "Display",
+        keys: &[code(KeyCode::Tab)],
+        action: Action::NextGroup,
+        description: "Cycle groups",
+    },
+    // Everything else
+    Binding {
+        section: "Actions",
+        keys: &[key(' '), key('R')],
+        action: Action::Refresh,
+        description: "Force refresh",
+    },
+    Binding {
+        section: "Actions",
+        keys: &[key('+'), key('=')],
+        action: Action::FasterRefresh,
+        description: "Refresh twice as often",
+    },
+    Binding {
+        section: "Actions",
+        keys: &[key('-')],
+        action: Action::SlowerRefresh,
+        description: "Refresh half as often",
+    },
+    Binding {
+        section: "Actions",
+        keys: &[key('y')],
+        action: Action::CopySymbol,
+        description: "Copy selected symbol to the clipboard",
+    },
+    Binding {
+        section: "Actions",
+        keys: &[key('Y')],
+        action: Action::CopyQuote,
+        description: "Copy selected quote line to the clipboard",
+    },
+    Binding {
+        section: "Actions",
+        keys: &[key('S')],
+        action: Action::ResetSession,
+        description: "Restart the SESSION change from current prices",
+    },
+    Binding {
+        section: "Actions",
+        keys: &[
+            key('q'),
+            code(KeyCode::Esc),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL),
+        ],
+        action: Action::Quit,
+        description: "Quit",
+    },
+    Binding {
+        section: "Actions",
+        keys: &[key('h'), key('?')],
+        action: Action::ToggleHelp,
+        description: "Toggle help",
+    },
+];
+
+/// Actions only reachable from `--on-start`, with the step that triggers them.
+pub const START_STEPS: &[(&str, Action, &str)] = &[
+    (
+        "sort=<field>",
+        Action::SortBy(SortOrder::ChangePercent),
+        "Sort by a field",
+    ),
+    ("view=<view>", Action::SetView(View::Quotes), "Switch view"),
+    (
+        "filter=<type>",
+        Action::SetFilter(None),
+        "Filter by quote type",
+    ),
+];
+
+/// Human-readable name of a key, with the arrow keys spelled as given.
+pub fn key_name(code: KeyCode, modifiers: KeyModifiers, up: &str, down: &str) -> String {
+    let name = match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => up.to_string(),
+        KeyCode::Down => down.to_string(),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        other => other.to_string(),
+    };
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        format!("Ctrl-{}", name)
+    } else {
+        name
+    }
+}
+
+/// The help overlay's view of [`BINDINGS`]: `(section, keys, description)`,
+/// with neighbouring bindings that share a description folded into one row
+/// (`1-7  Sort by column`). The number keys mean something else in the
+/// holdings view, so that gets a row of its own after the sorting keys.
+pub fn help_rows(up: &str, down: &str) -> Vec<(&'static str, String, String)> {
+    let mut rows: Vec<(&'static str, Vec<String>, &'static str)> = Vec::new();
+    for binding in BINDINGS {
+        let keys = binding
+            .keys
+            .iter()
+            .map(|&(code, modifiers)| key_name(code, modifiers, up, down));
+        match rows.last_mut() {
+            Some((section, names, description))
+                if *section == binding.section && *description == binding.description =>
+            {
+                names.extend(keys)
+            }
+            _ => rows.push((binding.section, keys.collect(), binding.description)),
+        }
+    }
+
+    let mut rows: Vec<_> = rows
+        .into_iter()
+        .map(|(section, names, description)| {
+            let keys = match names.as_slice() {
+                [first, .., last]
+                    if names
+                        .iter()
+                        .all(|n| n.len() == 1 && n.chars().all(|c| c.is_ascii_digit())) =>
+                {
+                    format!("{}-{}", first, last)
+                }
+                _ => names.join("/"),
+            };
+            (section, keys, description.to_string())
+        })
+        .collect();
+
+    let after_sorting = rows
+        .iter()
+        .rposition(|(section, _, _)| *section == "Sorting")
+        .map_or(rows.len(), |i| i + 1);
+    rows.insert(after_sorting, holdings_sort_help());
+    rows
+}
+
+/// The holdings view's sort keys, read off the columns its table draws:
+/// `1-3/5/7-9  Holdings: SYMBOL, NAME, PRICE, VALUE, P/L, P/L%, TODAY`.
+fn holdings_sort_help() -> (&'static str, String, String) {
+    let sortable: Vec<(usize, &str)> = HOLDINGS_COLUMNS
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, order))| order.is_some())
+        .map(|(i, (header, _))| (i + 1, *header))
+        .collect();
+
+    // Runs of consecutive keys fold into ranges
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for &(column, _) in &sortable {
+        match runs.last_mut() {
+            Some((_, end)) if *end + 1 == column => *end = column,
+            _ => runs.push((column, column)),
+        }
+    }
+    let keys: Vec<String> = runs
+        .iter()
+        .map(|&(start, end)| match end - start {
+            0 => start.to_string(),
+            1 => format!("{}/{}", start, end),
+            _ => format!("{}-{}", start, end),
+        })
+        .collect();
+    let headers: Vec<&str> = sortable.iter().map(|(_, header)| *header).collect();
+
+    (
+        "Sorting",
+        keys.join("/"),
+        format!("Holdings: {}", headers.join(", ")),
+    )
+}
+
+/// The effective key map as a plain-text table, for `--keys`.
+pub fn format_bindings() -> String {
+    let mut out = format!(
+        "{:<12} {:<20} {:<20} {}\n",
+        "SECTION", "KEYS", "ACTION", "DESCRIPTION"
+    );
+    for binding in BINDINGS {
+        let keys: Vec<String> = binding
+            .keys
+            .iter()
+            .map(|&(code, modifiers)| key_name(code, modifiers, "Up", "Down"))
+            .collect();
+        out.push_str(&format!(
+            "{:<12} {:<20} {:<20} {}\n",
+            binding.section,
+            keys.join(", "),
+            format!("{:?}", binding.action),
+            binding.description
+        ));
+    }
+    for (step, action, description) in START_STEPS {
+        out.push_str(&format!(
+            "{:<12} {:<20} {:<20} {}\n",
+            "--on-start",
+            step,
+            action_name(action),
+            description
+        ));
+    }
+    out
+}
+
+/// Variant name without the payload, e.g. `SortBy` for `SortBy(Price)`.
+fn action_name(action: &Action) -> String {
+    let debug = format!("{:?}", action);
+    match debug.split_once('(') {
+        Some((name, _)) => name.to_string(),
+        None => debug,
+    }
+}
+
+impl Action {
+    /// Map a key press to an action, according to [`BINDINGS`].
+    pub fn from_key(code: KeyCode, modifiers: KeyModifiers) -> Option<Self> {
+        BINDINGS
+            .iter()
+            .find(|binding| {
+                binding
+                    .keys
+                    .iter()
+                    .any(|&(key, required)| key == code && modifiers.contains(required))
+            })
+            .map(|binding| binding.action)
+    }
+
+    /// Whether the action is harmless enough for secure mode.
+    pub fn allowed_in_secure_mode(&self) -> bool {
+        matches!(self, Action::Quit | Action::SelectUp | Action::SelectDown)
+    }
+}
+
+/// Parse a startup sequence like `sort=price;view=holdings;filter=stocks`.
+///
+/// Supported steps:
+/// - `sort=<field>` using the same names as `--sort`
+/// - `reverse` to flip the sort direction
+/// - `view=quotes|holdings|dashboard|fundamentals`
+/// - `filter=stocks|crypto|etf|index|all`
+pub fn parse_start_sequence(sequence: &str) -> Result<Vec<Action>> {
+    sequence
+        .split(';')
+        .map(str::trim)
+        .filter(|step| !step.is_empty())
+        .map(parse_step)
+        .collect()
+}
+
+/// Parse a single `key=value` step of a startup sequence.
+fn parse_step(step: &str) -> Result<Action> {
+    let (key, value) = match step.split_once('=') {
+        Some((k, v)) => (k.trim(), v.trim()),
+        None => (step, ""),
+    };
+
+    let action = match key {
+        "sort" => {
+            let field = SortField::from_str(&value.replace('_', "-"), true)
+                .map_err(|_| anyhow::anyhow!("Unknown sort field in start sequence: {}", value))?;
+            Action::SortBy(field.into())
+        }
+        "reverse" => Action::ReverseSort,
+        "view" => match value.to_ascii_lowercase().as_str() {
+            "quotes" => Action::SetView(View::Quotes),
+            "holdings" | "dashboard" | "portfolio" => Action::SetView(View::Holdings),
+            "fundamentals" => Action::SetView(View::Fundamentals),
+            _ => bail!("Unknown view in start sequence: {}", value),
+        },
+        "filter" => match value.to_ascii_lowercase().as_str() {
+            "all" | "none" => Action::SetFilter(None),
+            other => {
+                let filter = FilterType::from_str(other, true)
+                    .map_err(|_| anyhow::anyhow!("Unknown filter in start sequence: {}", value))?;
+                Action::SetFilter(Some(filter))
+            }
+        },
+        _ => bail!("Unknown start sequence step: {}", step),
+    };
+
+    Ok(action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_start_sequence() {
+        let actions = parse_start_sequence("sort=price; view=dashboard;filter=stocks").unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                Action::SortBy(SortOrder::Price),
+                Action::SetView(View::Holdings),
+                Action::SetFilter(Some(FilterType::Stocks)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_start_sequence_accepts_config_spellings() {
+        let actions = parse_start_sequence("sort=change_percent;reverse;filter=all;").unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                Action::SortBy(SortOrder::ChangePercent),
+                Action::ReverseSort,
+                Action::SetFilter(None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_start_sequence_rejects_unknown_steps() {
+        assert!(parse_start_sequence("sort=vibes").is_err());
+        assert!(parse_start_sequence("view=moon").is_err());
+        assert!(parse_start_sequence("yolo=1").is_err());
+    }
+
+    #[test]
+    fn test_from_key_maps_sort_hotkeys() {
+        assert_eq!(
+            Action::from_key(KeyCode::Char('3'), KeyModifiers::NONE),
+            Some(Action::SortColumn(3))
+        );
+        assert_eq!(
+            Action::from_key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            Action::from_key(KeyCode::Char('z'), KeyModifiers::NONE),
+            None
+        );
+    }
+
+    /// One of every action. The match makes adding a variant without
+    /// listing it here a compile error.
+    fn every_action() -> Vec<Action> {
+        let all = vec![
+            Action::Quit,
+            Action::SelectUp,
+            Action::SelectDown,
+            Action::SelectTop,
+            Action::SelectBottom,
+            Action::PageUp,
+            Action::PageDown,
+            Action::CycleSort,
+            Action::ReverseSort,
+            Action::SortBy(SortOrder::Price),
+            Action::SortColumn(1),
+            Action::ToggleHoldings,
+            Action::ToggleFundamentals,
+            Action::ToggleFullVolume,
+            Action::FasterRefresh,
+            Action::SlowerRefresh,
+            Action::ToggleHelp,
+            Action::ToggleDetail,
+            Action::Refresh,
+            Action::NextGroup,
+            Action::SetView(View::Holdings),
+            Action::SetFilter(None),
+            Action::EditReference,
+            Action::EditNote,
+            Action::ToggleFavorite,
+            Action::CopySymbol,
+            Action::CopyQuote,
+            Action::ResetSession,
+            Action::Search,
+        ];
+        for action in &all {
+            match action {
+                Action::Quit
+                | Action::SelectUp
+                | Action::SelectDown
+                | Action::SelectTop
+                | Action::SelectBottom
+                | Action::PageUp
+                | Action::PageDown
+                | Action::CycleSort
+                | Action::ReverseSort
+                | Action::SortBy(_)
+                | Action::SortColumn(_)
+                | Action::ToggleHoldings
+                | Action::ToggleFundamentals
+                | Action::ToggleFullVolume
+                | Action::FasterRefresh
+                | Action::SlowerRefresh
+                | Action::ToggleHelp
+                | Action::ToggleDetail
+                | Action::Refresh
+                | Action::NextGroup
+                | Action::SetView(_)
+                | Action::SetFilter(_)
+                | Action::EditReference
+                | Action::EditNote
+                | Action::ToggleFavorite
+                | Action::CopySymbol
+                | Action::CopyQuote
+                | Action::ResetSession
+                | Action::Search => {}
+            }
+        }
+        all
+    }
+
+    #[test]
+    fn test_every_action_is_in_the_key_table() {
+        let table = format_bindings();
+        for action in every_action() {
+            assert!(
+                table.contains(&action_name(&action)),
+                "{:?} missing from --keys output",
+                action
+            );
+        }
+    }
+
+    #[test]
+    fn test_bindings_round_trip_through_from_key() {
+        for binding in BINDINGS {
+            for &(code, modifiers) in binding.keys {
+                assert_eq!(Action::from_key(code, modifiers), Some(binding.action));
+            }
+        }
+        // Ctrl-C quits, a plain c does nothing
+        assert_eq!(
+            Action::from_key(KeyCode::Char('c'), KeyModifiers::NONE),
+            None
+        );
+        // Shifted letters arrive with SHIFT set
+        assert_eq!(
+            Action::from_key(KeyCode::Char('G'), KeyModifiers::SHIFT),
+            Some(Action::SelectBottom)
+        );
+    }
+
+    #[test]
+    fn test_help_rows_fold_shared_descriptions() {
+        let rows = help_rows("Up", "Down");
+        let row = |section, keys: &str, description: &str| {
+            (section, keys.to_string(), description.to_string())
+        };
+        assert!(rows.contains(&row("Navigation", "Up/k", "Move up")));
+        assert!(rows.contains(&row("Sorting", "1-7", "Sort by column")));
+        assert!(rows.contains(&row("Sorting", "9", HOLDINGS_COLUMN)));
+        assert!(rows.contains(&row("Actions", "q/Esc/Ctrl-c", "Quit")));
+        // The holdings row follows the columns the table draws
+        let holdings = rows
+            .iter()
+            .position(|r| r.2.starts_with("Holdings:"))
+            .unwrap();
+        assert_eq!(
+            rows[holdings],
+            row(
+                "Sorting",
+                "1-3/5/7-9",
+                "Holdings: SYMBOL, NAME, PRICE, VALUE, P/L, P/L%, TODAY"
+            )
+        );
+        assert_eq!(rows[holdings - 1].1, "9");
+        assert_eq!(rows[holdings + 1].0, "Display");
+    }
+
+    #[test]
+    fn test_holdings_sort_keys_reach_every_sortable_column() {
+        use crate::app::App;
+        use crate::cli::Args;
+        use crate::config::Config;
+        use clap::Parser;
+
+        let mut app = App::new(&Args::parse_from(["stonktop"]), &Config::default()).unwrap();
+        app.set_view(View::Holdings);
+        let action = Action::from_key(KeyCode::Char('9'), KeyModifiers::NONE).unwrap();
+        assert_eq!(action, Action::SortColumn(9));
+        app.apply_action(action);
+        assert_eq!(app.holdings_sort_order, SortOrder::DayChange);
+
+        // Every sortable holdings column has a key bound to it
+        for (i, (header, order)) in HOLDINGS_COLUMNS.iter().enumerate() {
+            let Some(order) = order else { continue };
+            let digit = char::from_digit(i as u32 + 1, 10).unwrap();
+            let action = Action::from_key(KeyCode::Char(digit), KeyModifiers::NONE);
+            assert_eq!(action, Some(Action::SortColumn(i as u8 + 1)), "{}", header);
+            app.apply_action(action.unwrap());
+            assert_eq!(app.holdings_sort_order, *order, "{}", header);
+        }
+    }
+}
@@ -0,0 +1,404 @@
+//! Per-symbol alert rules, and the portfolio-wide panic threshold.
+//!
+//! Tell the app what scares you and it will let you know the moment it happens.
+
+use crate::models::Quote;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Something about a quote worth shouting about.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// Price at or above a level
+    PriceAbove(f64),
+    /// Price at or below a level
+    PriceBelow(f64),
+    /// Bid/ask spread wider than this many basis points
+    SpreadAbove(f64),
+    /// Day change at or above this percent
+    ChangePercentAbove(f64),
+    /// Day change at or below this percent (e.g. -5 for "down 5%")
+    ChangePercentBelow(f64),
+}
+
+impl AlertCondition {
+    /// Check the condition against a quote. Conditions on data the quote
+    /// doesn't have (e.g. no bid/ask) never match.
+    pub fn matches(&self, quote: &Quote) -> bool {
+        match *self {
+            AlertCondition::PriceAbove(level) => quote.price >= level,
+            AlertCondition::PriceBelow(level) => quote.price <= level,
+            AlertCondition::SpreadAbove(bps) => quote.spread_bps().is_some_and(|s| s > bps),
+            AlertCondition::ChangePercentAbove(pct) => quote.change_percent >= pct,
+            AlertCondition::ChangePercentBelow(pct) => quote.change_percent <= pct,
+        }
+    }
+
+    /// The condition's name as written in the config.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertCondition::PriceAbove(_) => "price_above",
+            AlertCondition::PriceBelow(_) => "price_below",
+            AlertCondition::SpreadAbove(_) => "spread_above",
+            AlertCondition::ChangePercentAbove(_) => "change_percent_above",
+            AlertCondition::ChangePercentBelow(_) => "change_percent_below",
+        }
+    }
+
+    /// The level, spread or percent the condition compares against.
+    pub fn threshold(&self) -> f64 {
+        match *self {
+            AlertCondition::PriceAbove(value)
+            | AlertCondition::PriceBelow(value)
+            | AlertCondition::SpreadAbove(value)
+            | AlertCondition::ChangePercentAbove(value)
+            | AlertCondition::ChangePercentBelow(value) => value,
+        }
+    }
+
+    /// Whether a negative threshold means anything. "Down 5%" does;
+    /// a price or spread of -5 would just never (or always) fire.
+    pub fn allows_negative(&self) -> bool {
+        matches!(
+            self,
+            AlertCondition::ChangePercentAbove(_) | AlertCondition::ChangePercentBelow(_)
+        )
+    }
+
+    /// Reject thresholds the condition can't use.
+    pub fn validate(&self) -> Result<()> {
+        let threshold = self.threshold();
+        if !threshold.is_finite() {
+            anyhow::bail!("{} needs a number, got {}", self.name(), threshold);
+        }
+        if threshold < 0.0 && !self.allows_negative() {
+            anyhow::bail!("{} can't be negative: {}", self.name(), threshold);
+        }
+        Ok(())
+    }
+
+    /// Describe the current state of a matching quote.
+    pub fn describe(&self, quote: &Quote) -> String {
+        match *self {
+            AlertCondition::PriceAbove(level) => {
+                format!("{} at {:.2}, above {:.2}", quote.symbol, quote.price, level)
+            }
+            AlertCondition::PriceBelow(level) => {
+                format!("{} at {:.2}, below {:.2}", quote.symbol, quote.price, level)
+            }
+            AlertCondition::SpreadAbove(bps) => format!(
+                "{} spread {:.0}bps, above {:.0}bps",
+                quote.symbol,
+                quote.spread_bps().unwrap_or(0.0),
+                bps
+            ),
+            AlertCondition::ChangePercentAbove(pct) => format!(
+                "{} {:+.2}% today, above {:+.2}%",
+                quote.symbol, quote.change_percent, pct
+            ),
+            AlertCondition::ChangePercentBelow(pct) => format!(
+                "{} {:+.2}% today, below {:+.2}%",
+                quote.symbol, quote.change_percent, pct
+            ),
+        }
+    }
+}
+
+/// An alert condition attached to a symbol.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub symbol: String,
+    pub condition: AlertCondition,
+}
+
+/// A rule that just started matching.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriggeredAlert {
+    pub message: String,
+    /// The quote behind it is older than the staleness threshold: the last
+    /// known price, not a live one
+    pub stale: bool,
+}
+
+impl std::fmt::Display for TriggeredAlert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.stale {
+            write!(f, "{} (stale)", self.message)
+        } else {
+            write!(f, "{}", self.message)
+        }
+    }
+}
+
+/// Find the rules that newly match, given the set of rules (by index) that
+/// matched last time. `active` is updated so a rule fires once per crossing.
+///
+/// Quotes are whatever was last fetched, so a rule can fire on a price that
+/// is no longer live (a failing feed, or a market that closed hours ago).
+/// With `stale_after` set, alerts on quotes older than that are tagged stale.
+pub fn check_alerts(
+    rules: &[AlertRule],
+    quotes: &[Quote],
+    active: &mut HashSet<usize>,
+    stale_after: Option<Duration>,
+    now: DateTime<Utc>,
+) -> Vec<TriggeredAlert> {
+    let mut fired = Vec::new();
+
+    for (index, rule) in rules.iter().enumerate() {
+        let Some(quote) = quotes
+            .iter()
+            .find(|q| q.symbol.eq_ignore_ascii_case(&rule.symbol))
+        else {
+            continue;
+        };
+
+        if rule.condition.matches(quote) {
+            if active.insert(index) {
+                fired.push(TriggeredAlert {
+                    message: rule.condition.describe(quote),
+                    stale: stale_after.is_some_and(|limit| now - quote.timestamp > limit),
+                });
+            }
+        } else {
+            active.remove(&index);
+        }
+    }
+
+    fired
+}
+
+/// Percentage points the day has to get worse, after the panic warning is
+/// dismissed, before it comes back. Less than this is the same bad day.
+pub const PANIC_REARM_STEP: f64 = 1.0;
+
+/// Watches the portfolio's day change for a drop past the panic threshold.
+///
+/// The warning shows while the day is at or below the threshold. Dismissing
+/// it holds it back until the day gets [`PANIC_REARM_STEP`] points worse, or
+/// recovers and crosses the threshold again.
+#[derive(Debug)]
+pub struct PanicWatch {
+    /// Always negative: a drop of this many percent
+    threshold: f64,
+    /// Day change while past the threshold
+    level: Option<f64>,
+    /// Day change when the warning was last dismissed
+    dismissed_at: Option<f64>,
+}
+
+impl PanicWatch {
+    /// Watch for a day drop of `threshold` percent; -5 and 5 both mean down 5%.
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold: -threshold.abs(),
+            level: None,
+            dismissed_at: None,
+        }
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// Record the portfolio's day change in percent (None without holdings).
+    pub fn update(&mut self, day_change_percent: Option<f64>) {
+        match day_change_percent {
+            Some(pct) if pct <= self.threshold => {
+                self.level = Some(pct);
+                if self
+                    .dismissed_at
+                    .is_some_and(|dismissed| pct <= dismissed - PANIC_REARM_STEP)
+                {
+                    self.dismissed_at = None;
+                }
+            }
+            // Back above the line: the next crossing is news again
+            _ => {
+                self.level = None;
+                self.dismissed_at = None;
+            }
+        }
+    }
+
+    /// The day change to warn about, if the warning should be showing.
+    pub fn active(&self) -> Option<f64> {
+        self.level.filter(|_| self.dismissed_at.is_none())
+    }
+
+    /// Hide the warning at the current level. Returns whether it was showing.
+    pub fn dismiss(&mut self) -> bool {
+        let showing = self.active();
+        if showing.is_some() {
+            self.dismissed_at = showing;
+        }
+        showing.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(symbol: &str, bid: Option<f64>, ask: Option<f64>) -> Quote {
+        Quote {
+            symbol: symbol.to_string(),
+            price: 10.0,
+            bid,
+            ask,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_spread_above_threshold() {
+        let rule = AlertCondition::SpreadAbove(50.0);
+        // 10.00 / 10.10: 0.10 over a 10.05 mid is ~99.5bps
+        assert!(rule.matches(&book("THIN", Some(10.0), Some(10.1))));
+        // 10.00 / 10.02: ~20bps
+        assert!(!rule.matches(&book("THIN", Some(10.0), Some(10.02))));
+    }
+
+    #[test]
+    fn test_spread_needs_both_sides() {
+        let rule = AlertCondition::SpreadAbove(0.0);
+        assert!(!rule.matches(&book("X", None, Some(10.0))));
+        assert!(!rule.matches(&book("X", Some(10.0), None)));
+        // Crossed book is data junk, not an infinitely liquid market
+        assert!(!rule.matches(&book("X", Some(10.2), Some(10.0))));
+    }
+
+    #[test]
+    fn test_check_alerts_fires_once_per_crossing() {
+        let rules = vec![AlertRule {
+            symbol: "thin".to_string(),
+            condition: AlertCondition::SpreadAbove(50.0),
+        }];
+        let mut active = HashSet::new();
+        let wide = vec![book("THIN", Some(10.0), Some(10.1))];
+        let tight = vec![book("THIN", Some(10.0), Some(10.01))];
+
+        let mut check = |quotes| check_alerts(&rules, quotes, &mut active, None, Utc::now());
+        assert_eq!(check(&wide).len(), 1);
+        assert!(check(&wide).is_empty());
+        assert!(check(&tight).is_empty());
+        assert_eq!(check(&wide).len(), 1);
+    }
+
+    #[test]
+    fn test_check_alerts_tags_stale_quotes() {
+        let rules = vec![AlertRule {
+            symbol: "AAPL".to_string(),
+            condition: AlertCondition::PriceBelow(150.0),
+        }];
+        let now = Utc::now();
+        let quote = |age_mins: i64| Quote {
+            symbol: "AAPL".to_string(),
+            price: 140.0,
+            timestamp: now - Duration::minutes(age_mins),
+            ..Default::default()
+        };
+        let fire = |quote: Quote, stale_after: Option<Duration>| {
+            check_alerts(&rules, &[quote], &mut HashSet::new(), stale_after, now).remove(0)
+        };
+
+        let fresh = fire(quote(5), Some(Duration::minutes(15)));
+        assert!(!fresh.stale);
+        assert_eq!(fresh.to_string(), "AAPL at 140.00, below 150.00");
+
+        // Still fires on the last known price, but says it isn't live
+        let stale = fire(quote(60), Some(Duration::minutes(15)));
+        assert!(stale.stale);
+        assert_eq!(stale.to_string(), "AAPL at 140.00, below 150.00 (stale)");
+
+        // No threshold, no tagging
+        assert!(!fire(quote(60), None).stale);
+    }
+
+    #[test]
+    fn test_rule_config_round_trip() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Wrapper {
+            rules: Vec<AlertRule>,
+        }
+
+        let parsed: Wrapper = toml::from_str(
+            r#"
+            [[rules]]
+            symbol = "PENNY"
+            condition = { spread_above = 75.0 }
+
+            [[rules]]
+            symbol = "AAPL"
+            condition = { price_below = 150.0 }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(parsed.rules[0].condition, AlertCondition::SpreadAbove(75.0));
+
+        let round_trip: Wrapper = toml::from_str(&toml::to_string(&parsed).unwrap()).unwrap();
+        assert_eq!(round_trip, parsed);
+    }
+
+    #[test]
+    fn test_negative_thresholds_only_for_percent_change() {
+        let down_five = AlertCondition::ChangePercentBelow(-5.0);
+        assert!(down_five.validate().is_ok());
+        assert!(AlertCondition::ChangePercentAbove(-1.0).validate().is_ok());
+
+        for condition in [
+            AlertCondition::PriceAbove(-5.0),
+            AlertCondition::PriceBelow(-5.0),
+            AlertCondition::SpreadAbove(-5.0),
+        ] {
+            let err = condition.validate().unwrap_err().to_string();
+            assert!(err.contains(condition.name()), "{}", err);
+        }
+        assert!(AlertCondition::PriceBelow(0.0).validate().is_ok());
+        assert!(AlertCondition::ChangePercentBelow(f64::NAN)
+            .validate()
+            .is_err());
+
+        let quote = |change_percent| Quote {
+            symbol: "TSLA".to_string(),
+            change_percent,
+            ..Default::default()
+        };
+        assert!(down_five.matches(&quote(-6.0)));
+        assert!(!down_five.matches(&quote(-4.0)));
+        assert!(down_five.describe(&quote(-6.0)).contains("-6.00%"));
+    }
+
+    #[test]
+    fn test_panic_watch_crossing_and_dismissal() {
+        let mut watch = PanicWatch::new(5.0);
+        assert_eq!(watch.threshold(), -5.0);
+
+        watch.update(Some(-4.9));
+        assert_eq!(watch.active(), None);
+        watch.update(None);
+        assert_eq!(watch.active(), None);
+
+        watch.update(Some(-5.0));
+        assert_eq!(watch.active(), Some(-5.0));
+        assert!(watch.dismiss());
+        assert!(!watch.dismiss());
+
+        // A little worse is the same bad day
+        watch.update(Some(-5.8));
+        assert_eq!(watch.active(), None);
+        // A point worse than when dismissed brings it back
+        watch.update(Some(-6.0));
+        assert_eq!(watch.active(), Some(-6.0));
+
+        // Recovering re-arms it for the next crossing
+        watch.dismiss();
+        watch.update(Some(-3.0));
+        assert_eq!(watch.active(), None);
+        watch.update(Some(-5.2));
+        assert_eq!(watch.active(), Some(-5.2));
+    }
+}
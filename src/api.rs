@@ -2,11 +2,12 @@
 //!
 //! Because checking your portfolio every 5 seconds is totally healthy behavior.
 
-use crate::models::{MarketState, Quote, QuoteType};
+use crate::models::{FetchFailure, MarketState, Quote, QuoteBatch, QuoteType};
 use anyhow::{Context, Result};
 use chrono::{TimeZone, Utc};
 use futures::future::join_all;
-use reqwest::Client;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, ACCEPT_LANGUAGE};
+use reqwest::{Client, ClientBuilder, StatusCode};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::time::Duration;
@@ -14,16 +15,115 @@ use std::time::Duration;
 /// The v8 chart API endpoint - the one that still works (for now).
 const YAHOO_CHART_URL: &str = "https://query1.finance.yahoo.com/v8/finance/chart";
 
+/// Hitting this sets the session cookie Yahoo wants before it hands out a crumb.
+const YAHOO_COOKIE_URL: &str = "https://fc.yahoo.com";
+
+/// Returns a crumb token for the current session cookie.
+const YAHOO_CRUMB_URL: &str = "https://query1.finance.yahoo.com/v1/test/getcrumb";
+
 /// Pretending to be a real browser because Yahoo has trust issues.
 const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
 
+/// Accept headers matching the browser in `USER_AGENT`.
+const ACCEPT_VALUE: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+const ACCEPT_LANGUAGE_VALUE: &str = "en-US,en;q=0.9";
+
+/// Region and language Yahoo assumes when nobody says otherwise.
+pub const DEFAULT_REGION: &str = "US";
+pub const DEFAULT_LANG: &str = "en-US";
+
 /// Validate that a symbol contains only safe characters for URL construction.
-fn is_valid_symbol(symbol: &str) -> bool {
+pub fn is_valid_symbol(symbol: &str) -> bool {
     !symbol.is_empty()
         && symbol.len() <= 20
         && symbol
             .chars()
-            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '^')
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '^' | '='))
+}
+
+/// Percent-encode a symbol for use as a URL path segment.
+/// Only RFC 3986 unreserved characters pass through, so index carets
+/// (`^GSPC`) and FX equals signs (`EURUSD=X`) never reach the wire raw.
+fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// HTTP client settings.
+/// Connections are pooled and kept alive between refreshes, so frequent
+/// refreshes skip the TCP/TLS handshake; these knobs tune that pool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientOptions {
+    /// Chart API base URL (overridable for tests)
+    pub chart_url: String,
+    /// Page that sets the session cookie
+    pub cookie_url: String,
+    /// Endpoint that returns a crumb for the session cookie
+    pub crumb_url: String,
+    /// User-Agent sent with every request
+    pub user_agent: String,
+    /// Per-request timeout
+    pub timeout: Duration,
+    /// How long an idle pooled connection is kept (None = reqwest default)
+    pub pool_idle_timeout: Option<Duration>,
+    /// Maximum idle connections kept per host (None = reqwest default)
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Market region sent with quote requests, e.g. "US" or "DE"
+    pub region: String,
+    /// Language for names and labels, e.g. "en-US" or "de-DE"
+    pub lang: String,
+    /// Ask for the intraday series with the pre/post sessions, for the
+    /// extended-hours price. Much bigger responses than one daily bar
+    pub extended_hours: bool,
+}
+
+impl ClientOptions {
+    /// Options with the given timeout and default pool settings.
+    pub fn with_timeout(timeout_secs: u64) -> Self {
+        Self {
+            chart_url: YAHOO_CHART_URL.to_string(),
+            cookie_url: YAHOO_COOKIE_URL.to_string(),
+            crumb_url: YAHOO_CRUMB_URL.to_string(),
+            user_agent: USER_AGENT.to_string(),
+            timeout: Duration::from_secs(timeout_secs),
+            pool_idle_timeout: None,
+            pool_max_idle_per_host: None,
+            region: DEFAULT_REGION.to_string(),
+            lang: DEFAULT_LANG.to_string(),
+            extended_hours: false,
+        }
+    }
+
+    /// Apply these options to a reqwest client builder.
+    fn apply(&self, builder: ClientBuilder) -> ClientBuilder {
+        // The rest of what a browser sends; a bare UA is easier to flag
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static(ACCEPT_VALUE));
+        headers.insert(
+            ACCEPT_LANGUAGE,
+            HeaderValue::from_static(ACCEPT_LANGUAGE_VALUE),
+        );
+
+        let mut builder = builder
+            .user_agent(&self.user_agent)
+            .default_headers(headers)
+            .cookie_store(true)
+            .timeout(self.timeout);
+        if let Some(idle) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(idle);
+        }
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        builder
+    }
 }
 
 /// Yahoo Finance API client.
@@ -31,28 +131,47 @@ fn is_valid_symbol(symbol: &str) -> bool {
 pub struct YahooFinanceClient {
     client: Client,
     timeout: Duration,
+    options: ClientOptions,
+    /// Crumb for the session cookie, fetched the first time Yahoo says 401.
+    /// An async lock, held across the handshake, so a batch of 401s shares one
+    crumb: tokio::sync::Mutex<Option<String>>,
 }
 
 impl YahooFinanceClient {
     /// Create a new Yahoo Finance client.
     pub fn new(timeout_secs: u64) -> Result<Self> {
-        let client = Client::builder()
-            .user_agent(USER_AGENT)
-            .timeout(Duration::from_secs(timeout_secs))
+        Self::with_options(ClientOptions::with_timeout(timeout_secs))
+    }
+
+    /// Create a client with explicit HTTP settings.
+    pub fn with_options(options: ClientOptions) -> Result<Self> {
+        let client = options
+            .apply(Client::builder())
             .build()
             .context("Failed to create HTTP client")?;
 
         Ok(Self {
             client,
-            timeout: Duration::from_secs(timeout_secs),
+            timeout: options.timeout,
+            options,
+            crumb: tokio::sync::Mutex::new(None),
+        })
+    }
+
+    /// Rebuild the client with a different User-Agent.
+    pub fn with_user_agent(self, user_agent: impl Into<String>) -> Result<Self> {
+        Self::with_options(ClientOptions {
+            user_agent: user_agent.into(),
+            ..self.options
         })
     }
 
     /// Fetch quotes for multiple symbols using parallel requests.
     /// Yahoo's v8 chart API only supports one symbol at a time, so we parallelize.
-    pub async fn get_quotes(&self, symbols: &[String]) -> Result<Vec<Quote>> {
+    /// A symbol that fails doesn't sink the batch; it's reported alongside.
+    pub async fn get_quotes(&self, symbols: &[String]) -> Result<QuoteBatch> {
         if symbols.is_empty() {
-            return Ok(Vec::new());
+            return Ok(QuoteBatch::default());
         }
 
         // Fetch all symbols in parallel
@@ -63,29 +182,44 @@ impl YahooFinanceClient {
 
         let results = join_all(futures).await;
 
-        // Collect successful results, skip failures
-        let quotes: Vec<Quote> = results.into_iter().filter_map(|r| r.ok()).collect();
+        let mut batch = QuoteBatch::default();
+        for (symbol, result) in symbols.iter().zip(results) {
+            match result {
+                Ok((quote, salvaged)) => {
+                    if salvaged {
+                        batch.warnings.push(format!(
+                            "{}: response was cut short, showing what arrived",
+                            symbol
+                        ));
+                    }
+                    batch.quotes.push(quote);
+                }
+                Err(e) => batch.failures.push(FetchFailure {
+                    symbol: symbol.clone(),
+                    reason: format!("{:#}", e),
+                }),
+            }
+        }
 
-        Ok(quotes)
+        Ok(batch)
     }
 
-    /// Fetch a single quote from the v8 chart API.
-    async fn fetch_single_quote(&self, symbol: &str) -> Result<Quote> {
+    /// Fetch a single quote from the v8 chart API, and whether it had to be
+    /// salvaged from a truncated response.
+    async fn fetch_single_quote(&self, symbol: &str) -> Result<(Quote, bool)> {
         // Validate symbol before constructing URL to prevent injection
         if !is_valid_symbol(symbol) {
             anyhow::bail!("Invalid symbol: {}", symbol);
         }
 
-        // Symbol goes in the path, not as a query parameter
-        let url = format!("{}/{}?interval=1d&range=1d", YAHOO_CHART_URL, symbol);
+        let crumb = self.crumb.lock().await.clone();
+        let mut response = self.send_chart_request(symbol, crumb.as_deref()).await?;
 
-        let response = self
-            .client
-            .get(&url)
-            .timeout(self.timeout)
-            .send()
-            .await
-            .with_context(|| format!("Failed to fetch quote for {}", symbol))?;
+        // No (or a stale) crumb: get a fresh session and try once more
+        if response.status() == StatusCode::UNAUTHORIZED {
+            let crumb = self.refresh_crumb(crumb).await?;
+            response = self.send_chart_request(symbol, Some(&crumb)).await?;
+        }
 
         if !response.status().is_success() {
             anyhow::bail!(
@@ -95,30 +229,115 @@ impl YahooFinanceClient {
             );
         }
 
-        let data: ChartResponse = response
-            .json()
+        let body = response
+            .bytes()
             .await
+            .with_context(|| format!("Failed to read response for {}", symbol))?;
+        let (chart, salvaged) = parse_chart_body(&body)
             .with_context(|| format!("Failed to parse response for {}", symbol))?;
 
         // Check for API errors
-        if let Some(error) = data.chart.error {
+        if let Some(error) = chart.error {
             anyhow::bail!("Yahoo Finance error for {}: {}", symbol, error.description);
         }
 
-        let result = data
-            .chart
+        let result = chart
             .result
             .and_then(|r| r.into_iter().next())
             .ok_or_else(|| anyhow::anyhow!("No data returned for {}", symbol))?;
 
-        Ok(result.into_quote())
+        Ok((result.into_quote(), salvaged))
+    }
+
+    /// Send the chart request for a symbol, with a crumb if there is one.
+    async fn send_chart_request(
+        &self,
+        symbol: &str,
+        crumb: Option<&str>,
+    ) -> Result<reqwest::Response> {
+        // Symbol goes in the path, not as a query parameter
+        let url = format!(
+            "{}/{}",
+            self.options.chart_url.trim_end_matches('/'),
+            encode_path_segment(symbol)
+        );
+
+        // One daily bar is all the meta needs; the extended-hours price
+        // takes intraday bars with the pre/post sessions
+        let series: &[(&str, &str)] = if self.options.extended_hours {
+            &[("interval", "5m"), ("includePrePost", "true")]
+        } else {
+            &[("interval", "1d")]
+        };
+        let mut request = self
+            .client
+            .get(&url)
+            .query(series)
+            .query(&[
+                ("range", "1d"),
+                ("region", self.options.region.as_str()),
+                ("lang", self.options.lang.as_str()),
+            ])
+            .timeout(self.timeout);
+        if let Some(crumb) = crumb {
+            request = request.query(&[("crumb", crumb)]);
+        }
+
+        request
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch quote for {}", symbol))
+    }
+
+    /// Replace `stale`, the crumb that just got a 401, with a fresh one:
+    /// pick up a session cookie, then exchange it for a crumb and cache it.
+    /// Single-flight: whoever waited on the lock while another request did
+    /// the handshake takes its crumb instead of starting one of their own.
+    async fn refresh_crumb(&self, stale: Option<String>) -> Result<String> {
+        let mut cached = self.crumb.lock().await;
+        if let Some(fresh) = cached.as_ref().filter(|c| Some(*c) != stale.as_ref()) {
+            return Ok(fresh.clone());
+        }
+
+        // The cookie page answers 404 more often than not; the cookie is what matters
+        self.client
+            .get(&self.options.cookie_url)
+            .timeout(self.timeout)
+            .send()
+            .await
+            .context("Failed to fetch Yahoo session cookie")?;
+
+        let response = self
+            .client
+            .get(&self.options.crumb_url)
+            .timeout(self.timeout)
+            .send()
+            .await
+            .context("Failed to fetch Yahoo crumb")?;
+        if !response.status().is_success() {
+            anyhow::bail!("Yahoo crumb endpoint returned {}", response.status());
+        }
+
+        let crumb = response
+            .text()
+            .await
+            .context("Failed to read Yahoo crumb")?
+            .trim()
+            .to_string();
+        // A consent page instead of a token means the cookie didn't take
+        if crumb.is_empty() || crumb.contains('<') {
+            anyhow::bail!("Yahoo returned an invalid crumb");
+        }
+
+        *cached = Some(crumb.clone());
+        Ok(crumb)
     }
 
     /// Fetch a single quote.
     /// For when you only need to be disappointed by one stock at a time.
     #[allow(dead_code)] // Reserved for future regret-checking functionality
     pub async fn get_quote(&self, symbol: &str) -> Result<Quote> {
-        self.fetch_single_quote(symbol).await
+        Ok(self.fetch_single_quote(symbol).await?.0)
     }
 }
 
@@ -152,6 +371,24 @@ struct ChartError {
 #[serde(rename_all = "camelCase")]
 struct ChartResult {
     meta: ChartMeta,
+    #[serde(default)]
+    timestamp: Vec<i64>,
+    #[serde(default)]
+    indicators: Option<Indicators>,
+}
+
+/// The price series alongside the bar timestamps.
+#[derive(Debug, Deserialize)]
+struct Indicators {
+    #[serde(default)]
+    quote: Vec<IndicatorQuote>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndicatorQuote {
+    /// Null for bars with no trades
+    #[serde(default)]
+    close: Vec<Option<f64>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -186,10 +423,76 @@ struct ChartMeta {
     instrument_type: Option<String>,
     #[serde(default)]
     regular_market_time: Option<i64>,
+    #[serde(default)]
+    current_trading_period: Option<TradingPeriods>,
+}
+
+/// Today's pre/regular/post session windows as reported by Yahoo.
+#[derive(Debug, Deserialize)]
+struct TradingPeriods {
+    pre: Option<TradingPeriod>,
+    regular: Option<TradingPeriod>,
+    post: Option<TradingPeriod>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TradingPeriod {
+    start: i64,
+    end: i64,
+}
+
+impl TradingPeriod {
+    fn contains(&self, timestamp: i64) -> bool {
+        (self.start..self.end).contains(&timestamp)
+    }
+}
+
+impl TradingPeriods {
+    /// Work out which session a timestamp falls in.
+    fn market_state_at(&self, timestamp: i64) -> MarketState {
+        let within =
+            |period: &Option<TradingPeriod>| period.as_ref().is_some_and(|p| p.contains(timestamp));
+
+        if within(&self.regular) {
+            MarketState::Regular
+        } else if within(&self.pre) {
+            MarketState::Pre
+        } else if within(&self.post) {
+            MarketState::Post
+        } else {
+            MarketState::Closed
+        }
+    }
 }
 
 impl ChartResult {
+    /// Last traded price in the pre- or post-market session we're in: the
+    /// close of the latest bar inside that session's window that had a trade.
+    fn extended_price(&self, market_state: MarketState) -> Option<f64> {
+        let periods = self.meta.current_trading_period.as_ref()?;
+        let session = match market_state {
+            MarketState::Pre => periods.pre.as_ref()?,
+            MarketState::Post => periods.post.as_ref()?,
+            MarketState::Regular | MarketState::Closed => return None,
+        };
+        let closes = &self.indicators.as_ref()?.quote.first()?.close;
+        self.timestamp
+            .iter()
+            .zip(closes)
+            .rev()
+            .filter(|(t, _)| session.contains(**t))
+            .filter_map(|(_, close)| *close)
+            .find(|close| close.is_finite() && *close > 0.0)
+    }
+
     fn into_quote(self) -> Quote {
+        let market_state = self
+            .meta
+            .current_trading_period
+            .as_ref()
+            .map(|p| p.market_state_at(Utc::now().timestamp()))
+            .unwrap_or(MarketState::Closed);
+        let extended_price = self.extended_price(market_state);
         let meta = self.meta;
         let prev_close = meta
             .chart_previous_close
@@ -203,6 +506,13 @@ impl ChartResult {
             0.0
         };
 
+        // Yahoo leaves exchangeName blank for some listings; fall back to the suffix
+        let exchange = meta
+            .exchange_name
+            .filter(|e| !e.is_empty())
+            .or_else(|| exchange_label(&meta.symbol).map(str::to_string))
+            .unwrap_or_default();
+
         Quote {
             symbol: meta.symbol,
             name: meta
@@ -221,18 +531,70 @@ impl ChartResult {
             volume: meta.regular_market_volume.unwrap_or(0),
             avg_volume: 0,    // Not available in chart API meta
             market_cap: None, // Not available in chart API meta
+            bid: None,        // Not available in chart API meta
+            ask: None,        // Not available in chart API meta
             currency: meta.currency.unwrap_or_else(|| "USD".to_string()),
-            exchange: meta.exchange_name.unwrap_or_default(),
+            exchange,
             quote_type: parse_quote_type(meta.instrument_type.as_deref()),
-            market_state: MarketState::Closed, // Would need separate call to determine
+            market_state,
+            extended_price,
+            // Malformed data shows up as 0 or out-of-range times; fall back to now
             timestamp: meta
                 .regular_market_time
+                .filter(|&t| t > 0)
                 .and_then(|t| Utc.timestamp_opt(t, 0).single())
                 .unwrap_or_else(Utc::now),
+            synthetic: false,
         }
     }
 }
 
+/// Parse a chart response body. When the body is cut off, every `meta`
+/// object that arrived whole is salvaged (it comes before the bulky price
+/// series) and the flag says so. Only a body with nothing to salvage is an error.
+fn parse_chart_body(body: &[u8]) -> serde_json::Result<(ChartData, bool)> {
+    let error = match serde_json::from_slice::<ChartResponse>(body) {
+        Ok(response) => return Ok((response.chart, false)),
+        Err(error) => error,
+    };
+    let salvaged = salvage_chart_results(body);
+    if salvaged.is_empty() {
+        return Err(error);
+    }
+    let chart = ChartData {
+        result: Some(salvaged),
+        error: None,
+    };
+    Ok((chart, true))
+}
+
+/// Pull each complete `"meta": {...}` out of a malformed body, in order,
+/// stopping at the first one that's incomplete.
+fn salvage_chart_results(body: &[u8]) -> Vec<ChartResult> {
+    const KEY: &[u8] = b"\"meta\"";
+
+    let mut results = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.windows(KEY.len()).position(|w| w == KEY) {
+        rest = rest[start + KEY.len()..].trim_ascii_start();
+        let Some(value) = rest.strip_prefix(b":") else {
+            continue;
+        };
+        let mut stream = serde_json::Deserializer::from_slice(value).into_iter::<ChartMeta>();
+        match stream.next() {
+            // The price series is what got cut off, so no extended-hours price
+            Some(Ok(meta)) => results.push(ChartResult {
+                meta,
+                timestamp: Vec::new(),
+                indicators: None,
+            }),
+            _ => break,
+        }
+        rest = &value[stream.byte_offset()..];
+    }
+    results
+}
+
 fn parse_quote_type(s: Option<&str>) -> QuoteType {
     match s {
         Some("EQUITY") => QuoteType::Equity,
@@ -247,9 +609,88 @@ fn parse_quote_type(s: Option<&str>) -> QuoteType {
     }
 }
 
+/// Yahoo exchange suffixes and the venues they stand for.
+/// The part after the dot in `BMW.DE` or `7203.T`.
+const EXCHANGE_SUFFIXES: &[(&str, &str)] = &[
+    ("AS", "Amsterdam"),
+    ("AX", "ASX"),
+    ("BO", "BSE"),
+    ("BR", "Brussels"),
+    ("CO", "Copenhagen"),
+    ("DE", "XETRA"),
+    ("F", "Frankfurt"),
+    ("HE", "Helsinki"),
+    ("HK", "Hong Kong"),
+    ("IR", "Dublin"),
+    ("JK", "Jakarta"),
+    ("KS", "KOSPI"),
+    ("L", "London"),
+    ("LS", "Lisbon"),
+    ("MC", "Madrid"),
+    ("MI", "Milan"),
+    ("MX", "Mexico"),
+    ("NS", "NSE"),
+    ("NZ", "NZX"),
+    ("OL", "Oslo"),
+    ("PA", "Paris"),
+    ("SA", "Sao Paulo"),
+    ("SI", "Singapore"),
+    ("SS", "Shanghai"),
+    ("ST", "Stockholm"),
+    ("SW", "SIX Swiss"),
+    ("SZ", "Shenzhen"),
+    ("T", "Tokyo"),
+    ("TO", "Toronto"),
+    ("TW", "Taiwan"),
+    ("V", "TSX Venture"),
+    ("VI", "Vienna"),
+];
+
+/// Get the exchange suffix of an international ticker (e.g. "DE" for "BMW.DE").
+/// Share classes like `BRK.B` are not exchanges, so only known suffixes count.
+pub fn exchange_suffix(symbol: &str) -> Option<&str> {
+    let (_, suffix) = symbol.rsplit_once('.')?;
+    EXCHANGE_SUFFIXES
+        .iter()
+        .any(|(s, _)| s.eq_ignore_ascii_case(suffix))
+        .then_some(suffix)
+}
+
+/// Get the base asset of a pair (e.g. "BTC" for "BTC-USD").
+/// Plain tickers are their own base.
+pub fn base_asset(symbol: &str) -> &str {
+    symbol.split_once('-').map_or(symbol, |(base, _)| base)
+}
+
+/// Get a human readable exchange label from a ticker's suffix.
+pub fn exchange_label(symbol: &str) -> Option<&'static str> {
+    let suffix = exchange_suffix(symbol)?;
+    EXCHANGE_SUFFIXES
+        .iter()
+        .find(|(s, _)| s.eq_ignore_ascii_case(suffix))
+        .map(|(_, label)| *label)
+}
+
 /// Symbol shortcuts for common cryptocurrencies.
 /// Because typing "-USD" is too much work for crypto bros.
+///
+/// Symbols are normalized to Yahoo's canonical uppercase form first, so
+/// `aapl`, `btc.x` and `bmw.de` behave like their uppercase spellings.
+/// Pairs like `eth-btc` are left exactly as written; `App::new` dedups
+/// them against other spellings without caring about case.
 pub fn expand_symbol(symbol: &str) -> String {
+    let symbol = symbol.trim();
+    if symbol.contains('-') {
+        return symbol.to_string();
+    }
+    let symbol = symbol.to_ascii_uppercase();
+    let symbol = symbol.as_str();
+
+    // International tickers are already fully qualified - SOL.DE is not Solana
+    if exchange_suffix(symbol).is_some() {
+        return symbol.to_string();
+    }
+
     // Handle shorthand crypto symbols like "BTC.X" -> "BTC-USD"
     // The .X suffix is like X marks the spot, but for losing money
     if let Some(base) = symbol.strip_suffix(".X") {
@@ -275,7 +716,7 @@ pub fn expand_symbol(symbol: &str) -> String {
     .into_iter()
     .collect();
 
-    // Only expand if it looks like a crypto symbol (all caps, short)
+    // Only expand if it looks like a crypto symbol (letters only, short)
     if symbol.len() <= 5 && symbol.chars().all(|c| c.is_ascii_uppercase()) {
         if let Some(expanded) = shortcuts.get(symbol) {
             return expanded.to_string();
@@ -325,6 +766,21 @@ mod tests {
         assert!(is_valid_symbol("9988")); // Alibaba on HKEX
     }
 
+    #[test]
+    fn test_valid_symbol_fx_and_futures() {
+        assert!(is_valid_symbol("EURUSD=X"));
+        assert!(is_valid_symbol("GC=F"));
+    }
+
+    #[test]
+    fn test_encode_path_segment() {
+        assert_eq!(encode_path_segment("AAPL"), "AAPL");
+        assert_eq!(encode_path_segment("BRK-B"), "BRK-B");
+        assert_eq!(encode_path_segment("BMW.DE"), "BMW.DE");
+        assert_eq!(encode_path_segment("^GSPC"), "%5EGSPC");
+        assert_eq!(encode_path_segment("EURUSD=X"), "EURUSD%3DX");
+    }
+
     #[test]
     fn test_valid_symbol_max_length() {
         assert!(is_valid_symbol("ABCDEFGHIJKLMNOPQRST")); // exactly 20
@@ -394,4 +850,510 @@ mod tests {
         assert_eq!(expand_symbol("AAPL"), "AAPL");
         assert_eq!(expand_symbol("GOOGL"), "GOOGL");
     }
+
+    #[test]
+    fn test_expand_symbol_normalizes_case() {
+        assert_eq!(expand_symbol("aapl"), "AAPL");
+        assert_eq!(expand_symbol("Msft"), "MSFT");
+        assert_eq!(expand_symbol("btc.x"), "BTC-USD");
+        assert_eq!(expand_symbol("bmw.de"), "BMW.DE");
+        assert_eq!(expand_symbol("^gspc"), "^GSPC");
+    }
+
+    #[test]
+    fn test_expand_symbol_preserves_crypto_pairs() {
+        assert_eq!(expand_symbol("BTC-EUR"), "BTC-EUR");
+        assert_eq!(expand_symbol("eth-btc"), "eth-btc");
+        assert_eq!(expand_symbol(" Eth-Btc "), "Eth-Btc");
+        // A pair is never re-expanded into a USD pair
+        assert_eq!(expand_symbol("ETH-BTC"), "ETH-BTC");
+    }
+
+    // --- request tests against a mock server ---
+
+    fn chart_body(symbol: &str, price: f64) -> serde_json::Value {
+        serde_json::json!({
+            "chart": {
+                "result": [{
+                    "meta": {
+                        "symbol": symbol,
+                        "regularMarketPrice": price,
+                        "chartPreviousClose": price - 1.0
+                    }
+                }],
+                "error": null
+            }
+        })
+    }
+
+    #[test]
+    fn test_truncated_body_salvages_complete_entries() {
+        let full = serde_json::json!({
+            "chart": {
+                "result": [{
+                    "meta": {"symbol": "AAPL", "regularMarketPrice": 150.0},
+                    "timestamp": [1, 2, 3],
+                    "indicators": {"quote": [{"close": [149.0, 150.0, 151.0]}]}
+                }, {
+                    "meta": {"symbol": "MSFT", "regularMarketPrice": 300.0},
+                    "timestamp": [1, 2, 3]
+                }],
+                "error": null
+            }
+        })
+        .to_string();
+
+        let (chart, salvaged) = parse_chart_body(full.as_bytes()).unwrap();
+        assert!(!salvaged);
+        assert_eq!(chart.result.unwrap().len(), 2);
+
+        // Cut off in the middle of the second entry's price series
+        let cut = &full[..full.find("\"timestamp\":[1,2,3]}]").unwrap() + 15];
+        let (chart, salvaged) = parse_chart_body(cut.as_bytes()).unwrap();
+        assert!(salvaged);
+        let symbols: Vec<String> = chart
+            .result
+            .unwrap()
+            .into_iter()
+            .map(|r| r.meta.symbol)
+            .collect();
+        assert_eq!(symbols, vec!["AAPL", "MSFT"]);
+
+        // Cut off inside the second meta: only the first survives
+        let cut = &full[..full.find("300.0").unwrap()];
+        let (chart, _) = parse_chart_body(cut.as_bytes()).unwrap();
+        assert_eq!(chart.result.unwrap().len(), 1);
+
+        // Nothing whole at all is still an error
+        assert!(parse_chart_body(&full.as_bytes()[..30]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_truncated_response_is_a_warning_not_a_failure() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let body = chart_body("AAPL", 10.0).to_string();
+        // Lose the closing brackets after the meta
+        let cut = &body[..body.len() - 3];
+        Mock::given(path("/v8/finance/chart/AAPL"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(cut))
+            .mount(&server)
+            .await;
+
+        let batch = mock_client(&server)
+            .get_quotes(&["AAPL".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(batch.quotes[0].price, 10.0);
+        assert!(batch.failures.is_empty());
+        assert!(batch.warnings[0].starts_with("AAPL"));
+    }
+
+    fn mock_client(server: &wiremock::MockServer) -> YahooFinanceClient {
+        YahooFinanceClient::with_options(ClientOptions {
+            chart_url: format!("{}/v8/finance/chart", server.uri()),
+            cookie_url: format!("{}/cookie", server.uri()),
+            crumb_url: format!("{}/getcrumb", server.uri()),
+            ..ClientOptions::with_timeout(5)
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_symbols_are_encoded_in_requests() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        for (symbol, encoded) in [("^GSPC", "%5EGSPC"), ("EURUSD=X", "EURUSD%3DX")] {
+            Mock::given(method("GET"))
+                .and(path(format!("/v8/finance/chart/{}", encoded)))
+                .and(query_param("interval", "1d"))
+                .and(query_param("range", "1d"))
+                .and(query_param("region", "US"))
+                .and(query_param("lang", "en-US"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(chart_body(symbol, 10.0)))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let client = mock_client(&server);
+        let symbols = vec!["^GSPC".to_string(), "EURUSD=X".to_string()];
+        let batch = client.get_quotes(&symbols).await.unwrap();
+        assert!(batch.failures.is_empty());
+
+        let mut fetched: Vec<&str> = batch.quotes.iter().map(|q| q.symbol.as_str()).collect();
+        fetched.sort();
+        assert_eq!(fetched, vec!["EURUSD=X", "^GSPC"]);
+    }
+
+    #[tokio::test]
+    async fn test_region_and_lang_are_sent() {
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/v8/finance/chart/SAP.DE"))
+            .and(query_param("region", "DE"))
+            .and(query_param("lang", "de-DE"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chart_body("SAP.DE", 120.0)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = YahooFinanceClient::with_options(ClientOptions {
+            chart_url: format!("{}/v8/finance/chart", server.uri()),
+            cookie_url: format!("{}/cookie", server.uri()),
+            crumb_url: format!("{}/getcrumb", server.uri()),
+            region: "DE".to_string(),
+            lang: "de-DE".to_string(),
+            ..ClientOptions::with_timeout(5)
+        })
+        .unwrap();
+        let quote = client.get_quote("SAP.DE").await.unwrap();
+        assert_eq!(quote.price, 120.0);
+    }
+
+    #[tokio::test]
+    async fn test_intraday_series_only_with_extended_hours() {
+        use wiremock::matchers::{path, query_param, query_param_is_missing};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/v8/finance/chart/AAPL"))
+            .and(query_param("interval", "1d"))
+            .and(query_param_is_missing("includePrePost"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chart_body("AAPL", 10.0)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/v8/finance/chart/MSFT"))
+            .and(query_param("interval", "5m"))
+            .and(query_param("includePrePost", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chart_body("MSFT", 10.0)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        mock_client(&server).get_quote("AAPL").await.unwrap();
+        let extended = YahooFinanceClient::with_options(ClientOptions {
+            chart_url: format!("{}/v8/finance/chart", server.uri()),
+            extended_hours: true,
+            ..ClientOptions::with_timeout(5)
+        })
+        .unwrap();
+        extended.get_quote("MSFT").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_symbols_are_reported() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/v8/finance/chart/AAPL"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chart_body("AAPL", 10.0)))
+            .mount(&server)
+            .await;
+        Mock::given(path("/v8/finance/chart/NOPE"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let symbols = vec!["AAPL".to_string(), "NOPE".to_string()];
+        let batch = mock_client(&server).get_quotes(&symbols).await.unwrap();
+
+        assert_eq!(batch.quotes.len(), 1);
+        assert_eq!(batch.failures.len(), 1);
+        assert_eq!(batch.failures[0].symbol, "NOPE");
+        assert!(batch.failures[0].reason.contains("404"));
+    }
+
+    #[tokio::test]
+    async fn test_crumb_is_refreshed_on_401() {
+        use wiremock::matchers::{header_exists, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        // Session cookie, then a crumb only for requests carrying it
+        Mock::given(path("/cookie"))
+            .respond_with(
+                ResponseTemplate::new(404).insert_header("set-cookie", "A3=session; Path=/"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/getcrumb"))
+            .and(header_exists("cookie"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("abc123"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // Chart data with the crumb, 401 without it
+        Mock::given(method("GET"))
+            .and(path("/v8/finance/chart/AAPL"))
+            .and(query_param("crumb", "abc123"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chart_body("AAPL", 10.0)))
+            .with_priority(1)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v8/finance/chart/AAPL"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("Invalid Crumb"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = mock_client(&server);
+        assert_eq!(client.get_quote("AAPL").await.unwrap().price, 10.0);
+        // The crumb is cached; no second handshake
+        assert_eq!(client.get_quote("AAPL").await.unwrap().price, 10.0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_401s_share_one_crumb_handshake() {
+        use wiremock::matchers::{method, path, path_regex, query_param};
+        use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/cookie"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/getcrumb"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("abc123")
+                    .set_delay(std::time::Duration::from_millis(50)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex("^/v8/finance/chart/"))
+            .and(query_param("crumb", "abc123"))
+            .respond_with(|req: &Request| {
+                let symbol = req.url.path().rsplit('/').next().unwrap().to_string();
+                ResponseTemplate::new(200).set_body_json(chart_body(&symbol, 10.0))
+            })
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex("^/v8/finance/chart/"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let symbols: Vec<String> = ["AAPL", "MSFT", "GOOGL", "AMZN", "NVDA"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let batch = mock_client(&server).get_quotes(&symbols).await.unwrap();
+        assert_eq!(batch.quotes.len(), 5, "{:?}", batch.failures);
+        // The .expect(1)s above check there was exactly one handshake
+    }
+
+    #[tokio::test]
+    async fn test_bad_crumb_is_an_error() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/getcrumb"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>consent</html>"))
+            .mount(&server)
+            .await;
+        Mock::given(path("/v8/finance/chart/AAPL"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let err = mock_client(&server).get_quote("AAPL").await.unwrap_err();
+        assert!(err.to_string().contains("invalid crumb"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_and_browser_headers_are_sent() {
+        use wiremock::matchers::{header, header_exists, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("user-agent", "stonktop-test/1.0"))
+            .and(header_exists("accept"))
+            .and(header_exists("accept-language"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chart_body("AAPL", 10.0)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = mock_client(&server)
+            .with_user_agent("stonktop-test/1.0")
+            .unwrap();
+        let quote = client.get_quote("AAPL").await.unwrap();
+        assert_eq!(quote.symbol, "AAPL");
+    }
+
+    #[test]
+    fn test_default_user_agent_is_kept() {
+        assert_eq!(ClientOptions::with_timeout(5).user_agent, USER_AGENT);
+    }
+
+    // --- timestamp edge cases ---
+
+    fn quote_with_time(time: i64) -> Quote {
+        let result: ChartResult = serde_json::from_value(serde_json::json!({
+            "meta": {"symbol": "AAPL", "regularMarketPrice": 1.0, "regularMarketTime": time}
+        }))
+        .unwrap();
+        result.into_quote()
+    }
+
+    #[test]
+    fn test_absurd_timestamps_fall_back_to_now() {
+        let before = Utc::now();
+        for time in [i64::MAX, i64::MIN, 0, -5] {
+            let quote = quote_with_time(time);
+            assert!(quote.timestamp >= before, "{} -> {}", time, quote.timestamp);
+            assert!(quote.timestamp <= Utc::now());
+        }
+    }
+
+    #[test]
+    fn test_valid_timestamp_is_kept() {
+        let quote = quote_with_time(1_700_000_000);
+        assert_eq!(quote.timestamp.timestamp(), 1_700_000_000);
+    }
+
+    // --- client options tests ---
+
+    #[tokio::test]
+    async fn test_slow_responses_hit_the_configured_timeout() {
+        use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(chart_body("AAPL", 10.0))
+                    .set_delay(Duration::from_secs(3)),
+            )
+            .mount(&server)
+            .await;
+
+        let client = YahooFinanceClient::with_options(ClientOptions {
+            chart_url: format!("{}/v8/finance/chart", server.uri()),
+            pool_idle_timeout: Some(Duration::from_secs(30)),
+            pool_max_idle_per_host: Some(4),
+            ..ClientOptions::with_timeout(1)
+        })
+        .unwrap();
+
+        let error = client.get_quote("AAPL").await.unwrap_err();
+        let timed_out = error.chain().any(|e| {
+            e.downcast_ref::<reqwest::Error>()
+                .is_some_and(|e| e.is_timeout())
+        });
+        assert!(timed_out, "{:#}", error);
+    }
+
+    // --- market state tests ---
+
+    #[test]
+    fn test_market_state_from_trading_periods() {
+        let periods: TradingPeriods = serde_json::from_value(serde_json::json!({
+            "pre": { "start": 100, "end": 200 },
+            "regular": { "start": 200, "end": 300 },
+            "post": { "start": 300, "end": 400 }
+        }))
+        .unwrap();
+
+        assert_eq!(periods.market_state_at(150), MarketState::Pre);
+        assert_eq!(periods.market_state_at(200), MarketState::Regular);
+        assert_eq!(periods.market_state_at(350), MarketState::Post);
+        assert_eq!(periods.market_state_at(50), MarketState::Closed);
+        assert_eq!(periods.market_state_at(400), MarketState::Closed);
+    }
+
+    #[test]
+    fn test_extended_price_from_session_bars() {
+        let meta = serde_json::json!({
+            "symbol": "AAPL",
+            "regularMarketPrice": 150.0,
+            "currentTradingPeriod": {
+                "pre": { "start": 100, "end": 200 },
+                "regular": { "start": 200, "end": 300 },
+                "post": { "start": 300, "end": 400 }
+            }
+        });
+        let result: ChartResult = serde_json::from_value(serde_json::json!({
+            "meta": meta,
+            "timestamp": [100, 150, 180, 200, 250, 300, 330, 360],
+            "indicators": {
+                "quote": [{
+                    "close": [148.0, 149.5, null, 150.5, 150.0, 151.0, 152.5, null]
+                }]
+            }
+        }))
+        .unwrap();
+
+        // Bars without a trade are skipped, and each session only looks at its own bars
+        assert_eq!(result.extended_price(MarketState::Pre), Some(149.5));
+        assert_eq!(result.extended_price(MarketState::Post), Some(152.5));
+        assert_eq!(result.extended_price(MarketState::Regular), None);
+        assert_eq!(result.extended_price(MarketState::Closed), None);
+
+        // No price series (a salvaged entry, say), no extended price
+        let bare: ChartResult =
+            serde_json::from_value(serde_json::json!({ "meta": meta })).unwrap();
+        assert_eq!(bare.extended_price(MarketState::Pre), None);
+    }
+
+    // --- exchange suffix tests ---
+
+    #[test]
+    fn test_exchange_suffix_maps_to_label() {
+        assert_eq!(exchange_suffix("BMW.DE"), Some("DE"));
+        assert_eq!(exchange_label("BMW.DE"), Some("XETRA"));
+        assert_eq!(exchange_label("7203.T"), Some("Tokyo"));
+    }
+
+    #[test]
+    fn test_base_asset() {
+        assert_eq!(base_asset("BTC-USD"), "BTC");
+        assert_eq!(base_asset("BTC-EUR"), "BTC");
+        assert_eq!(base_asset("ETH-BTC"), "ETH");
+        assert_eq!(base_asset("AAPL"), "AAPL");
+        assert_eq!(base_asset("BMW.DE"), "BMW.DE");
+        assert_eq!(base_asset("^GSPC"), "^GSPC");
+    }
+
+    #[test]
+    fn test_exchange_suffix_ignores_share_classes() {
+        assert_eq!(exchange_suffix("BRK.B"), None);
+        assert_eq!(exchange_suffix("AAPL"), None);
+        assert_eq!(exchange_label("BTC-USD"), None);
+    }
+
+    #[test]
+    fn test_expand_symbol_keeps_exchange_suffix() {
+        assert_eq!(expand_symbol("BMW.DE"), "BMW.DE");
+        // Not Solana on XETRA, just a ticker that happens to look like one
+        assert_eq!(expand_symbol("SOL.DE"), "SOL.DE");
+    }
+
+    #[test]
+    fn test_into_quote_falls_back_to_suffix_exchange() {
+        let result: ChartResult = serde_json::from_value(serde_json::json!({
+            "meta": { "symbol": "BMW.DE", "regularMarketPrice": 90.0 }
+        }))
+        .unwrap();
+        assert_eq!(result.into_quote().exchange, "XETRA");
+    }
 }
@@ -2,13 +2,63 @@
 //!
 //! Where we keep track of your hopes, dreams, and unrealized losses.
 
-use crate::api::{expand_symbol, YahooFinanceClient};
-use crate::cli::Args;
-use crate::config::Config;
-use crate::models::{Holding, Quote, SortDirection, SortOrder};
-use anyhow::Result;
-use std::collections::HashMap;
+use crate::action::Action;
+use crate::alerts::{self, AlertRule, PanicWatch};
+use crate::api::{base_asset, exchange_suffix, expand_symbol, ClientOptions, YahooFinanceClient};
+use crate::cli::{Args, ExportFormat, FilterType};
+use crate::clipboard::{Clipboard, SystemClipboard};
+use crate::config::{self, Config};
+use crate::history::{self, Snapshot, ValuePoint};
+use crate::models::{
+    annualized_return, reference_change_percent, Breadth, ColorBasis, FetchFailure, Holding,
+    InitialOrder, PrimaryChange, Quote, QuoteBatch, QuoteType, Rounding, SortDirection, SortOrder,
+    View, Weighting,
+};
+use crate::notify::{DesktopNotifier, MoveWatcher, Notifier, QuietHours};
+use crate::provider::{FixtureProvider, QuoteProvider};
+use crate::synthetic::Synthetic;
+use anyhow::{Context, Result};
+use chrono::{Local, Utc};
+use clap::ValueEnum;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// More decimals than this is just noise, even for stablecoins.
+const MAX_PERCENT_PRECISION: usize = 6;
+
+/// Jitter beyond ±50% stops being "spread out" and starts being "random".
+const MAX_REFRESH_JITTER: f64 = 0.5;
+
+/// Refreshing faster than once a second mostly refreshes the rate limiter.
+const MIN_REFRESH_SECS: f64 = 1.0;
+
+/// Slowest the refresh keys will go: past ten minutes it's not a ticker.
+pub const MAX_REFRESH_SECS: f64 = 600.0;
+
+/// Prices kept per symbol for the detail view's indicators: twice the
+/// longest lookback (SMA 50), so the MACD's EMAs have time to settle.
+const PRICE_HISTORY_LEN: usize = 100;
+
+/// Where the composite's readings go in `price_history`. Not a symbol
+/// Yahoo would ever hand back.
+pub const COMPOSITE_KEY: &str = "@composite";
+
+/// Autoscroll faster than this is a slot machine, not a ticker.
+const MIN_AUTOSCROLL_SECS: f64 = 1.0;
+
+/// Tick rate limits: below 50ms it's all CPU, above a second keys feel stuck.
+const MIN_TICK_MS: u64 = 50;
+const MAX_TICK_MS: u64 = 1000;
+
+/// How long each spinner frame shows while a fetch is in flight.
+const SPINNER_STEP: Duration = Duration::from_millis(100);
+
+/// Name column limits: narrower is unreadable, wider crowds out the numbers.
+const MIN_NAME_WIDTH: usize = 8;
+const MAX_NAME_WIDTH: usize = 60;
 
 /// Application state.
 /// Think of it as your financial life, but with better error handling.
@@ -19,16 +69,49 @@ pub struct App {
     pub holdings: HashMap<String, Holding>,
     /// Symbols being watched
     pub symbols: Vec<String>,
-    /// API client
-    client: YahooFinanceClient,
+    /// Symbols fetched for internal use (like the benchmark) but not listed
+    pub hidden_symbols: HashSet<String>,
+    /// Held symbols listed only in the holdings view (`include_holdings = false`)
+    pub holdings_only_symbols: HashSet<String>,
+    /// Benchmark for the RS column, when it's on
+    pub benchmark: Option<String>,
+    /// Ratio rows recomputed from their components on every refresh
+    pub synthetics: Vec<Synthetic>,
+    /// Where quotes come from (Yahoo, or a fixture file)
+    provider: Arc<dyn QuoteProvider>,
+    /// When the fetch in flight started, if any. Also drives the spinner
+    pub fetching_since: Option<Instant>,
+    /// Advance the selection this often, for unattended displays
+    pub autoscroll: Option<Duration>,
+    /// When autoscroll last moved the selection (or started)
+    last_autoscroll: Instant,
+    /// How long the event loop waits for a key before redrawing
+    pub tick_rate: Duration,
     /// Last refresh time
     pub last_refresh: Option<Instant>,
     /// Refresh interval
     pub refresh_interval: Duration,
+    /// Floor for the refresh interval, from `[general] min_refresh_interval`
+    min_refresh_interval: Duration,
+    /// The refresh interval was changed with `+`/`-` and can be saved
+    refresh_interval_changed: bool,
+    /// Random spread applied to each refresh delay (0.1 = ±10%)
+    pub refresh_jitter: f64,
+    /// Jittered delay until the refresh after `last_refresh`
+    pub next_refresh_delay: Duration,
+    /// Faster cadence for held symbols in the holdings view, if configured
+    pub holdings_refresh_interval: Option<Duration>,
+    /// Last refresh of the held symbols, full or holdings-only
+    pub last_holdings_refresh: Option<Instant>,
+    /// Symbols the fetch in flight asked for, when it wasn't all of them
+    fetch_scope: Option<Vec<String>>,
     /// Current sort order
     pub sort_order: SortOrder,
     /// Sort direction
     pub sort_direction: SortDirection,
+    /// The holdings view keeps its own sort, biggest positions first by default
+    pub holdings_sort_order: SortOrder,
+    pub holdings_sort_direction: SortDirection,
     /// Current iteration count
     pub iteration: u64,
     /// Maximum iterations (0 = infinite)
@@ -37,6 +120,19 @@ pub struct App {
     pub running: bool,
     /// Error message to display
     pub error: Option<String>,
+    /// Set once refreshes have failed for long enough to call it an outage.
+    /// Unlike `error`, a key press doesn't clear it; only a good refresh does.
+    pub offline: Option<String>,
+    /// Refreshes in a row where nothing came back
+    failed_refreshes: u32,
+    /// How many of those before the error gets loud (0 = never)
+    max_failed_refreshes: u32,
+    /// Refreshes in a row each symbol has been missing from the batch
+    pub missing_streak: HashMap<String, u32>,
+    /// How long a streak before the symbol counts as having no data (0 = never)
+    no_data_after: u32,
+    /// Latest quote that came back for each symbol, stood in for it when it goes missing
+    last_known: HashMap<String, Quote>,
     /// Selected row index
     pub selected: usize,
     /// Scroll offset for when you have more regrets than fit on screen
@@ -44,35 +140,197 @@ pub struct App {
     pub scroll_offset: usize,
     /// Show help overlay
     pub show_help: bool,
+    /// Show the detail view for the selected symbol
+    pub show_detail: bool,
     /// Show holdings view
     pub show_holdings: bool,
     /// Show fundamentals
     pub show_fundamentals: bool,
     /// Batch mode (non-interactive)
     pub batch_mode: bool,
+    /// Batch output format, when not the text table
+    pub export: Option<ExportFormat>,
+    /// Batch output is a single ticker line per refresh
+    pub ticker: bool,
+    /// Plain-text output may use ANSI colors (`--color`)
+    pub ansi_colors: bool,
+    /// Batch rows are only printed once they move this many percent
+    pub min_change_pct: Option<f64>,
+    /// Price of each symbol when its batch row was last printed
+    last_printed: HashMap<String, f64>,
     /// Secure mode (no interactive commands)
     pub secure_mode: bool,
     /// Active group index
     pub active_group: usize,
     /// Group names
     pub groups: Vec<String>,
+    /// Which group each symbol belongs to (the first by name, if several)
+    pub group_map: HashMap<String, String>,
+    /// Only show symbols on this exchange suffix ("US" for no suffix)
+    pub exchange_filter: Option<String>,
+    /// Only show this kind of instrument
+    pub type_filter: Option<FilterType>,
+    /// Only show symbols or names containing this (case-insensitive)
+    pub search_query: Option<String>,
+    /// Only show the first N rows (`--top`)
+    pub top: Option<usize>,
+    /// Weighting for the watchlist net change in the header
+    pub net_change_weighting: Weighting,
+    /// Weighting of the header's "my market" composite, when it's on
+    pub composite_index: Option<Weighting>,
+    /// Change column(s) the quotes table leads with
+    pub primary_change: PrimaryChange,
+    /// What a row's gain/loss color follows
+    pub color_basis: ColorBasis,
+    /// Decimal places for percentage columns
+    pub percent_precision: usize,
+    /// Rounding for monetary values (portfolio totals, P/L)
+    pub rounding: Rounding,
+    /// Blank lines between table rows (0 or 1)
+    pub row_spacing: u16,
+    /// Alternate row backgrounds
+    pub zebra: bool,
+    /// Pad prices so their decimal points line up
+    pub align_decimals: bool,
+    /// Column separators in the tables
+    pub grid: bool,
+    /// PREV column with the previous close
+    pub show_prev_close: bool,
+    /// Show the SESS% column (change since launch)
+    pub show_since_launch: bool,
+    /// Show the EXT% column while any row is in pre-market or after-hours
+    pub show_extended_hours: bool,
+    /// Report priceless quotes as failures instead of listing them
+    pub hide_invalid: bool,
+    /// Full grouped volume numbers instead of K/M/B suffixes (`#` flips it)
+    pub full_volume: bool,
+    /// Pairs in `currency` are shown without the suffix
+    pub compact_crypto: bool,
+    /// Display currency (`--currency`), uppercased
+    pub currency: String,
+    /// Unicode glyphs, or plain ASCII for terminals that can't cope
+    pub unicode: bool,
+    /// Direction glyphs in front of gain/loss cells
+    pub change_glyphs: bool,
+    /// Name column width in the main table
+    pub name_width: usize,
+    /// Total portfolio value over time, persisted across sessions
+    pub portfolio_value_history: Vec<ValuePoint>,
+    /// Maximum number of points kept in the portfolio value history
+    pub portfolio_history_len: usize,
+    /// One portfolio value per day, persisted for the net worth chart
+    pub portfolio_snapshots: Vec<Snapshot>,
+    /// Last price and volume seen per symbol
+    last_seen: HashMap<String, (f64, u64)>,
+    /// Symbols that moved on the last refresh and haven't been selected since
+    unseen_changes: HashSet<String>,
+    /// Symbols that failed to fetch on the last refresh
+    pub failures: Vec<FetchFailure>,
+    /// Warnings from the last refresh that still produced quotes
+    pub warnings: Vec<String>,
+    /// Per-symbol reference prices (e.g. entry) for the REF% column
+    pub reference_prices: HashMap<String, f64>,
+    /// Per-symbol notes for the detail view
+    pub notes: HashMap<String, String>,
+    /// Hex colors for symbol cells that should always stand out
+    pub symbol_colors: HashMap<String, String>,
+    /// Price of each symbol at its first successful fetch this session
+    pub session_open: HashMap<String, f64>,
+    /// Price of each symbol at every refresh this session, oldest first
+    pub price_history: HashMap<String, Vec<f64>>,
+    /// Symbols pinned to the top whatever the sort
+    pub favorites: HashSet<String>,
+    /// Favorites were toggled and need writing back to the config
+    favorites_changed: bool,
+    /// Interactive changes the config doesn't have yet
+    pub dirty: bool,
+    /// Ask before quitting with unsaved changes; off saves them without asking
+    pub confirm_quit: bool,
+    /// The user chose to write unsaved changes to the config on the way out
+    save_on_quit: bool,
+    /// Text prompt shown in the footer while typing
+    pub prompt: Option<Prompt>,
+    /// Watches for big moves worth a desktop notification
+    move_watcher: Option<MoveWatcher>,
+    /// Per-symbol alert rules from the config
+    alert_rules: Vec<AlertRule>,
+    /// Age past which a quote that fires an alert is tagged stale
+    alert_stale_after: Option<chrono::Duration>,
+    /// Rules (by index) matching at the last refresh
+    active_alerts: HashSet<usize>,
+    /// Watches the portfolio's day change for the full-screen warning
+    panic: Option<PanicWatch>,
+    /// Where notifications go
+    notifier: Box<dyn Notifier>,
+    /// Where `y` copies to
+    clipboard: Box<dyn Clipboard>,
+    /// When notifications are held back
+    quiet_hours: Option<QuietHours>,
     /// Verbose mode - for when you want MORE numbers to stress about
-    #[allow(dead_code)] // TODO: Add more verbosity, because anxiety needs details
     pub verbose: bool,
 }
 
 impl App {
     /// Create a new application from CLI args and config.
     pub fn new(args: &Args, config: &Config) -> Result<Self> {
-        // Merge symbols from args and config
+        // A fixture replaces the network entirely
+        let fixture = args
+            .fixture
+            .as_deref()
+            .map(FixtureProvider::load)
+            .transpose()?;
+
+        // Merge symbols from args and config, falling back to the fixture's own
         let mut symbols: Vec<String> = args.symbols.clone().unwrap_or_else(|| config.all_symbols());
+        if symbols.is_empty() {
+            if let Some(ref fixture) = fixture {
+                symbols = fixture.symbols();
+            }
+        }
 
         // Expand symbol shortcuts
         symbols = symbols.into_iter().map(|s| expand_symbol(&s)).collect();
 
-        // Remove duplicates while preserving order
+        // Remove duplicates while preserving order (aapl and AAPL are the same row)
         let mut seen = std::collections::HashSet::new();
-        symbols.retain(|s| seen.insert(s.clone()));
+        symbols.retain(|s| seen.insert(s.to_ascii_uppercase()));
+
+        // The benchmark rides along with the watchlist, out of sight unless watched
+        let benchmark = (args.relative_strength
+            || args.benchmark.is_some()
+            || config.display.relative_strength
+            || config.display.color_basis == ColorBasis::Benchmark)
+            .then(|| expand_symbol(args.benchmark.as_ref().unwrap_or(&config.display.benchmark)));
+        let mut hidden_symbols = HashSet::new();
+        if let Some(ref benchmark) = benchmark {
+            if !symbols.is_empty() && !seen.contains(&benchmark.to_ascii_uppercase()) {
+                symbols.push(benchmark.clone());
+                hidden_symbols.insert(benchmark.clone());
+            }
+        }
+
+        // Synthetic rows need their components fetched, listed or not
+        let synthetics = config.synthetics()?;
+        for synthetic in &synthetics {
+            for component in [&synthetic.numerator, &synthetic.denominator] {
+                if !symbols.iter().any(|s| s.eq_ignore_ascii_case(component)) {
+                    symbols.push(component.clone());
+                    hidden_symbols.insert(component.clone());
+                }
+            }
+        }
+
+        // Held but unlisted symbols stay out of the main view when asked to.
+        // An explicit -s list leaves holdings out altogether, so nothing to hide.
+        let holdings_only_symbols: HashSet<String> = if args.symbols.is_some() {
+            HashSet::new()
+        } else {
+            config
+                .holdings_only_symbols()
+                .iter()
+                .map(|s| expand_symbol(s))
+                .collect()
+        };
 
         // Build holdings map
         let holdings: HashMap<String, Holding> = config
@@ -83,78 +341,699 @@ impl App {
 
         // Get groups
         let groups: Vec<String> = config.groups.keys().cloned().collect();
+        let mut group_map = HashMap::new();
+        let mut named_groups: Vec<_> = config.groups.iter().collect();
+        named_groups.sort_by_key(|(name, _)| name.as_str());
+        for (name, members) in named_groups {
+            for symbol in members {
+                group_map
+                    .entry(expand_symbol(symbol))
+                    .or_insert_with(|| name.clone());
+            }
+        }
 
-        let client = YahooFinanceClient::new(args.timeout)?;
+        // The baseline every interactive sort falls back on for ties
+        match config.display.initial_order {
+            InitialOrder::AsListed => {}
+            InitialOrder::Alpha => symbols.sort(),
+            InitialOrder::Grouped => symbols.sort_by_key(|s| match group_map.get(s) {
+                Some(group) => (false, group.clone()),
+                None => (true, String::new()),
+            }),
+        }
+
+        let provider: Arc<dyn QuoteProvider> = match fixture {
+            Some(fixture) => Arc::new(fixture),
+            None => {
+                let mut client = YahooFinanceClient::with_options(ClientOptions {
+                    pool_idle_timeout: config.general.pool_idle_timeout.map(Duration::from_secs),
+                    pool_max_idle_per_host: config.general.pool_max_idle_per_host,
+                    region: config.general.region.clone(),
+                    lang: config.general.lang.clone(),
+                    extended_hours: args.extended_hours || config.display.extended_hours,
+                    ..ClientOptions::with_timeout(args.timeout)
+                })?;
+                if let Some(ua) = args
+                    .user_agent
+                    .as_ref()
+                    .or(config.general.user_agent.as_ref())
+                {
+                    client = client.with_user_agent(ua.clone())?;
+                }
+                Arc::new(client)
+            }
+        };
 
-        // Enforce minimum refresh interval of 1.0 second
-        let delay = if args.delay < 1.0 { 1.0 } else { args.delay };
+        // Enforce the minimum refresh interval, 1.0 second unless raised
+        let min_refresh = config.general.min_refresh_interval.max(MIN_REFRESH_SECS);
+        let delay = clamp_refresh(
+            "--delay",
+            args.delay.unwrap_or(config.general.refresh_interval),
+            min_refresh,
+        );
 
         Ok(Self {
             quotes: Vec::new(),
             holdings,
             symbols,
-            client,
+            hidden_symbols,
+            holdings_only_symbols,
+            synthetics,
+            benchmark,
+            provider,
+            fetching_since: None,
+            autoscroll: args
+                .autoscroll
+                .map(|secs| Duration::from_secs_f64(secs.max(MIN_AUTOSCROLL_SECS))),
+            last_autoscroll: Instant::now(),
+            tick_rate: Duration::from_millis(args.tick_ms.clamp(MIN_TICK_MS, MAX_TICK_MS)),
             last_refresh: None,
             refresh_interval: Duration::from_secs_f64(delay),
+            min_refresh_interval: Duration::from_secs_f64(min_refresh),
+            refresh_interval_changed: false,
+            refresh_jitter: config.general.refresh_jitter.clamp(0.0, MAX_REFRESH_JITTER),
+            next_refresh_delay: Duration::from_secs_f64(delay),
+            holdings_refresh_interval: config.general.holdings_refresh_interval.map(|secs| {
+                Duration::from_secs_f64(clamp_refresh(
+                    "holdings_refresh_interval",
+                    secs,
+                    min_refresh,
+                ))
+            }),
+            last_holdings_refresh: None,
+            fetch_scope: None,
             sort_order: args.sort.into(),
+            holdings_sort_order: SortOrder::Value,
+            holdings_sort_direction: SortDirection::Descending,
             sort_direction: if args.reverse {
                 SortDirection::Ascending
             } else {
                 SortDirection::Descending
             },
             iteration: 0,
-            max_iterations: args.iterations,
+            // An export is one document; looping forever would glue several together
+            max_iterations: match args.iterations {
+                0 if args.export.is_some() => 1,
+                n => n,
+            },
             running: true,
             error: None,
+            offline: None,
+            failed_refreshes: 0,
+            max_failed_refreshes: config.general.max_failed_refreshes,
+            missing_streak: HashMap::new(),
+            no_data_after: config.general.no_data_after,
+            last_known: HashMap::new(),
             selected: 0,
             scroll_offset: 0,
             show_help: false,
+            show_detail: false,
             show_holdings: args.holdings || config.display.show_holdings,
             show_fundamentals: config.display.show_fundamentals,
-            batch_mode: args.batch,
+            batch_mode: args.batch
+                || args.export.is_some()
+                || args.ticker
+                || args.min_change_pct.is_some(),
+            export: args.export,
+            ticker: args.ticker,
+            ansi_colors: args.use_colors(),
+            min_change_pct: args.min_change_pct.map(f64::abs),
+            last_printed: HashMap::new(),
             secure_mode: args.secure,
             active_group: 0,
             groups,
+            group_map,
+            exchange_filter: args.exchange.as_ref().map(|e| e.to_uppercase()),
+            type_filter: args.filter,
+            search_query: None,
+            top: args.top,
+            net_change_weighting: config.display.net_change_weighting,
+            composite_index: config.display.composite_index,
+            primary_change: config.display.primary_change,
+            color_basis: config.display.color_basis,
+            rounding: config.display.rounding,
+            percent_precision: args
+                .percent_precision
+                .unwrap_or(config.display.percent_precision)
+                .min(MAX_PERCENT_PRECISION),
+            row_spacing: args
+                .row_spacing
+                .unwrap_or(config.display.row_spacing)
+                .min(1),
+            zebra: args.zebra || config.display.zebra,
+            align_decimals: args.align_decimals || config.display.align_decimals,
+            grid: args.grid || config.display.grid,
+            show_prev_close: args.prev_close || config.display.prev_close,
+            show_since_launch: args.since_launch || config.display.since_launch,
+            show_extended_hours: args.extended_hours || config.display.extended_hours,
+            hide_invalid: args.hide_invalid || config.display.hide_invalid,
+            unicode: !args.ascii && config.display.unicode,
+            change_glyphs: args.glyphs || config.display.change_glyphs,
+            name_width: config
+                .display
+                .name_width
+                .clamp(MIN_NAME_WIDTH, MAX_NAME_WIDTH),
+            full_volume: args.full_volume || config.display.full_volume,
+            compact_crypto: args.compact_crypto || config.display.compact_crypto,
+            currency: args.currency.to_ascii_uppercase(),
+            portfolio_value_history: Vec::new(),
+            portfolio_history_len: config.display.portfolio_history_len,
+            portfolio_snapshots: Vec::new(),
+            last_seen: HashMap::new(),
+            unseen_changes: HashSet::new(),
+            failures: Vec::new(),
+            warnings: Vec::new(),
+            reference_prices: config
+                .watchlist
+                .reference_prices
+                .iter()
+                .map(|(symbol, price)| (expand_symbol(symbol), *price))
+                .collect(),
+            symbol_colors: config
+                .symbol_colors
+                .iter()
+                .map(|(symbol, color)| (expand_symbol(symbol), color.clone()))
+                .collect(),
+            notes: config
+                .notes
+                .iter()
+                .map(|note| (expand_symbol(&note.symbol), note.text.clone()))
+                .collect(),
+            session_open: HashMap::new(),
+            price_history: HashMap::new(),
+            favorites: config
+                .watchlist
+                .favorites
+                .iter()
+                .map(|s| expand_symbol(s))
+                .collect(),
+            favorites_changed: false,
+            dirty: false,
+            confirm_quit: config.general.confirm_quit,
+            save_on_quit: false,
+            prompt: None,
+            move_watcher: config.alerts.notify_on_move_pct.map(MoveWatcher::new),
+            alert_rules: config.alerts.rules.clone(),
+            alert_stale_after: config
+                .alerts
+                .stale_after
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .and_then(|age| chrono::Duration::from_std(age).ok()),
+            active_alerts: HashSet::new(),
+            panic: config.alerts.panic_pct.map(PanicWatch::new),
+            notifier: Box::new(DesktopNotifier),
+            clipboard: Box::new(SystemClipboard::default()),
+            quiet_hours: config.alerts.quiet_hours()?,
             verbose: args.verbose,
         })
     }
 
-    /// Check if refresh is needed.
+    /// Check if refresh is needed. Never while a fetch is already in flight.
     pub fn needs_refresh(&self) -> bool {
-        match self.last_refresh {
-            None => true,
-            Some(last) => last.elapsed() >= self.refresh_interval,
+        if self.is_fetching() {
+            return false;
+        }
+        match self.refresh_schedule() {
+            (None, _) => true,
+            (Some(last), delay) => last.elapsed() >= delay,
         }
     }
 
-    /// Refresh quotes from API.
+    /// Whether only held symbols are being refreshed: configured, in the
+    /// holdings view, and actually holding something on the watchlist.
+    fn holdings_fast_path(&self) -> bool {
+        self.holdings_refresh_interval.is_some()
+            && self.active_view() == View::Holdings
+            && self.symbols.iter().any(|s| self.holdings.contains_key(s))
+    }
+
+    /// Symbols the next refresh fetches: just the holdings on the fast
+    /// path, the whole watchlist otherwise.
+    pub fn active_symbols(&self) -> Vec<String> {
+        if self.holdings_fast_path() {
+            self.symbols
+                .iter()
+                .filter(|s| self.holdings.contains_key(*s))
+                .cloned()
+                .collect()
+        } else {
+            self.symbols.clone()
+        }
+    }
+
+    /// When the active schedule last ran, and the delay it waits between runs.
+    fn refresh_schedule(&self) -> (Option<Instant>, Duration) {
+        match self.holdings_refresh_interval {
+            Some(interval) if self.holdings_fast_path() => (self.last_holdings_refresh, interval),
+            _ => (self.last_refresh, self.next_refresh_delay),
+        }
+    }
+
+    /// Delay until the next refresh on the active schedule.
+    pub fn refresh_delay(&self) -> Duration {
+        self.refresh_schedule().1
+    }
+
+    /// Mark a fetch as started and return the symbols it should ask for.
+    fn begin_fetch(&mut self) -> Vec<String> {
+        let symbols = self.active_symbols();
+        self.fetch_scope = (symbols.len() < self.symbols.len()).then(|| symbols.clone());
+        self.fetching_since = Some(Instant::now());
+        symbols
+    }
+
+    /// Pick the delay until the next refresh, spread by the configured jitter
+    /// so instances started together don't hit Yahoo in synchronized bursts.
+    fn schedule_next_refresh(&mut self) {
+        self.next_refresh_delay =
+            jittered(self.refresh_interval, self.refresh_jitter, random_unit());
+    }
+
+    /// Halve (`faster`) or double the refresh interval, staying between the
+    /// configured minimum and [`MAX_REFRESH_SECS`]. The new interval counts
+    /// from the last refresh, so speeding up can refresh right away.
+    pub fn step_refresh_interval(&mut self, faster: bool) {
+        let requested = if faster {
+            self.refresh_interval / 2
+        } else {
+            self.refresh_interval * 2
+        };
+        let max = Duration::from_secs_f64(MAX_REFRESH_SECS).max(self.min_refresh_interval);
+        let interval = requested.clamp(self.min_refresh_interval, max);
+        if interval != self.refresh_interval {
+            self.refresh_interval = interval;
+            self.schedule_next_refresh();
+            self.refresh_interval_changed = true;
+            self.dirty = true;
+        }
+    }
+
+    /// Refresh quotes from API, waiting for the result.
     pub async fn refresh(&mut self) -> Result<()> {
         if self.symbols.is_empty() {
             return Ok(());
         }
 
-        match self.client.get_quotes(&self.symbols).await {
-            Ok(quotes) => {
-                self.quotes = quotes;
+        let symbols = self.begin_fetch();
+        let result = self.provider.get_quotes(&symbols).await;
+        self.apply_fetch(result);
+        Ok(())
+    }
+
+    /// Start a refresh in the background. The result arrives on `results`
+    /// and goes through [`App::apply_fetch`], so the UI keeps drawing and
+    /// handling keys while Yahoo takes its time.
+    pub fn spawn_refresh(&mut self, results: &UnboundedSender<Result<QuoteBatch>>) {
+        if self.symbols.is_empty() || self.is_fetching() {
+            return;
+        }
+
+        let provider = Arc::clone(&self.provider);
+        let symbols = self.begin_fetch();
+        let results = results.clone();
+        tokio::spawn(async move {
+            // Nobody listening means we're shutting down; drop the result
+            let _ = results.send(provider.get_quotes(&symbols).await);
+        });
+    }
+
+    /// Symbols whose batch rows are worth printing this iteration under
+    /// --min-change-pct, remembering their prices for next time. Moves are
+    /// measured from the last printed price, so a slow drift still shows up
+    /// once it adds up. None prints every row.
+    pub fn batch_rows_to_print(&mut self) -> Option<HashSet<String>> {
+        let threshold = self.min_change_pct?;
+        let shown: Vec<(String, f64)> = self
+            .display_quotes()
+            .into_iter()
+            .map(|q| (q.symbol.clone(), q.price))
+            .collect();
+        let mut rows = HashSet::new();
+        for (symbol, price) in shown {
+            let moved = match self.last_printed.get(&symbol) {
+                None => true,
+                Some(&last) => match reference_change_percent(price, last) {
+                    Some(pct) => pct.abs() >= threshold,
+                    None => price != last,
+                },
+            };
+            if moved {
+                self.last_printed.insert(symbol.clone(), price);
+                rows.insert(symbol);
+            }
+        }
+        Some(rows)
+    }
+
+    /// Whether a fetch is in flight.
+    pub fn is_fetching(&self) -> bool {
+        self.fetching_since.is_some()
+    }
+
+    /// Spinner frame for the fetch in flight, counted from when it started
+    /// so the spinner turns at the same speed however often the screen is
+    /// redrawn (keys and mouse events redraw too). None when idle.
+    pub fn spinner_step(&self, now: Instant) -> Option<usize> {
+        let elapsed = now.saturating_duration_since(self.fetching_since?);
+        Some((elapsed.as_millis() / SPINNER_STEP.as_millis()) as usize)
+    }
+
+    /// Step the selection down one row once per autoscroll interval,
+    /// wrapping to the top after the last row. Returns whether it moved.
+    pub fn advance_autoscroll(&mut self, now: Instant) -> bool {
+        let Some(interval) = self.autoscroll else {
+            return false;
+        };
+        if now.duration_since(self.last_autoscroll) < interval {
+            return false;
+        }
+        self.last_autoscroll = now;
+        let rows = self.display_quotes().len();
+        if rows == 0 {
+            return false;
+        }
+        self.selected = (self.selected + 1) % rows;
+        true
+    }
+
+    /// Hand the display back to the user: any key press ends autoscroll.
+    pub fn stop_autoscroll(&mut self) {
+        self.autoscroll = None;
+    }
+
+    /// Apply the outcome of a fetch, whether awaited or from the background.
+    pub fn apply_fetch(&mut self, result: Result<QuoteBatch>) {
+        self.fetching_since = None;
+        let scope = self.fetch_scope.take();
+        match result {
+            Ok(batch) => {
+                let fetched_any = !batch.quotes.is_empty();
+                let outcome = match batch.failures.first() {
+                    Some(failure) if batch.quotes.is_empty() => Err(failure.reason.clone()),
+                    _ => Ok(()),
+                };
+                self.warnings = batch.warnings;
+                match scope {
+                    // A holdings-only fetch updates its symbols and leaves the rest be
+                    Some(ref scope) => {
+                        let in_scope = |symbol: &String| scope.contains(symbol);
+                        self.quotes.retain(|q| !in_scope(&q.symbol));
+                        self.quotes.extend(batch.quotes);
+                        self.failures.retain(|f| !in_scope(&f.symbol));
+                        self.failures.extend(batch.failures);
+                    }
+                    None => {
+                        self.quotes = batch.quotes;
+                        self.failures = batch.failures;
+                    }
+                }
+                if self.hide_invalid {
+                    let invalid = take_invalid_quotes(&mut self.quotes);
+                    self.failures.extend(invalid);
+                }
+                if fetched_any {
+                    self.carry_missing_quotes(scope.as_deref());
+                }
+                self.update_synthetics();
+                self.track_changes();
+                self.record_session_open();
+                self.record_price_history(scope.as_deref());
+                if scope.is_none() {
+                    self.record_composite();
+                }
+                self.notify_big_movers();
+                self.notify_alert_rules();
                 self.sort_quotes();
-                self.last_refresh = Some(Instant::now());
+                self.clamp_selection();
+                self.record_portfolio_value();
+                let day_change = self.today_portfolio_change_percent();
+                if let Some(ref mut panic) = self.panic {
+                    panic.update(day_change);
+                }
+                let now = Instant::now();
+                self.last_holdings_refresh = Some(now);
+                if scope.is_none() {
+                    self.last_refresh = Some(now);
+                    self.schedule_next_refresh();
+                }
                 self.iteration += 1;
                 self.error = None;
+                self.note_refresh_outcome(outcome);
             }
             Err(e) => {
                 self.error = Some(format!("API Error: {}", e));
+                self.note_refresh_outcome(Err(e.to_string()));
             }
         }
+    }
 
-        Ok(())
+    /// Count refreshes where nothing came back, and go offline once there
+    /// have been too many in a row. Any success brings us back.
+    fn note_refresh_outcome(&mut self, outcome: std::result::Result<(), String>) {
+        let reason = match outcome {
+            Ok(()) => {
+                self.failed_refreshes = 0;
+                self.offline = None;
+                return;
+            }
+            Err(reason) => reason,
+        };
+
+        self.failed_refreshes += 1;
+        if self.max_failed_refreshes > 0 && self.failed_refreshes >= self.max_failed_refreshes {
+            self.offline = Some(format!(
+                "No quotes for {} refreshes in a row (last error: {})\n\n\
+                 Check your network connection and proxy settings, make sure the \
+                 symbols exist, or try --user-agent if requests are being blocked. \
+                 --fixture runs without the network.",
+                self.failed_refreshes, reason
+            ));
+        }
+    }
+
+    /// Put symbols the batch left out back at their last known price, so a
+    /// delisting doesn't quietly drop them from the totals, and count how
+    /// many refreshes in a row each has been missing. A scoped fetch only
+    /// answers for the symbols in it.
+    fn carry_missing_quotes(&mut self, scope: Option<&[String]>) {
+        let fetched: HashSet<String> = self.quotes.iter().map(|q| q.symbol.clone()).collect();
+        for quote in self.quotes.iter().filter(|q| !q.synthetic) {
+            self.last_known.insert(quote.symbol.clone(), quote.clone());
+        }
+        self.missing_streak
+            .retain(|symbol, _| !fetched.contains(symbol));
+
+        let asked = scope.unwrap_or(&self.symbols);
+        for symbol in asked.iter().filter(|s| !fetched.contains(*s)) {
+            *self.missing_streak.entry(symbol.clone()).or_default() += 1;
+            if let Some(quote) = self.last_known.get(symbol) {
+                self.quotes.push(quote.clone());
+            }
+        }
+    }
+
+    /// How a symbol is shown in the tables. With `compact_crypto`, pairs
+    /// quoted in the display currency lose the suffix (BTC-USD is BTC);
+    /// the full symbol is still what gets fetched.
+    pub fn display_symbol(&self, symbol: &str) -> String {
+        if self.compact_crypto {
+            return self.strip_display_currency(symbol).to_string();
+        }
+        symbol.to_string()
+    }
+
+    /// A pair quoted in the display currency without the suffix (BTC-USD is
+    /// BTC). Pairs quoted in anything else keep it, so ETH-BTC and BTC-EUR
+    /// can't pass for dollar prices.
+    pub fn strip_display_currency<'a>(&self, symbol: &'a str) -> &'a str {
+        symbol
+            .strip_suffix(self.currency.as_str())
+            .and_then(|rest| rest.strip_suffix('-'))
+            .filter(|base| !base.is_empty())
+            .unwrap_or(symbol)
+    }
+
+    /// Whether a row is a stand-in at its last known price.
+    pub fn is_carried(&self, symbol: &str) -> bool {
+        self.missing_streak.contains_key(symbol)
+    }
+
+    /// Symbols missing for `no_data_after` refreshes in a row, sorted.
+    pub fn no_data_symbols(&self) -> Vec<&str> {
+        if self.no_data_after == 0 {
+            return Vec::new();
+        }
+        let mut symbols: Vec<&str> = self
+            .missing_streak
+            .iter()
+            .filter(|(_, streak)| **streak >= self.no_data_after)
+            .map(|(symbol, _)| symbol.as_str())
+            .collect();
+        symbols.sort_unstable();
+        symbols
+    }
+
+    /// Held symbols currently priced from a carried-over quote, sorted.
+    pub fn stale_holdings(&self) -> Vec<&str> {
+        let mut symbols: Vec<&str> = self
+            .holdings
+            .keys()
+            .filter(|symbol| self.is_carried(symbol) && self.last_known.contains_key(*symbol))
+            .map(String::as_str)
+            .collect();
+        symbols.sort_unstable();
+        symbols
+    }
+
+    /// Note which symbols moved since the previous refresh, replacing the
+    /// marks from the refresh before. The first sighting of a symbol isn't a
+    /// change, just an introduction.
+    fn track_changes(&mut self) {
+        self.unseen_changes.clear();
+        for quote in &self.quotes {
+            let current = (quote.price, quote.volume);
+            if let Some(previous) = self.last_seen.insert(quote.symbol.clone(), current) {
+                if previous != current {
+                    self.unseen_changes.insert(quote.symbol.clone());
+                }
+            }
+        }
+    }
+
+    /// Clear the change mark on the selected row, which the user is looking at.
+    pub fn mark_selected_seen(&mut self) {
+        if let Some(symbol) = self.selected_quote().map(|q| q.symbol.clone()) {
+            self.unseen_changes.remove(&symbol);
+        }
+    }
+
+    /// Remember the first price seen for each symbol, the baseline for SESSION.
+    fn record_session_open(&mut self) {
+        for quote in &self.quotes {
+            if quote.price > 0.0 {
+                self.session_open
+                    .entry(quote.symbol.clone())
+                    .or_insert(quote.price);
+            }
+        }
+    }
+
+    /// Append this refresh's prices to each symbol's history. A scoped
+    /// fetch only brought new prices for the symbols in it.
+    fn record_price_history(&mut self, scope: Option<&[String]>) {
+        for quote in &self.quotes {
+            let carried = self.missing_streak.contains_key(&quote.symbol);
+            if quote.price <= 0.0
+                || carried
+                || scope.is_some_and(|scope| !scope.contains(&quote.symbol))
+            {
+                continue;
+            }
+            let history = self.price_history.entry(quote.symbol.clone()).or_default();
+            history.push(quote.price);
+            if history.len() > PRICE_HISTORY_LEN {
+                history.drain(..history.len() - PRICE_HISTORY_LEN);
+            }
+        }
+    }
+
+    /// Start the session over from the current prices.
+    pub fn reset_session(&mut self) {
+        self.session_open.clear();
+        self.record_session_open();
+    }
+
+    /// Percent change of a quote since it was first fetched this session.
+    pub fn session_change(&self, quote: &Quote) -> Option<f64> {
+        let open = *self.session_open.get(&quote.symbol)?;
+        reference_change_percent(quote.price, open)
+    }
+
+    /// Send a notification for each symbol that just crossed the big-move threshold.
+    fn notify_big_movers(&mut self) {
+        let Some(watcher) = self.move_watcher.as_mut() else {
+            return;
+        };
+
+        for quote in &self.quotes {
+            if watcher.check(&quote.symbol, quote.change_percent) {
+                let title = format!("{} {:+.2}%", quote.symbol, quote.change_percent);
+                let body = format!("{} is at {:.2} {}", quote.name, quote.price, quote.currency);
+                notify_unless_quiet(&*self.notifier, self.quiet_hours, &title, &body);
+            }
+        }
+    }
+
+    /// Send a notification for each alert rule that just started matching.
+    fn notify_alert_rules(&mut self) {
+        let fired = alerts::check_alerts(
+            &self.alert_rules,
+            &self.quotes,
+            &mut self.active_alerts,
+            self.alert_stale_after,
+            Utc::now(),
+        );
+        for alert in fired {
+            notify_unless_quiet(
+                &*self.notifier,
+                self.quiet_hours,
+                "stonktop alert",
+                &alert.to_string(),
+            );
+        }
+    }
+
+    /// Check if a symbol moved on the last refresh and its row hasn't been
+    /// selected since.
+    pub fn has_unseen_change(&self, symbol: &str) -> bool {
+        self.unseen_changes.contains(symbol)
+    }
+
+    /// The sort the active view uses: the holdings view has its own.
+    pub fn active_sort(&self) -> (SortOrder, SortDirection) {
+        if self.show_holdings {
+            (self.holdings_sort_order, self.holdings_sort_direction)
+        } else {
+            (self.sort_order, self.sort_direction)
+        }
+    }
+
+    fn active_sort_mut(&mut self) -> (&mut SortOrder, &mut SortDirection) {
+        if self.show_holdings {
+            (
+                &mut self.holdings_sort_order,
+                &mut self.holdings_sort_direction,
+            )
+        } else {
+            (&mut self.sort_order, &mut self.sort_direction)
+        }
     }
 
-    /// Sort quotes according to current sort settings.
+    /// Sort quotes according to the active view's sort settings.
     pub fn sort_quotes(&mut self) {
-        let direction = self.sort_direction;
+        let (order, direction) = self.active_sort();
+        let metric = |q: &Quote| Self::holding_metric(&self.holdings, order, q);
 
         self.quotes.sort_by(|a, b| {
-            let cmp = match self.sort_order {
+            // Crypto and indices have no market cap; keep them at the bottom
+            // whichever way we sort instead of letting them jump to the top
+            if order == SortOrder::MarketCap {
+                match (a.market_cap, b.market_cap) {
+                    (Some(_), None) => return std::cmp::Ordering::Less,
+                    (None, Some(_)) => return std::cmp::Ordering::Greater,
+                    _ => {}
+                }
+            }
+            // Same for symbols without a position under a holdings sort
+            if order.holdings_only() {
+                match (metric(a), metric(b)) {
+                    (Some(_), None) => return std::cmp::Ordering::Less,
+                    (None, Some(_)) => return std::cmp::Ordering::Greater,
+                    _ => {}
+                }
+            }
+
+            let cmp = match order {
                 SortOrder::Symbol => a.symbol.cmp(&b.symbol),
                 SortOrder::Name => a.name.cmp(&b.name),
                 SortOrder::Price => a
@@ -169,8 +1048,26 @@ impl App {
                     .change_percent
                     .partial_cmp(&b.change_percent)
                     .unwrap_or(std::cmp::Ordering::Equal),
+                SortOrder::AbsChangePercent => a
+                    .change_percent
+                    .abs()
+                    .partial_cmp(&b.change_percent.abs())
+                    .unwrap_or(std::cmp::Ordering::Equal),
                 SortOrder::Volume => a.volume.cmp(&b.volume),
                 SortOrder::MarketCap => a.market_cap.cmp(&b.market_cap),
+                SortOrder::Exchange => exchange_suffix(&a.symbol)
+                    .unwrap_or("")
+                    .cmp(exchange_suffix(&b.symbol).unwrap_or(""))
+                    .then_with(|| a.symbol.cmp(&b.symbol)),
+                SortOrder::BaseAsset => base_asset(&a.symbol)
+                    .cmp(base_asset(&b.symbol))
+                    .then_with(|| a.symbol.cmp(&b.symbol)),
+                SortOrder::Value
+                | SortOrder::ProfitLoss
+                | SortOrder::ProfitLossPercent
+                | SortOrder::DayChange => metric(a)
+                    .partial_cmp(&metric(b))
+                    .unwrap_or(std::cmp::Ordering::Equal),
             };
 
             match direction {
@@ -178,31 +1075,167 @@ impl App {
                 SortDirection::Descending => cmp.reverse(),
             }
         });
+
+        // Favorites float to the top; the sort is stable so each half keeps its order
+        self.quotes
+            .sort_by_key(|q| !self.favorites.contains(&q.symbol));
+    }
+
+    /// The holdings figure a sort order uses, if the quote is a holding.
+    fn holding_metric(
+        holdings: &HashMap<String, Holding>,
+        order: SortOrder,
+        quote: &Quote,
+    ) -> Option<f64> {
+        let holding = holdings.get(&quote.symbol)?;
+        match order {
+            SortOrder::Value => Some(holding.current_value(quote.price)),
+            SortOrder::ProfitLoss => Some(holding.profit_loss(quote.price)),
+            SortOrder::ProfitLossPercent => Some(holding.profit_loss_percent(quote.price)),
+            SortOrder::DayChange => Some(holding.quantity * quote.change),
+            _ => None,
+        }
+    }
+
+    /// Get the quotes that pass the active filters, in display order,
+    /// cut to `--top` rows. The holdings view only lists what you hold,
+    /// so navigation there never lands on a row that isn't drawn.
+    pub fn display_quotes(&self) -> Vec<&Quote> {
+        self.view_quotes()
+            .filter(|q| self.matches_exchange(q) && self.matches_type(q) && self.matches_search(q))
+            .take(self.top.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    /// The rows the active view has before any filter: nothing hidden, and
+    /// the holdings view sticks to held symbols.
+    fn view_quotes(&self) -> impl Iterator<Item = &Quote> {
+        let holdings_only = self.show_holdings;
+        self.quotes
+            .iter()
+            .filter(move |q| !holdings_only || self.holdings.contains_key(&q.symbol))
+            .filter(move |q| holdings_only || !self.holdings_only_symbols.contains(&q.symbol))
+            .filter(|q| !self.hidden_symbols.contains(&q.symbol))
+    }
+
+    /// Which filters are cutting rows and how many are left, e.g.
+    /// "Showing 12 of 50 (search: 'ap', top: 20)". None with no filter on.
+    pub fn filter_summary(&self) -> Option<String> {
+        let mut active = Vec::new();
+        if let Some(ref exchange) = self.exchange_filter {
+            active.push(format!("exchange: {}", exchange));
+        }
+        if let Some(value) = self.type_filter.and_then(|f| f.to_possible_value()) {
+            active.push(format!("type: {}", value.get_name()));
+        }
+        if let Some(ref query) = self.search_query {
+            active.push(format!("search: '{}'", query));
+        }
+        if let Some(top) = self.top {
+            active.push(format!("top: {}", top));
+        }
+        if active.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "Showing {} of {} ({})",
+            self.display_quotes().len(),
+            self.view_quotes().count(),
+            active.join(", ")
+        ))
+    }
+
+    /// Keep the selection on a visible row after the list shrinks (new
+    /// quotes, a filter, `--top`, another group), and drop the detail view
+    /// if there's nothing left to show in it.
+    pub fn clamp_selection(&mut self) {
+        let len = self.display_quotes().len();
+        if self.selected >= len {
+            self.selected = len.saturating_sub(1);
+        }
+        if len == 0 {
+            self.show_detail = false;
+        }
+    }
+
+    /// Check a quote against the instrument type filter.
+    fn matches_type(&self, quote: &Quote) -> bool {
+        match self.type_filter {
+            None => true,
+            Some(FilterType::Stocks) => quote.quote_type == QuoteType::Equity,
+            Some(FilterType::Crypto) => quote.quote_type == QuoteType::Cryptocurrency,
+            Some(FilterType::Etf) => quote.quote_type == QuoteType::Etf,
+            Some(FilterType::Index) => quote.quote_type == QuoteType::Index,
+        }
+    }
+
+    /// Check a quote against the search query.
+    fn matches_search(&self, quote: &Quote) -> bool {
+        match self.search_query.as_deref() {
+            None => true,
+            Some(query) => {
+                find_ignore_case(&quote.symbol, query).is_some()
+                    || find_ignore_case(&quote.name, query).is_some()
+            }
+        }
+    }
+
+    /// Check a quote against the exchange filter.
+    fn matches_exchange(&self, quote: &Quote) -> bool {
+        match self.exchange_filter.as_deref() {
+            None => true,
+            Some("US") => exchange_suffix(&quote.symbol).is_none(),
+            Some(wanted) => {
+                exchange_suffix(&quote.symbol).is_some_and(|s| s.eq_ignore_ascii_case(wanted))
+            }
+        }
     }
 
     /// Toggle sort direction.
     pub fn toggle_sort_direction(&mut self) {
-        self.sort_direction = self.sort_direction.toggle();
+        let (_, direction) = self.active_sort_mut();
+        *direction = direction.toggle();
         self.sort_quotes();
     }
 
     /// Cycle to next sort order.
     pub fn next_sort_order(&mut self) {
-        self.sort_order = self.sort_order.next();
+        let view = self.active_view();
+        let (order, _) = self.active_sort_mut();
+        *order = order.next();
+        while !view.can_sort_by(*order) {
+            *order = order.next();
+        }
         self.sort_quotes();
     }
 
     /// Set specific sort order.
+    /// Orders the active view can't show (e.g. P/L outside holdings) are ignored.
     pub fn set_sort_order(&mut self, order: SortOrder) {
-        if self.sort_order == order {
-            self.toggle_sort_direction();
+        if !self.active_view().can_sort_by(order) {
+            return;
+        }
+        let (current, direction) = self.active_sort_mut();
+        if *current == order {
+            *direction = direction.toggle();
         } else {
-            self.sort_order = order;
-            self.sort_direction = SortDirection::Descending;
+            *current = order;
+            *direction = order.default_direction();
         }
         self.sort_quotes();
     }
 
+    /// Sort by the nth (1-based) hotkey column of the active view.
+    pub fn sort_by_column(&mut self, column: u8) {
+        let columns = self.active_view().hotkey_columns();
+        if let Some(&Some(order)) = (column as usize)
+            .checked_sub(1)
+            .and_then(|i| columns.get(i))
+        {
+            self.set_sort_order(order);
+        }
+    }
+
     /// Move selection up.
     pub fn select_up(&mut self) {
         if self.selected > 0 {
@@ -212,7 +1245,7 @@ impl App {
 
     /// Move selection down.
     pub fn select_down(&mut self) {
-        if self.selected < self.quotes.len().saturating_sub(1) {
+        if self.selected < self.display_quotes().len().saturating_sub(1) {
             self.selected += 1;
         }
     }
@@ -224,7 +1257,7 @@ impl App {
 
     /// Move selection to bottom.
     pub fn select_bottom(&mut self) {
-        self.selected = self.quotes.len().saturating_sub(1);
+        self.selected = self.display_quotes().len().saturating_sub(1);
     }
 
     /// Toggle help display.
@@ -234,10 +1267,17 @@ impl App {
         }
     }
 
+    /// Open or close the detail view for the selected symbol.
+    pub fn toggle_detail(&mut self) {
+        self.show_detail = !self.show_detail && self.selected_quote().is_some();
+    }
+
     /// Toggle holdings view.
     pub fn toggle_holdings(&mut self) {
         if !self.secure_mode {
             self.show_holdings = !self.show_holdings;
+            self.sort_quotes();
+            self.clamp_selection();
         }
     }
 
@@ -248,73 +1288,674 @@ impl App {
         }
     }
 
-    /// Quit the application.
-    pub fn quit(&mut self) {
-        self.running = false;
+    /// Get the view currently shown in the main area.
+    pub fn active_view(&self) -> View {
+        if self.show_holdings {
+            View::Holdings
+        } else if self.show_fundamentals {
+            View::Fundamentals
+        } else {
+            View::Quotes
+        }
     }
 
-    /// Check if max iterations reached.
-    pub fn should_quit(&self) -> bool {
-        !self.running || (self.max_iterations > 0 && self.iteration >= self.max_iterations)
+    /// Whether the quotes table shows the (dollar change, percent change)
+    /// columns. Verbose mode and the fundamentals view want both.
+    pub fn change_columns(&self) -> (bool, bool) {
+        if self.verbose || self.active_view() == View::Fundamentals {
+            return (true, true);
+        }
+        match self.primary_change {
+            PrimaryChange::Both => (true, true),
+            PrimaryChange::Change => (true, false),
+            PrimaryChange::ChangePercent => (false, true),
+        }
     }
 
-    /// Get total portfolio value.
-    pub fn total_portfolio_value(&self) -> f64 {
-        self.quotes
-            .iter()
-            .filter_map(|q| {
-                self.holdings
-                    .get(&q.symbol)
-                    .map(|h| h.current_value(q.price))
-            })
-            .sum()
+    /// Switch the main area to a specific view.
+    pub fn set_view(&mut self, view: View) {
+        self.show_holdings = view == View::Holdings;
+        self.show_fundamentals = view == View::Fundamentals;
+        self.sort_quotes();
+        self.clamp_selection();
     }
 
-    /// Get total portfolio cost.
-    pub fn total_portfolio_cost(&self) -> f64 {
-        self.holdings.values().map(|h| h.total_cost()).sum()
+    /// Set the instrument type filter.
+    pub fn set_type_filter(&mut self, filter: Option<FilterType>) {
+        self.type_filter = filter;
+        self.clamp_selection();
     }
 
-    /// Get total portfolio profit/loss.
-    pub fn total_portfolio_pnl(&self) -> f64 {
-        self.total_portfolio_value() - self.total_portfolio_cost()
+    /// Apply a user action.
+    pub fn apply_action(&mut self, action: Action) {
+        match action {
+            Action::Quit => self.request_quit(),
+            Action::SelectUp => self.select_up(),
+            Action::SelectDown => self.select_down(),
+            Action::SelectTop => self.select_top(),
+            Action::SelectBottom => self.select_bottom(),
+            Action::PageUp => {
+                for _ in 0..10 {
+                    self.select_up();
+                }
+            }
+            Action::PageDown => {
+                for _ in 0..10 {
+                    self.select_down();
+                }
+            }
+            Action::CycleSort => self.next_sort_order(),
+            Action::ReverseSort => self.toggle_sort_direction(),
+            Action::SortBy(order) => self.set_sort_order(order),
+            Action::SortColumn(column) => self.sort_by_column(column),
+            Action::ToggleHoldings => self.toggle_holdings(),
+            Action::ToggleFundamentals => self.toggle_fundamentals(),
+            Action::ToggleFullVolume => self.full_volume = !self.full_volume,
+            Action::FasterRefresh => self.step_refresh_interval(true),
+            Action::SlowerRefresh => self.step_refresh_interval(false),
+            Action::ToggleHelp => self.toggle_help(),
+            Action::ToggleDetail => self.toggle_detail(),
+            Action::Refresh => {
+                // Force refresh on next tick
+                self.last_refresh = None;
+                self.last_holdings_refresh = None;
+            }
+            Action::NextGroup => {
+                if !self.groups.is_empty() {
+                    self.active_group = (self.active_group + 1) % self.groups.len();
+                }
+                self.clamp_selection();
+            }
+            Action::SetView(view) => self.set_view(view),
+            Action::SetFilter(filter) => self.set_type_filter(filter),
+            Action::EditReference => self.start_reference_prompt(),
+            Action::EditNote => self.start_note_prompt(),
+            Action::ToggleFavorite => self.toggle_favorite(),
+            Action::CopySymbol => self.copy_selected(false),
+            Action::CopyQuote => self.copy_selected(true),
+            Action::ResetSession => self.reset_session(),
+            Action::Search => self.start_search_prompt(),
+        }
     }
 
-    /// Get today's portfolio change.
-    pub fn today_portfolio_change(&self) -> f64 {
-        self.quotes
-            .iter()
-            .filter_map(|q| self.holdings.get(&q.symbol).map(|h| h.quantity * q.change))
-            .sum()
+    /// Dismiss a transient error. The offline banner isn't dismissable.
+    /// Returns whether there was anything to dismiss.
+    pub fn dismiss_error(&mut self) -> bool {
+        self.error.take().is_some()
     }
 
-    /// Add a symbol to watch.
-    /// For when FOMO hits and you need to track one more meme stock.
-    #[allow(dead_code)] // Interactive symbol adding - coming in v2.0 (probably)
-    pub fn add_symbol(&mut self, symbol: &str) {
-        let expanded = expand_symbol(symbol);
-        if !self.symbols.contains(&expanded) {
-            self.symbols.push(expanded);
+    /// Copy the selected symbol, or its whole quote line, to the clipboard.
+    /// Not in secure mode, and a missing clipboard is an error, not a crash.
+    pub fn copy_selected(&mut self, full: bool) {
+        if self.secure_mode {
+            return;
+        }
+        let Some(quote) = self.selected_quote() else {
+            return;
+        };
+        let text = if full {
+            crate::ui::quote_line(quote, self.percent_precision)
+        } else {
+            quote.symbol.clone()
+        };
+        if let Err(e) = self.clipboard.copy(&text) {
+            self.error = Some(format!("{:#}", e));
         }
     }
 
-    /// Remove a symbol from watch.
-    /// Denial is the first stage of grief. Removing it from your watchlist is the second.
-    #[allow(dead_code)] // Interactive symbol removal - for those who can't handle the truth
-    pub fn remove_symbol(&mut self, symbol: &str) {
-        let expanded = expand_symbol(symbol);
-        self.symbols.retain(|s| s != &expanded);
-        self.quotes.retain(|q| q.symbol != expanded);
-        if self.selected >= self.quotes.len() {
-            self.selected = self.quotes.len().saturating_sub(1);
+    /// Pin or unpin the selected symbol.
+    pub fn toggle_favorite(&mut self) {
+        let Some(symbol) = self.selected_quote().map(|q| q.symbol.clone()) else {
+            return;
+        };
+        if !self.favorites.remove(&symbol) {
+            self.favorites.insert(symbol.clone());
         }
-    }
+        self.favorites_changed = true;
+        self.sort_quotes();
+
+        // Keep the cursor on the row that just moved
+        if let Some(i) = self
+            .display_quotes()
+            .iter()
+            .position(|q| q.symbol == symbol)
+        {
+            self.selected = i;
+        }
+    }
+
+    /// Open the footer prompt to set the selected symbol's reference price.
+    pub fn start_reference_prompt(&mut self) {
+        if let Some(quote) = self.selected_quote() {
+            let symbol = quote.symbol.clone();
+            let buffer = self
+                .reference_prices
+                .get(&symbol)
+                .map(|r| r.to_string())
+                .unwrap_or_default();
+            self.prompt = Some(Prompt {
+                kind: PromptKind::ReferencePrice(symbol),
+                buffer,
+            });
+        }
+    }
+
+    /// Open the footer prompt to edit the selected symbol's note.
+    pub fn start_note_prompt(&mut self) {
+        if let Some(quote) = self.selected_quote() {
+            let symbol = quote.symbol.clone();
+            let buffer = self.note(&symbol).unwrap_or_default().to_string();
+            self.prompt = Some(Prompt {
+                kind: PromptKind::Note(symbol),
+                buffer,
+            });
+        }
+    }
+
+    /// Open the footer prompt to search, starting from the current query.
+    pub fn start_search_prompt(&mut self) {
+        self.prompt = Some(Prompt {
+            kind: PromptKind::Search,
+            buffer: self.search_query.clone().unwrap_or_default(),
+        });
+    }
+
+    /// React to the prompt text changing. Search filters as you type.
+    pub fn prompt_changed(&mut self) {
+        if let Some(Prompt {
+            kind: PromptKind::Search,
+            buffer,
+        }) = &self.prompt
+        {
+            self.set_search(buffer.clone());
+        }
+    }
+
+    /// Close the prompt without applying it. A cancelled search clears the filter.
+    pub fn cancel_prompt(&mut self) {
+        if let Some(prompt) = self.prompt.take() {
+            if prompt.kind == PromptKind::Search {
+                self.set_search(String::new());
+            }
+        }
+    }
+
+    /// Set the search query; blank clears it.
+    fn set_search(&mut self, query: String) {
+        let query = query.trim();
+        self.search_query = (!query.is_empty()).then(|| query.to_string());
+        self.clamp_selection();
+    }
+
+    /// Apply what was typed at the prompt and close it.
+    pub fn submit_prompt(&mut self) {
+        let Some(prompt) = self.prompt.take() else {
+            return;
+        };
+
+        match prompt.kind {
+            PromptKind::ReferencePrice(ref symbol) => {
+                let input = prompt.buffer.trim();
+                if input.is_empty() {
+                    self.set_reference_price(symbol, None);
+                    return;
+                }
+                match input.parse::<f64>() {
+                    Ok(price) if price > 0.0 && price.is_finite() => {
+                        self.set_reference_price(symbol, Some(price))
+                    }
+                    // Say what was wrong and leave the prompt up to fix it
+                    _ => {
+                        self.error = Some(format!("Invalid price: {}", input));
+                        self.prompt = Some(prompt);
+                    }
+                }
+            }
+            PromptKind::Note(ref symbol) => {
+                let text = prompt.buffer.trim();
+                self.set_note(symbol, (!text.is_empty()).then(|| text.to_string()));
+            }
+            PromptKind::Search => self.set_search(prompt.buffer),
+            // Enter isn't an answer; wait for y or n
+            PromptKind::ConfirmQuit => self.prompt = Some(prompt),
+        }
+    }
+
+    /// Set or clear (None) a symbol's reference price.
+    pub fn set_reference_price(&mut self, symbol: &str, price: Option<f64>) {
+        match price {
+            Some(price) => {
+                self.reference_prices.insert(symbol.to_string(), price);
+            }
+            None => {
+                self.reference_prices.remove(symbol);
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// Set or clear (None) a symbol's note.
+    pub fn set_note(&mut self, symbol: &str, text: Option<String>) {
+        match text {
+            Some(text) => {
+                self.notes.insert(symbol.to_string(), text);
+            }
+            None => {
+                self.notes.remove(symbol);
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// A symbol's note, if it has one.
+    pub fn note(&self, symbol: &str) -> Option<&str> {
+        self.notes.get(symbol).map(String::as_str)
+    }
+
+    /// Percent change of a quote from its reference price, if one is set.
+    pub fn reference_change(&self, quote: &Quote) -> Option<f64> {
+        let reference = *self.reference_prices.get(&quote.symbol)?;
+        reference_change_percent(quote.price, reference)
+    }
+
+    /// How much a quote beat (or trailed) the benchmark today, in
+    /// percentage points. None without a benchmark quote, and for the
+    /// benchmark itself.
+    pub fn relative_strength(&self, quote: &Quote) -> Option<f64> {
+        let benchmark = self.benchmark.as_deref()?;
+        if quote.symbol.eq_ignore_ascii_case(benchmark) {
+            return None;
+        }
+        let benchmark = self
+            .quotes
+            .iter()
+            .find(|q| q.symbol.eq_ignore_ascii_case(benchmark))?;
+        Some(quote.change_percent - benchmark.change_percent)
+    }
+
+    /// The change percent a row is colored by: its own, or its lead over the
+    /// benchmark. The benchmark's own row is neither ahead nor behind, and
+    /// until it arrives everything falls back to its own change.
+    pub fn color_change(&self, quote: &Quote) -> f64 {
+        match self.color_basis {
+            ColorBasis::Absolute => quote.change_percent,
+            ColorBasis::Benchmark => {
+                let is_benchmark = self
+                    .benchmark
+                    .as_deref()
+                    .is_some_and(|b| quote.symbol.eq_ignore_ascii_case(b));
+                if is_benchmark {
+                    0.0
+                } else {
+                    self.relative_strength(quote)
+                        .unwrap_or(quote.change_percent)
+                }
+            }
+        }
+    }
+
+    /// Apply a startup sequence of actions, in order.
+    pub fn apply_start_sequence(&mut self, actions: &[Action]) {
+        for action in actions {
+            self.apply_action(*action);
+        }
+    }
+
+    /// Quit the application.
+    pub fn quit(&mut self) {
+        self.running = false;
+    }
+
+    /// Quit, unless there are unsaved changes worth asking about first.
+    /// With `confirm_quit` off they're saved without asking, like favorites.
+    /// Secure and batch mode never ask or save: there's nobody to save for.
+    pub fn request_quit(&mut self) {
+        if !self.dirty || self.secure_mode || self.batch_mode {
+            self.quit();
+        } else if self.confirm_quit {
+            self.prompt = Some(Prompt {
+                kind: PromptKind::ConfirmQuit,
+                buffer: String::new(),
+            });
+        } else {
+            self.answer_quit(true);
+        }
+    }
+
+    /// Answer the quit prompt: save the changes to the config on the way
+    /// out, or leave them behind.
+    pub fn answer_quit(&mut self, save: bool) {
+        self.prompt = None;
+        self.save_on_quit = save;
+        self.quit();
+    }
+
+    /// Check if max iterations reached.
+    pub fn should_quit(&self) -> bool {
+        !self.running || (self.max_iterations > 0 && self.iteration >= self.max_iterations)
+    }
+
+    /// Get total portfolio value.
+    pub fn total_portfolio_value(&self) -> f64 {
+        self.quotes
+            .iter()
+            .filter(|q| !q.synthetic)
+            .filter_map(|q| {
+                self.holdings
+                    .get(&q.symbol)
+                    .map(|h| h.current_value(q.price))
+            })
+            .sum()
+    }
+
+    /// Share of portfolio value per group, largest first. Positions in no
+    /// group are lumped into "Other".
+    pub fn group_weights(&self) -> Vec<(String, f64)> {
+        let mut values: HashMap<&str, f64> = HashMap::new();
+        for quote in self.quotes.iter().filter(|q| !q.synthetic) {
+            if let Some(holding) = self.holdings.get(&quote.symbol) {
+                let group = self
+                    .group_map
+                    .get(&quote.symbol)
+                    .map_or("Other", String::as_str);
+                *values.entry(group).or_default() += holding.current_value(quote.price);
+            }
+        }
+
+        let total: f64 = values.values().sum();
+        if total <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut weights: Vec<(String, f64)> = values
+            .into_iter()
+            .map(|(group, value)| (group.to_string(), value / total * 100.0))
+            .collect();
+        weights.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        weights
+    }
+
+    /// Get total portfolio cost.
+    pub fn total_portfolio_cost(&self) -> f64 {
+        self.holdings.values().map(|h| h.total_cost()).sum()
+    }
+
+    /// Get total portfolio profit/loss.
+    pub fn total_portfolio_pnl(&self) -> f64 {
+        self.total_portfolio_value() - self.total_portfolio_cost()
+    }
+
+    /// Get the annualized return of the dated holdings as a whole.
+    /// Uses the cost-weighted average holding period, so one old position
+    /// doesn't make the whole portfolio look ancient.
+    pub fn portfolio_annualized_return(&self) -> Option<f64> {
+        let today = Local::now().date_naive();
+        let mut cost = 0.0;
+        let mut value = 0.0;
+        let mut weighted_days = 0.0;
+
+        for quote in &self.quotes {
+            let Some(holding) = self.holdings.get(&quote.symbol) else {
+                continue;
+            };
+            let Some(date) = holding.purchase_date else {
+                continue;
+            };
+            let holding_cost = holding.total_cost();
+            cost += holding_cost;
+            value += holding.current_value(quote.price);
+            weighted_days += holding_cost * (today - date).num_days() as f64;
+        }
+
+        if cost <= 0.0 {
+            return None;
+        }
+        annualized_return(cost, value, (weighted_days / cost).round() as i64)
+    }
+
+    /// Get today's portfolio change.
+    pub fn today_portfolio_change(&self) -> f64 {
+        self.quotes
+            .iter()
+            .filter(|q| !q.synthetic)
+            .filter_map(|q| self.holdings.get(&q.symbol).map(|h| h.quantity * q.change))
+            .sum()
+    }
+
+    /// Day change of the portfolio in percent of its value at the previous
+    /// close. None without priced holdings.
+    pub fn today_portfolio_change_percent(&self) -> Option<f64> {
+        let value = self.total_portfolio_value();
+        reference_change_percent(value, value - self.today_portfolio_change())
+    }
+
+    /// The day change and threshold behind the panic warning, while it's up.
+    pub fn panic_warning(&self) -> Option<(f64, f64)> {
+        let panic = self.panic.as_ref()?;
+        Some((panic.active()?, panic.threshold()))
+    }
+
+    /// Dismiss the panic warning. Returns whether it was showing.
+    pub fn dismiss_panic(&mut self) -> bool {
+        self.panic.as_mut().is_some_and(PanicWatch::dismiss)
+    }
+
+    /// Recompute the synthetic rows from the quotes just fetched.
+    fn update_synthetics(&mut self) {
+        self.quotes.retain(|q| !q.synthetic);
+        let rows: Vec<Quote> = self
+            .synthetics
+            .iter()
+            .filter_map(|synthetic| synthetic.quote(&self.quotes))
+            .collect();
+        self.quotes.extend(rows);
+    }
+
+    /// Advance/decline and new highs/lows across the rows on screen.
+    pub fn market_breadth(&self) -> Breadth {
+        Breadth::of(self.display_quotes())
+    }
+
+    /// Get the aggregate watchlist move as (dollar change, percent change).
+    /// Holdings are left out since the portfolio view already covers them.
+    /// Returns None when nothing carries any weight.
+    pub fn watchlist_net_change(&self) -> Option<(f64, f64)> {
+        let weighted = weigh(
+            self.display_quotes()
+                .into_iter()
+                .filter(|q| !q.synthetic && !self.holdings.contains_key(&q.symbol)),
+            self.net_change_weighting,
+        );
+
+        let total_weight: f64 = weighted.iter().map(|(w, _)| w).sum();
+        if total_weight <= 0.0 {
+            return None;
+        }
+
+        let change = weighted.iter().map(|(w, q)| w * q.change).sum::<f64>() / total_weight;
+        let percent = weighted
+            .iter()
+            .map(|(w, q)| w * q.change_percent)
+            .sum::<f64>()
+            / total_weight;
+
+        Some((change, percent))
+    }
+
+    /// Today's change of the whole watchlist rolled into one "my market"
+    /// percentage, holdings included. The basket is fixed by the config, so
+    /// the view, filters and search don't move it; the benchmark and ratio
+    /// rows aren't part of it, and neither are quotes carried over from an
+    /// earlier refresh. None when nothing carries any weight.
+    pub fn composite_change(&self, weighting: Weighting) -> Option<f64> {
+        let weighted = weigh(
+            self.quotes.iter().filter(|q| {
+                !q.synthetic
+                    && !self.hidden_symbols.contains(&q.symbol)
+                    && !self.is_carried(&q.symbol)
+            }),
+            weighting,
+        );
+        let total_weight: f64 = weighted.iter().map(|(w, _)| w).sum();
+        if total_weight <= 0.0 {
+            return None;
+        }
+        Some(
+            weighted
+                .iter()
+                .map(|(w, q)| w * q.change_percent)
+                .sum::<f64>()
+                / total_weight,
+        )
+    }
+
+    /// Track the composite in `price_history` under [`COMPOSITE_KEY`], so
+    /// it gets a sparkline like any symbol.
+    fn record_composite(&mut self) {
+        let Some(percent) = self.composite_index.and_then(|w| self.composite_change(w)) else {
+            return;
+        };
+        let history = self
+            .price_history
+            .entry(COMPOSITE_KEY.to_string())
+            .or_default();
+        history.push(percent);
+        if history.len() > PRICE_HISTORY_LEN {
+            history.drain(..history.len() - PRICE_HISTORY_LEN);
+        }
+    }
+
+    /// Append the current portfolio value to the history.
+    /// Skipped when no holding has a quote yet, so a failed first fetch doesn't log a $0 crash.
+    pub fn record_portfolio_value(&mut self) {
+        let has_priced_holding = self
+            .quotes
+            .iter()
+            .any(|q| self.holdings.contains_key(&q.symbol));
+        if !has_priced_holding {
+            return;
+        }
+
+        let value = self.total_portfolio_value();
+        history::push_capped(
+            &mut self.portfolio_value_history,
+            (Utc::now(), value),
+            self.portfolio_history_len,
+        );
+        history::record_snapshot(
+            &mut self.portfolio_snapshots,
+            chrono::Local::now().date_naive(),
+            value,
+            history::MAX_SNAPSHOTS,
+        );
+    }
+
+    /// Load the persisted portfolio value history from the state file next to the config.
+    pub fn load_portfolio_history(&mut self, config_path: &Path) {
+        let path = history::state_file(config_path, history::PORTFOLIO_HISTORY_FILE);
+        match history::load_value_history(&path) {
+            Ok(mut points) => {
+                let excess = points.len().saturating_sub(self.portfolio_history_len);
+                points.drain(..excess);
+                self.portfolio_value_history = points;
+            }
+            Err(e) => eprintln!("Warning: {:#}", e),
+        }
+
+        let path = history::state_file(config_path, history::PORTFOLIO_SNAPSHOTS_FILE);
+        match history::load_snapshots(&path) {
+            Ok(snapshots) => self.portfolio_snapshots = snapshots,
+            Err(e) => eprintln!("Warning: {:#}", e),
+        }
+    }
+
+    /// Save everything that outlives a session next to the config file.
+    /// Each file is written atomically and independently; failures are
+    /// collected rather than stopping the remaining writes, so an unwritable
+    /// directory costs the caller a warning and nothing else.
+    pub fn persist_all(&self, config_path: &Path) -> Vec<anyhow::Error> {
+        let mut errors = Vec::new();
+
+        if !self.portfolio_value_history.is_empty() {
+            let path = history::state_file(config_path, history::PORTFOLIO_HISTORY_FILE);
+            if let Err(e) = history::save_value_history(&path, &self.portfolio_value_history) {
+                errors.push(e);
+            }
+        }
+
+        if !self.portfolio_snapshots.is_empty() {
+            let path = history::state_file(config_path, history::PORTFOLIO_SNAPSHOTS_FILE);
+            if let Err(e) = history::save_snapshots(&path, &self.portfolio_snapshots) {
+                errors.push(e);
+            }
+        }
+
+        if self.favorites_changed {
+            let mut favorites: Vec<String> = self.favorites.iter().cloned().collect();
+            favorites.sort();
+            if let Err(e) = config::save_favorites(config_path, &favorites) {
+                errors.push(e);
+            }
+        }
+
+        if self.save_on_quit {
+            if let Err(e) = config::save_reference_prices(config_path, &self.reference_prices) {
+                errors.push(e);
+            }
+            if let Err(e) = config::save_notes(config_path, &self.notes) {
+                errors.push(e);
+            }
+            if self.refresh_interval_changed {
+                let secs = self.refresh_interval.as_secs_f64();
+                if let Err(e) = config::save_refresh_interval(config_path, secs) {
+                    errors.push(e);
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// Fetch every symbol once, for `--check --probe`, and hand back the
+    /// ones that didn't come back. Nothing in the app state changes.
+    pub async fn probe_symbols(&self) -> Result<Vec<FetchFailure>> {
+        let batch = self
+            .provider
+            .get_quotes(&self.symbols)
+            .await
+            .context("Couldn't probe the symbols")?;
+        Ok(batch.failures)
+    }
+
+    /// Add a symbol to watch.
+    /// For when FOMO hits and you need to track one more meme stock.
+    #[allow(dead_code)] // Interactive symbol adding - coming in v2.0 (probably)
+    pub fn add_symbol(&mut self, symbol: &str) {
+        let expanded = expand_symbol(symbol);
+        if !self
+            .symbols
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(&expanded))
+        {
+            self.symbols.push(expanded);
+        }
+    }
+
+    /// Remove a symbol from watch.
+    /// Denial is the first stage of grief. Removing it from your watchlist is the second.
+    #[allow(dead_code)] // Interactive symbol removal - for those who can't handle the truth
+    pub fn remove_symbol(&mut self, symbol: &str) {
+        let expanded = expand_symbol(symbol);
+        self.symbols.retain(|s| s != &expanded);
+        self.quotes.retain(|q| q.symbol != expanded);
+        self.clamp_selection();
+    }
 
     /// Get the currently selected quote.
     /// Returns the quote you're currently staring at in disbelief.
     #[allow(dead_code)] // Used by future detail view feature
     pub fn selected_quote(&self) -> Option<&Quote> {
-        self.quotes.get(self.selected)
+        self.display_quotes().get(self.selected).copied()
     }
 
     /// Get time since last refresh as human readable string.
@@ -331,4 +1972,1940 @@ impl App {
             None => "never".to_string(),
         }
     }
+
+    /// Get the time until the next automatic refresh, e.g. "next: 3s".
+    pub fn refresh_countdown(&self) -> String {
+        let ellipsis = if self.unicode { "…" } else { "..." };
+        let (last, delay) = self.refresh_schedule();
+        countdown_label(last, delay, Instant::now(), ellipsis)
+    }
+}
+
+/// Show a notification unless it's quiet hours. Watchers and rules still
+/// track crossings during the window, so nothing stale fires when it ends.
+fn notify_unless_quiet(
+    notifier: &dyn Notifier,
+    quiet: Option<QuietHours>,
+    title: &str,
+    body: &str,
+) {
+    if quiet.is_some_and(|q| q.contains(Local::now().time())) {
+        return;
+    }
+    // Best effort: a missing notify-send shouldn't take the app down
+    let _ = notifier.notify(title, body);
+}
+
+/// Pull quotes without a usable price out of `quotes`, as failures.
+/// Yahoo sometimes resolves a symbol and then has nothing to say about it.
+fn take_invalid_quotes(quotes: &mut Vec<Quote>) -> Vec<FetchFailure> {
+    let mut invalid = Vec::new();
+    quotes.retain(|quote| {
+        let valid = quote.price.is_finite() && quote.price > 0.0;
+        if !valid {
+            invalid.push(FetchFailure {
+                symbol: quote.symbol.clone(),
+                reason: "No price (bad data or unsupported symbol)".to_string(),
+            });
+        }
+        valid
+    });
+    invalid
+}
+
+/// Countdown until `last_refresh + interval`, clamped at zero.
+/// No last refresh means one is due right now (startup or a forced refresh).
+fn countdown_label(
+    last_refresh: Option<Instant>,
+    interval: Duration,
+    now: Instant,
+    ellipsis: &str,
+) -> String {
+    match last_refresh {
+        Some(last) => {
+            let remaining = (last + interval).saturating_duration_since(now);
+            format!("next: {}s", remaining.as_secs_f64().ceil() as u64)
+        }
+        None => format!("refreshing{}", ellipsis),
+    }
+}
+
+/// What a footer prompt is asking for.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PromptKind {
+    /// Reference price for a symbol
+    ReferencePrice(String),
+    /// Free-text note for a symbol
+    Note(String),
+    /// Symbol/name search
+    Search,
+    /// Save unsaved changes before quitting? Answered with y/n, not Enter
+    ConfirmQuit,
+}
+
+/// A line of text being typed in the footer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub buffer: String,
+}
+
+impl Prompt {
+    /// Label shown before the typed text.
+    pub fn label(&self) -> String {
+        match &self.kind {
+            PromptKind::ReferencePrice(symbol) => {
+                format!("Reference price for {} (empty clears): ", symbol)
+            }
+            PromptKind::Note(symbol) => format!("Note for {} (empty clears): ", symbol),
+            PromptKind::Search => "/".to_string(),
+            PromptKind::ConfirmQuit => {
+                "Unsaved reference prices, notes or refresh interval. Save to config before quitting? (y/n, Esc stays) "
+                    .to_string()
+            }
+        }
+    }
+}
+
+/// Byte range of the first ASCII-case-insensitive match of `needle` in
+/// `haystack`. Case folding never changes byte lengths this way, so the
+/// range can be used to slice the original text.
+pub fn find_ignore_case(haystack: &str, needle: &str) -> Option<(usize, usize)> {
+    if needle.is_empty() {
+        return None;
+    }
+    haystack.char_indices().find_map(|(start, _)| {
+        let end = start + needle.len();
+        let candidate = haystack.get(start..end)?;
+        candidate
+            .eq_ignore_ascii_case(needle)
+            .then_some((start, end))
+    })
+}
+
+/// Pair quotes with their weight. Market-cap weighting skips quotes without a cap.
+fn weigh<'a>(
+    quotes: impl IntoIterator<Item = &'a Quote>,
+    weighting: Weighting,
+) -> Vec<(f64, &'a Quote)> {
+    quotes
+        .into_iter()
+        .filter_map(|q| match weighting {
+            Weighting::Equal => Some((1.0, q)),
+            Weighting::MarketCap => q.market_cap.map(|cap| (cap as f64, q)),
+        })
+        .collect()
+}
+
+/// Raise a requested refresh interval to `floor`, saying so when it had to.
+fn clamp_refresh(name: &str, requested: f64, floor: f64) -> f64 {
+    if requested < floor {
+        eprintln!(
+            "Warning: {} of {}s is below the {}s minimum, using {}s",
+            name, requested, floor, floor
+        );
+    }
+    requested.max(floor)
+}
+
+/// Scale an interval by a random factor in `[1 - jitter, 1 + jitter]`.
+/// `unit` is a uniform sample from `[0, 1)`.
+fn jittered(interval: Duration, jitter: f64, unit: f64) -> Duration {
+    let factor = 1.0 + jitter * (2.0 * unit - 1.0);
+    interval.mul_f64(factor.max(0.0))
+}
+
+/// A uniform-ish sample from `[0, 1)`.
+/// Good enough to spread refreshes around; don't use it for crypto (the other kind).
+fn random_unit() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+
+    // RandomState is seeded randomly per instance, which is all we need here
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn test_app(args: &[&str]) -> App {
+        let mut argv = vec!["stonktop"];
+        argv.extend_from_slice(args);
+        App::new(&Args::parse_from(argv), &Config::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_repeated_total_failure_goes_offline() {
+        use crate::provider::{FixtureProvider, FixtureQuote};
+
+        let mut app = test_app(&[]);
+        app.provider = Arc::new(FixtureProvider::new(vec![]));
+        app.symbols = vec!["NOPE".to_string()];
+
+        app.refresh().await.unwrap();
+        app.refresh().await.unwrap();
+        assert_eq!(app.failed_refreshes, 2);
+        assert!(app.offline.is_none());
+
+        app.refresh().await.unwrap();
+        let offline = app.offline.clone().unwrap();
+        assert!(offline.contains("3 refreshes"));
+        assert!(offline.contains("Not in fixture"));
+        assert!(offline.contains("network"));
+
+        // A key press clears transient errors, not the outage
+        app.error = Some("blip".to_string());
+        assert!(app.dismiss_error());
+        assert!(!app.dismiss_error());
+        assert!(app.offline.is_some());
+
+        // One good refresh brings us back
+        let fixture: FixtureQuote =
+            serde_json::from_str(r#"{"symbol": "NOPE", "price": 1.0}"#).unwrap();
+        app.provider = Arc::new(FixtureProvider::new(vec![fixture]));
+        app.refresh().await.unwrap();
+        assert_eq!(app.failed_refreshes, 0);
+        assert!(app.offline.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_escalation_can_be_disabled() {
+        use crate::provider::FixtureProvider;
+
+        let mut app = test_app(&[]);
+        app.provider = Arc::new(FixtureProvider::new(vec![]));
+        app.symbols = vec!["NOPE".to_string()];
+        app.max_failed_refreshes = 0;
+
+        for _ in 0..5 {
+            app.refresh().await.unwrap();
+        }
+        assert_eq!(app.failed_refreshes, 5);
+        assert!(app.offline.is_none());
+    }
+
+    #[test]
+    fn test_zero_delay_clamped_to_minimum() {
+        let app = test_app(&["-d", "0"]);
+        assert_eq!(app.refresh_interval, Duration::from_secs(1));
+
+        let mut config = Config::default();
+        config.general.min_refresh_interval = 3.0;
+        config.general.holdings_refresh_interval = Some(2.0);
+        let app = App::new(&Args::parse_from(["stonktop", "-d", "0"]), &config).unwrap();
+        assert_eq!(app.refresh_interval, Duration::from_secs(3));
+        assert_eq!(app.holdings_refresh_interval, Some(Duration::from_secs(3)));
+
+        // The floor can be raised but not lowered
+        config.general.min_refresh_interval = 0.0;
+        let app = App::new(&Args::parse_from(["stonktop", "-d", "0"]), &config).unwrap();
+        assert_eq!(app.refresh_interval, Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_background_refresh_applies_through_channel() {
+        use crate::provider::{FixtureProvider, FixtureQuote};
+
+        let fixture: FixtureQuote =
+            serde_json::from_str(r#"{"symbol": "AAPL", "price": 150.0}"#).unwrap();
+        let mut app = test_app(&[]);
+        app.provider = Arc::new(FixtureProvider::new(vec![fixture]));
+        app.symbols = vec!["AAPL".to_string()];
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        assert!(app.needs_refresh());
+        app.spawn_refresh(&tx);
+        assert!(app.fetching_since.is_some());
+        // One fetch at a time
+        assert!(!app.needs_refresh());
+        app.spawn_refresh(&tx);
+
+        // Nothing changes until the result is applied
+        assert!(app.quotes.is_empty());
+        let result = rx.recv().await.unwrap();
+        app.apply_fetch(result);
+        assert!(rx.try_recv().is_err());
+
+        assert!(app.fetching_since.is_none());
+        assert_eq!(app.quotes[0].symbol, "AAPL");
+        assert_eq!(app.iteration, 1);
+        assert!(!app.needs_refresh());
+
+        // Errors land the same way
+        app.apply_fetch(Err(anyhow::anyhow!("connection reset")));
+        assert!(app.error.as_deref().unwrap().contains("connection reset"));
+    }
+
+    #[tokio::test]
+    async fn test_group_switch_needs_no_fetch() {
+        use crate::provider::{FixtureProvider, FixtureQuote, QuoteProvider};
+        use futures::future::BoxFuture;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Fixture quotes, counting every fetch that reaches them.
+        struct CountingProvider {
+            inner: FixtureProvider,
+            fetches: AtomicUsize,
+        }
+
+        impl QuoteProvider for CountingProvider {
+            fn get_quotes<'a>(
+                &'a self,
+                symbols: &'a [String],
+            ) -> BoxFuture<'a, Result<QuoteBatch>> {
+                self.fetches.fetch_add(1, Ordering::SeqCst);
+                self.inner.get_quotes(symbols)
+            }
+        }
+
+        // What the event loop does each pass
+        async fn poll(app: &mut App) {
+            if app.needs_refresh() {
+                app.refresh().await.unwrap();
+            }
+        }
+
+        let mut config = Config::default();
+        config
+            .groups
+            .insert("tech".to_string(), vec!["AAPL".to_string()]);
+        config
+            .groups
+            .insert("crypto".to_string(), vec!["BTC-USD".to_string()]);
+        let mut app = App::new(&Args::parse_from(["stonktop"]), &config).unwrap();
+        let fixtures: Vec<FixtureQuote> = serde_json::from_str(
+            r#"[{"symbol": "AAPL", "price": 150.0},
+                {"symbol": "BTC-USD", "price": 50000.0}]"#,
+        )
+        .unwrap();
+        let provider = Arc::new(CountingProvider {
+            inner: FixtureProvider::new(fixtures),
+            fetches: AtomicUsize::new(0),
+        });
+        app.provider = provider.clone();
+
+        // Every group comes back in the one startup fetch
+        poll(&mut app).await;
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(app.quotes.len(), 2);
+
+        // Flipping through the groups, inactive ones included, asks for nothing
+        for group in [1, 0, 1] {
+            app.apply_action(Action::NextGroup);
+            assert_eq!(app.active_group, group);
+            poll(&mut app).await;
+            assert_eq!(app.quotes.len(), 2);
+        }
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 1);
+
+        // The next fetch is the scheduled one, and there's exactly one of it
+        app.last_refresh = Some(Instant::now() - app.refresh_interval * 2);
+        poll(&mut app).await;
+        poll(&mut app).await;
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_holdings_view_refreshes_only_held_symbols() {
+        use crate::provider::{FixtureProvider, FixtureQuote};
+
+        let fixtures: Vec<FixtureQuote> = serde_json::from_str(
+            r#"[{"symbol": "AAPL", "price": 150.0, "step": 1.0},
+                {"symbol": "MSFT", "price": 300.0, "step": 1.0}]"#,
+        )
+        .unwrap();
+        let mut app = test_app(&[]);
+        app.provider = Arc::new(FixtureProvider::new(fixtures));
+        app.symbols = vec!["AAPL".to_string(), "MSFT".to_string()];
+        app.holdings.insert(
+            "MSFT".to_string(),
+            Holding {
+                symbol: "MSFT".to_string(),
+                quantity: 1.0,
+                cost_basis: 1.0,
+                purchase_date: None,
+            },
+        );
+        app.set_view(View::Holdings);
+
+        // Off unless configured
+        assert_eq!(app.active_symbols(), app.symbols);
+
+        app.holdings_refresh_interval = Some(Duration::from_secs(2));
+        assert_eq!(app.active_symbols(), vec!["MSFT"]);
+        assert_eq!(app.refresh_delay(), Duration::from_secs(2));
+        app.set_view(View::Quotes);
+        assert_eq!(app.active_symbols(), app.symbols);
+        assert_eq!(app.refresh_delay(), app.next_refresh_delay);
+
+        // A full refresh first, then a holdings-only one on top of it
+        app.refresh().await.unwrap();
+        app.set_view(View::Holdings);
+        assert!(!app.needs_refresh());
+        app.last_holdings_refresh = None;
+        assert!(app.needs_refresh());
+        let full_refresh = app.last_refresh;
+        app.refresh().await.unwrap();
+
+        let price = |symbol: &str| {
+            app.quotes
+                .iter()
+                .find(|q| q.symbol == symbol)
+                .map(|q| q.price)
+        };
+        // Fixture prices step per fetch, and the second fetch only asked for MSFT
+        assert_eq!(price("AAPL"), Some(150.0));
+        assert_eq!(price("MSFT"), Some(301.0));
+        assert_eq!(app.last_refresh, full_refresh);
+        assert!(app.last_holdings_refresh.is_some());
+    }
+
+    #[test]
+    fn test_autoscroll_advances_and_wraps() {
+        let mut app = test_app(&["--autoscroll", "3"]);
+        app.quotes = vec![
+            quote("AAA", 3.0, 3.0, None),
+            quote("BBB", 2.0, 2.0, None),
+            quote("CCC", 1.0, 1.0, None),
+        ];
+        let start = app.last_autoscroll;
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        // Simulated UI ticks: nothing until the interval is up
+        assert!(!app.advance_autoscroll(at(1)));
+        assert!(app.advance_autoscroll(at(3)));
+        assert_eq!(app.selected, 1);
+        assert!(!app.advance_autoscroll(at(4)));
+        assert!(app.advance_autoscroll(at(6)));
+        assert!(app.advance_autoscroll(at(9)));
+        assert_eq!(app.selected, 0, "wraps after the last row");
+
+        // A key press ends it for good
+        app.stop_autoscroll();
+        assert!(!app.advance_autoscroll(at(60)));
+        assert_eq!(app.selected, 0);
+
+        // Off unless asked for
+        let mut app = test_app(&[]);
+        app.quotes = vec![quote("AAA", 1.0, 1.0, None), quote("BBB", 1.0, 1.0, None)];
+        assert!(!app.advance_autoscroll(Instant::now() + Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn test_synthetic_rows_follow_their_components() {
+        let mut config = Config::default();
+        config
+            .synthetics
+            .insert("ETH/BTC".to_string(), "ETH-USD/BTC-USD".to_string());
+        let args = Args::parse_from(["stonktop", "-s", "ETH-USD,AAPL"]);
+        let mut app = App::new(&args, &config).unwrap();
+        // BTC-USD is fetched for the ratio but not listed
+        assert_eq!(app.symbols, vec!["ETH-USD", "AAPL", "BTC-USD"]);
+        assert!(app.hidden_symbols.contains("BTC-USD"));
+
+        let coin = |symbol: &str, price: f64| Quote {
+            symbol: symbol.to_string(),
+            price,
+            previous_close: price,
+            change: 0.0,
+            quote_type: QuoteType::Cryptocurrency,
+            ..Default::default()
+        };
+        app.holdings.insert(
+            "ETH/BTC".to_string(),
+            Holding {
+                symbol: "ETH/BTC".to_string(),
+                quantity: 1000.0,
+                cost_basis: 1.0,
+                purchase_date: None,
+            },
+        );
+        for btc in [50_000.0, 60_000.0] {
+            app.apply_fetch(Ok(QuoteBatch {
+                quotes: vec![coin("ETH-USD", 3_000.0), coin("BTC-USD", btc)],
+                failures: Vec::new(),
+                warnings: Vec::new(),
+            }));
+        }
+
+        // One row, recomputed from the latest fetch
+        let rows: Vec<&Quote> = app.quotes.iter().filter(|q| q.synthetic).collect();
+        assert_eq!(rows.len(), 1);
+        assert!((rows[0].price - 0.05).abs() < 1e-12);
+        let shown: Vec<&str> = app
+            .display_quotes()
+            .iter()
+            .map(|q| q.symbol.as_str())
+            .collect();
+        assert!(shown.contains(&"ETH/BTC") && !shown.contains(&"BTC-USD"));
+
+        // Never part of the portfolio, even under a held name
+        assert_eq!(app.total_portfolio_value(), 0.0);
+    }
+
+    #[test]
+    fn test_filter_summary() {
+        let mut app = test_app(&["-s", "AAPL,APP,BTC-USD,MSFT"]);
+        app.quotes = ["AAPL", "APP", "BTC-USD", "MSFT"]
+            .iter()
+            .map(|s| quote(s, 0.0, 0.0, None))
+            .collect();
+        assert_eq!(app.filter_summary(), None);
+
+        app.search_query = Some("ap".to_string());
+        app.top = Some(1);
+        assert_eq!(
+            app.filter_summary().as_deref(),
+            Some("Showing 1 of 4 (search: 'ap', top: 1)")
+        );
+
+        app.quotes[2].quote_type = QuoteType::Cryptocurrency;
+        app.search_query = None;
+        app.top = None;
+        app.apply_action(Action::SetFilter(Some(FilterType::Crypto)));
+        assert_eq!(
+            app.filter_summary().as_deref(),
+            Some("Showing 1 of 4 (type: crypto)")
+        );
+
+        // Counted against the rows the view would have, not ones it never shows
+        app.hidden_symbols.insert("MSFT".to_string());
+        app.exchange_filter = Some("US".to_string());
+        assert_eq!(
+            app.filter_summary().as_deref(),
+            Some("Showing 1 of 3 (exchange: US, type: crypto)")
+        );
+    }
+
+    #[test]
+    fn test_selection_is_clamped_to_visible_rows() {
+        let mut app = test_app(&["--top", "2"]);
+        app.quotes = vec![
+            quote("AAA", 3.0, 3.0, None),
+            quote("BTC-USD", 2.0, 2.0, None),
+            quote("CCC", 1.0, 1.0, None),
+        ];
+        app.quotes[1].quote_type = QuoteType::Cryptocurrency;
+
+        // --top caps both the rows and how far the selection can go
+        assert_eq!(app.display_quotes().len(), 2);
+        app.select_bottom();
+        assert_eq!(app.selected, 1);
+        app.select_down();
+        assert_eq!(app.selected, 1);
+
+        // A filter that leaves one row pulls the selection back onto it
+        app.apply_action(Action::SetFilter(Some(FilterType::Crypto)));
+        assert_eq!(app.selected, 0);
+        assert_eq!(app.selected_quote().unwrap().symbol, "BTC-USD");
+
+        // A refresh that comes back shorter does the same
+        app.apply_action(Action::SetFilter(None));
+        app.selected = 1;
+        app.show_detail = true;
+        app.apply_fetch(Ok(QuoteBatch {
+            quotes: vec![quote("AAA", 3.0, 3.0, None)],
+            failures: Vec::new(),
+            warnings: Vec::new(),
+        }));
+        assert_eq!(app.selected, 0);
+        assert!(app.show_detail);
+
+        // And with nothing left, the detail view has nothing to show
+        app.apply_action(Action::SetFilter(Some(FilterType::Etf)));
+        assert_eq!(app.selected, 0);
+        assert!(!app.show_detail);
+        assert!(app.selected_quote().is_none());
+    }
+
+    #[test]
+    fn test_hide_invalid_moves_priceless_quotes_to_failures() {
+        let batch = || QuoteBatch {
+            quotes: vec![
+                quote("AAA", 1.0, 1.0, None),
+                Quote {
+                    symbol: "ZERO".to_string(),
+                    ..Default::default()
+                },
+                Quote {
+                    symbol: "NAN".to_string(),
+                    price: f64::NAN,
+                    ..Default::default()
+                },
+            ],
+            failures: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        // Shown by default, so broken data gets noticed
+        let mut app = test_app(&[]);
+        app.apply_fetch(Ok(batch()));
+        assert_eq!(app.quotes.len(), 3);
+        assert!(app.failures.is_empty());
+
+        let mut app = test_app(&["--hide-invalid"]);
+        app.apply_fetch(Ok(batch()));
+        assert_eq!(app.quotes.len(), 1);
+        assert_eq!(app.quotes[0].symbol, "AAA");
+        let failed: Vec<&str> = app.failures.iter().map(|f| f.symbol.as_str()).collect();
+        assert_eq!(failed, vec!["ZERO", "NAN"]);
+        // Bad data isn't an outage
+        assert_eq!(app.failed_refreshes, 0);
+    }
+
+    #[test]
+    fn test_search_filters_as_you_type() {
+        let mut app = test_app(&[]);
+        app.quotes = vec![
+            quote("AAPL", 1.0, 1.0, None),
+            quote("MSFT", 1.0, 1.0, None),
+            quote("GOOGL", 1.0, 1.0, None),
+        ];
+        app.quotes[1].name = "Microsoft Corporation".to_string();
+        app.selected = 2;
+
+        app.apply_action(Action::Search);
+        for c in "micro".chars() {
+            app.prompt.as_mut().unwrap().buffer.push(c);
+            app.prompt_changed();
+        }
+        assert_eq!(app.search_query.as_deref(), Some("micro"));
+        assert_eq!(app.display_quotes().len(), 1);
+        assert_eq!(app.selected_quote().unwrap().symbol, "MSFT");
+
+        // Enter keeps the filter, reopening starts from it, Esc clears it
+        app.submit_prompt();
+        assert_eq!(app.display_quotes().len(), 1);
+        app.apply_action(Action::Search);
+        assert_eq!(app.prompt.as_ref().unwrap().buffer, "micro");
+        app.cancel_prompt();
+        assert!(app.search_query.is_none());
+        assert_eq!(app.display_quotes().len(), 3);
+    }
+
+    #[test]
+    fn test_switching_sort_field_uses_its_default_direction() {
+        let mut app = test_app(&[]);
+        app.set_sort_order(SortOrder::Name);
+        assert_eq!(
+            app.active_sort(),
+            (SortOrder::Name, SortDirection::Ascending)
+        );
+        app.set_sort_order(SortOrder::Volume);
+        assert_eq!(
+            app.active_sort(),
+            (SortOrder::Volume, SortDirection::Descending)
+        );
+        // Picking the same field again still flips it
+        app.set_sort_order(SortOrder::Volume);
+        assert_eq!(
+            app.active_sort(),
+            (SortOrder::Volume, SortDirection::Ascending)
+        );
+        app.apply_action(Action::SortColumn(1));
+        assert_eq!(
+            app.active_sort(),
+            (SortOrder::Symbol, SortDirection::Ascending)
+        );
+    }
+
+    #[test]
+    fn test_color_basis_benchmark() {
+        let mut config = Config::default();
+        config.display.color_basis = ColorBasis::Benchmark;
+        let mut app =
+            App::new(&Args::parse_from(["stonktop", "-s", "AAPL,MSFT"]), &config).unwrap();
+        // The benchmark gets fetched for it
+        assert_eq!(app.benchmark.as_deref(), Some("^GSPC"));
+
+        // Nothing to compare against yet: plain up/down
+        app.quotes = vec![quote("AAPL", 0.5, 0.5, None)];
+        assert_eq!(app.color_change(&app.quotes[0]), 0.5);
+
+        app.quotes = vec![
+            quote("AAPL", 0.5, 0.5, None),
+            quote("MSFT", -1.0, -1.0, None),
+            quote("^GSPC", 2.0, 2.0, None),
+        ];
+        let colored: Vec<f64> = app.quotes.iter().map(|q| app.color_change(q)).collect();
+        // Up half a percent on a +2% day is a red row; the benchmark is neutral
+        assert_eq!(colored, vec![-1.5, -3.0, 0.0]);
+
+        app.quotes[2].change_percent = -2.0;
+        assert_eq!(app.color_change(&app.quotes[1]), 1.0);
+
+        app.color_basis = ColorBasis::Absolute;
+        assert_eq!(app.color_change(&app.quotes[1]), -1.0);
+    }
+
+    #[test]
+    fn test_relative_strength_against_benchmark() {
+        let mut app = test_app(&["-s", "AAPL,MSFT", "--relative-strength"]);
+        assert_eq!(app.benchmark.as_deref(), Some("^GSPC"));
+        assert_eq!(app.symbols, vec!["AAPL", "MSFT", "^GSPC"]);
+
+        app.quotes = vec![
+            quote("AAPL", 2.0, 2.5, None),
+            quote("MSFT", -1.0, -0.5, None),
+            quote("^GSPC", 0.5, 1.0, None),
+        ];
+        let rs: Vec<Option<f64>> = app
+            .quotes
+            .iter()
+            .map(|q| app.relative_strength(q))
+            .collect();
+        assert_eq!(rs, vec![Some(1.5), Some(-1.5), None]);
+
+        // Fetched, but not a row
+        let shown: Vec<&str> = app
+            .display_quotes()
+            .iter()
+            .map(|q| q.symbol.as_str())
+            .collect();
+        assert_eq!(shown, vec!["AAPL", "MSFT"]);
+
+        // Nothing to compare against until the benchmark arrives
+        app.quotes.pop();
+        assert_eq!(app.relative_strength(&app.quotes[0]), None);
+
+        // A watched benchmark stays visible, and is off by default
+        let app = test_app(&["-s", "AAPL,^GSPC", "--benchmark", "^GSPC"]);
+        assert!(app.hidden_symbols.is_empty());
+        assert_eq!(test_app(&["-s", "AAPL"]).benchmark, None);
+    }
+
+    #[test]
+    fn test_missing_symbols_keep_last_price_then_show_no_data() {
+        let mut app = test_app(&["-s", "AAPL,GONE"]);
+        app.holdings.insert(
+            "GONE".to_string(),
+            Holding {
+                symbol: "GONE".to_string(),
+                quantity: 10.0,
+                cost_basis: 1.0,
+                purchase_date: None,
+            },
+        );
+        let batch = |quotes: Vec<Quote>| {
+            Ok(QuoteBatch {
+                quotes,
+                failures: Vec::new(),
+                warnings: Vec::new(),
+            })
+        };
+
+        app.apply_fetch(batch(vec![
+            quote("AAPL", 1.0, 1.0, None),
+            quote("GONE", 1.0, 1.0, None),
+        ]));
+        assert!(app.missing_streak.is_empty());
+        assert_eq!(app.total_portfolio_value(), 1010.0);
+
+        // Delisted: the last price stays in the totals, marked stale
+        for refresh in 1..=3 {
+            app.apply_fetch(batch(vec![quote("AAPL", 2.0, 2.0, None)]));
+            assert_eq!(app.missing_streak["GONE"], refresh);
+            assert!(app.is_carried("GONE"));
+            assert_eq!(app.total_portfolio_value(), 1010.0);
+            assert_eq!(app.stale_holdings(), vec!["GONE"]);
+            let listed = if refresh < 3 { vec![] } else { vec!["GONE"] };
+            assert_eq!(app.no_data_symbols(), listed);
+        }
+        assert_eq!(app.price_history["GONE"], vec![101.0]);
+
+        // A total outage is the offline banner's business, not a delisting
+        app.apply_fetch(batch(Vec::new()));
+        assert_eq!(app.missing_streak["GONE"], 3);
+
+        // Back from the dead
+        app.apply_fetch(batch(vec![
+            quote("AAPL", 2.0, 2.0, None),
+            quote("GONE", 3.0, 3.0, None),
+        ]));
+        assert!(app.missing_streak.is_empty());
+        assert!(app.no_data_symbols().is_empty());
+        assert_eq!(app.total_portfolio_value(), 1030.0);
+    }
+
+    #[test]
+    fn test_display_symbol_compacts_crypto_pairs() {
+        let mut app = test_app(&["--compact-crypto"]);
+        assert_eq!(app.display_symbol("BTC-USD"), "BTC");
+        // Priced in something else: the suffix is the point
+        assert_eq!(app.display_symbol("BTC-EUR"), "BTC-EUR");
+        assert_eq!(app.display_symbol("ETH-BTC"), "ETH-BTC");
+        assert_eq!(app.display_symbol("AAPL"), "AAPL");
+        assert_eq!(app.display_symbol("BRK-B"), "BRK-B");
+        assert_eq!(app.display_symbol("-USD"), "-USD");
+
+        // The display currency decides which suffix goes
+        let app_eur = test_app(&["--compact-crypto", "--currency", "eur"]);
+        assert_eq!(app_eur.display_symbol("BTC-EUR"), "BTC");
+        assert_eq!(app_eur.display_symbol("BTC-USD"), "BTC-USD");
+
+        app.compact_crypto = false;
+        assert_eq!(app.display_symbol("BTC-USD"), "BTC-USD");
+    }
+
+    #[test]
+    fn test_session_change() {
+        let mut app = test_app(&[]);
+        app.session_open.insert("AAPL".to_string(), 100.0);
+        let aapl = quote("AAPL", 10.0, 10.0, None);
+        assert!((app.session_change(&aapl).unwrap() - 10.0).abs() < 1e-9);
+        assert_eq!(app.session_change(&quote("MSFT", 1.0, 1.0, None)), None);
+
+        // The first fetch sets the baseline and later ones leave it alone
+        app.apply_fetch(Ok(QuoteBatch {
+            quotes: vec![quote("MSFT", 1.0, 1.0, None)],
+            failures: Vec::new(),
+            warnings: Vec::new(),
+        }));
+        app.apply_fetch(Ok(QuoteBatch {
+            quotes: vec![quote("MSFT", 21.0, 21.0, None), aapl.clone()],
+            failures: Vec::new(),
+            warnings: Vec::new(),
+        }));
+        assert_eq!(app.session_open["MSFT"], 101.0);
+        assert_eq!(app.session_open["AAPL"], 100.0);
+
+        // Reset starts over from where things are now
+        app.apply_action(Action::ResetSession);
+        assert_eq!(app.session_open["MSFT"], 121.0);
+        assert_eq!(app.session_change(&aapl), Some(0.0));
+    }
+
+    fn quote(symbol: &str, change: f64, change_percent: f64, market_cap: Option<u64>) -> Quote {
+        Quote {
+            symbol: symbol.to_string(),
+            price: 100.0 + change,
+            change,
+            change_percent,
+            market_cap,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_min_change_pct_filters_against_last_printed() {
+        let mut app = test_app(&["--min-change-pct", "1"]);
+        assert!(app.batch_mode);
+        let set = |app: &mut App, aapl: f64, msft: f64| {
+            app.quotes = vec![quote("AAPL", aapl - 100.0, 0.0, None)];
+            app.quotes.push(quote("MSFT", msft - 100.0, 0.0, None));
+        };
+        let printed = |app: &mut App| {
+            let mut rows: Vec<String> = app.batch_rows_to_print().unwrap().into_iter().collect();
+            rows.sort();
+            rows
+        };
+
+        // Everything the first time, as the baseline
+        set(&mut app, 100.0, 100.0);
+        assert_eq!(printed(&mut app), vec!["AAPL", "MSFT"]);
+
+        // Micro-noise stays quiet
+        set(&mut app, 100.5, 99.5);
+        assert!(printed(&mut app).is_empty());
+
+        // Drift adds up against the last printed price, not the last fetch
+        set(&mut app, 101.0, 99.5);
+        assert_eq!(printed(&mut app), vec!["AAPL"]);
+
+        // AAPL's baseline moved to 101, so 101.5 isn't enough; MSFT's is still 100
+        set(&mut app, 101.5, 98.9);
+        assert_eq!(printed(&mut app), vec!["MSFT"]);
+
+        // Off by default
+        assert_eq!(test_app(&[]).batch_rows_to_print(), None);
+    }
+
+    #[test]
+    fn test_since_launch_baseline_for_late_symbols() {
+        let mut app = test_app(&["--since-launch"]);
+        assert!(app.show_since_launch);
+        let fetch = |app: &mut App, quotes: Vec<Quote>| {
+            app.apply_fetch(Ok(QuoteBatch {
+                quotes,
+                ..Default::default()
+            }))
+        };
+
+        fetch(&mut app, vec![quote("AAPL", 0.0, 0.0, None)]);
+        // MSFT joins the watchlist mid-session at 110
+        fetch(
+            &mut app,
+            vec![
+                quote("AAPL", 5.0, 5.0, None),
+                quote("MSFT", 10.0, 10.0, None),
+            ],
+        );
+        let aapl = quote("AAPL", 20.0, 20.0, None);
+        let msft = quote("MSFT", 21.0, 21.0, None);
+        fetch(&mut app, vec![aapl.clone(), msft.clone()]);
+
+        // Each measured from its own first sighting, not from launch
+        assert!((app.session_change(&aapl).unwrap() - 20.0).abs() < 1e-9);
+        assert!((app.session_change(&msft).unwrap() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_price_history_is_capped_and_scoped() {
+        let mut app = test_app(&[]);
+        for i in 0..PRICE_HISTORY_LEN + 5 {
+            app.apply_fetch(Ok(QuoteBatch {
+                quotes: vec![
+                    quote("AAPL", i as f64, 0.0, None),
+                    quote("MSFT", 0.0, 0.0, None),
+                ],
+                ..Default::default()
+            }));
+        }
+        let aapl = &app.price_history["AAPL"];
+        assert_eq!(aapl.len(), PRICE_HISTORY_LEN);
+        assert_eq!(aapl.first(), Some(&105.0));
+        assert_eq!(aapl.last(), Some(&(104.0 + PRICE_HISTORY_LEN as f64)));
+
+        // A holdings-only refresh leaves the other symbols' histories alone
+        app.fetch_scope = Some(vec!["AAPL".to_string()]);
+        app.apply_fetch(Ok(QuoteBatch {
+            quotes: vec![quote("AAPL", 1.0, 0.0, None)],
+            ..Default::default()
+        }));
+        assert_eq!(app.price_history["AAPL"].last(), Some(&101.0));
+        assert_eq!(app.price_history["MSFT"].len(), PRICE_HISTORY_LEN);
+    }
+
+    #[test]
+    fn test_apply_start_sequence() {
+        let mut app = test_app(&[]);
+        let actions =
+            crate::action::parse_start_sequence("sort=price;view=dashboard;filter=crypto").unwrap();
+        app.apply_start_sequence(&actions);
+
+        assert_eq!(app.sort_order, SortOrder::Price);
+        assert_eq!(app.active_view(), View::Holdings);
+        assert_eq!(app.type_filter, Some(FilterType::Crypto));
+
+        app.quotes = vec![
+            Quote {
+                symbol: "AAPL".to_string(),
+                ..Default::default()
+            },
+            Quote {
+                symbol: "BTC-USD".to_string(),
+                quote_type: QuoteType::Cryptocurrency,
+                ..Default::default()
+            },
+        ];
+        // The holdings view only lists held symbols, so hold both
+        for quote in &app.quotes {
+            app.holdings.insert(
+                quote.symbol.clone(),
+                Holding {
+                    symbol: quote.symbol.clone(),
+                    quantity: 1.0,
+                    cost_basis: 1.0,
+                    purchase_date: None,
+                },
+            );
+        }
+        let shown: Vec<&str> = app
+            .display_quotes()
+            .iter()
+            .map(|q| q.symbol.as_str())
+            .collect();
+        assert_eq!(shown, vec!["BTC-USD"]);
+    }
+
+    #[test]
+    fn test_reference_price_change_and_clearing() {
+        let mut app = test_app(&["-s", "AAPL"]);
+        app.quotes = vec![quote("AAPL", 10.0, 10.0, None)]; // price 110
+        assert_eq!(app.reference_change(&app.quotes[0]), None);
+
+        app.apply_action(Action::EditReference);
+        app.prompt.as_mut().unwrap().buffer = "100".to_string();
+        app.submit_prompt();
+        assert_eq!(app.reference_prices.get("AAPL"), Some(&100.0));
+        assert_eq!(app.reference_change(&app.quotes[0]), Some(10.0));
+
+        // Reopening prefills the current value; submitting empty clears it
+        app.start_reference_prompt();
+        assert_eq!(app.prompt.as_ref().unwrap().buffer, "100");
+        app.prompt.as_mut().unwrap().buffer.clear();
+        app.submit_prompt();
+        assert!(app.reference_prices.is_empty());
+        assert!(app.prompt.is_none());
+    }
+
+    #[test]
+    fn test_note_lookup_and_editing() {
+        let mut config = Config::default();
+        config.notes.push(crate::config::NoteConfig {
+            symbol: "btc".to_string(),
+            text: "Digital gold, allegedly".to_string(),
+        });
+        let mut app = App::new(&Args::parse_from(["stonktop"]), &config).unwrap();
+        // Looked up by the expanded symbol, like everything else
+        assert_eq!(app.note("BTC-USD"), Some("Digital gold, allegedly"));
+        assert_eq!(app.note("AAPL"), None);
+
+        app.quotes = vec![Quote {
+            symbol: "BTC-USD".to_string(),
+            ..Default::default()
+        }];
+        app.apply_action(Action::EditNote);
+        let prompt = app.prompt.as_mut().unwrap();
+        assert_eq!(prompt.buffer, "Digital gold, allegedly");
+        prompt.buffer = "  HODL  ".to_string();
+        app.submit_prompt();
+        assert_eq!(app.note("BTC-USD"), Some("HODL"));
+        assert!(app.dirty);
+
+        app.apply_action(Action::EditNote);
+        app.prompt.as_mut().unwrap().buffer.clear();
+        app.submit_prompt();
+        assert_eq!(app.note("BTC-USD"), None);
+    }
+
+    #[test]
+    fn test_invalid_reference_price_is_rejected() {
+        let mut app = test_app(&["-s", "AAPL"]);
+        app.quotes = vec![quote("AAPL", 0.0, 0.0, None)];
+        app.start_reference_prompt();
+        app.prompt.as_mut().unwrap().buffer = "abc".to_string();
+        app.submit_prompt();
+        assert!(app.reference_prices.is_empty());
+        assert!(app.error.as_deref().unwrap().contains("abc"));
+        assert_eq!(app.prompt.as_ref().unwrap().buffer, "abc");
+
+        // A lone decimal point is not a price either
+        app.error = None;
+        app.prompt.as_mut().unwrap().buffer = ".".to_string();
+        app.submit_prompt();
+        assert_eq!(app.error.as_deref(), Some("Invalid price: ."));
+        assert!(
+            app.prompt.is_some(),
+            "the prompt stays open to fix the typo"
+        );
+
+        // Fixing it goes through as usual
+        app.error = None;
+        app.prompt.as_mut().unwrap().buffer = ".5".to_string();
+        app.submit_prompt();
+        assert_eq!(app.reference_prices.get("AAPL"), Some(&0.5));
+        assert!(app.error.is_none());
+        assert!(app.prompt.is_none());
+    }
+
+    #[test]
+    fn test_quit_asks_first_with_unsaved_changes() {
+        let mut app = test_app(&["-s", "AAPL"]);
+        app.quotes = vec![quote("AAPL", 0.0, 0.0, None)];
+
+        // Nothing changed: straight out
+        assert!(!app.dirty);
+        app.apply_action(Action::Quit);
+        assert!(app.should_quit());
+
+        let mut app = test_app(&["-s", "AAPL"]);
+        app.quotes = vec![quote("AAPL", 0.0, 0.0, None)];
+        app.start_reference_prompt();
+        app.prompt.as_mut().unwrap().buffer = "95".to_string();
+        app.submit_prompt();
+        assert!(app.dirty);
+
+        app.apply_action(Action::Quit);
+        assert!(!app.should_quit());
+        assert_eq!(app.prompt.as_ref().unwrap().kind, PromptKind::ConfirmQuit);
+        // Enter doesn't count as an answer, Esc stays
+        app.submit_prompt();
+        assert!(app.prompt.is_some());
+        app.cancel_prompt();
+        assert!(app.prompt.is_none() && !app.should_quit());
+
+        // Saving writes the reference prices and notes out with the rest
+        app.set_note("AAPL", Some("Entry after earnings".to_string()));
+        let dir = std::env::temp_dir().join(format!("stonktop-quit-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        app.apply_action(Action::Quit);
+        app.answer_quit(true);
+        assert!(app.should_quit());
+        assert!(app.persist_all(&path).is_empty());
+        let loaded = Config::load(&path).unwrap();
+        let saved = loaded.watchlist.reference_prices;
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(saved.get("AAPL"), Some(&95.0));
+        assert_eq!(loaded.notes[0].text, "Entry after earnings");
+
+        // And it's back next time
+        let mut config = Config::default();
+        config.watchlist.reference_prices = saved;
+        let app = App::new(&Args::parse_from(["stonktop"]), &config).unwrap();
+        assert_eq!(app.reference_prices.get("AAPL"), Some(&95.0));
+        assert!(!app.dirty);
+    }
+
+    #[test]
+    fn test_persist_all_survives_unwritable_directory() {
+        let mut app = test_app(&["-s", "AAPL"]);
+        app.portfolio_value_history.push((Utc::now(), 100.0));
+        app.favorites.insert("AAPL".to_string());
+        app.favorites_changed = true;
+        app.set_note("AAPL", Some("hold".to_string()));
+        app.step_refresh_interval(false);
+        app.answer_quit(true);
+
+        // A file where the directory should be: no write can succeed, as any user
+        let blocker = std::env::temp_dir().join(format!("stonktop-ro-{}", std::process::id()));
+        std::fs::write(&blocker, b"").unwrap();
+        let errors = app.persist_all(&blocker.join("config.toml"));
+        std::fs::remove_file(&blocker).ok();
+
+        // Every write is tried and reported, none of them takes the others down
+        assert_eq!(errors.len(), 5, "{:?}", errors);
+        assert!(app.should_quit());
+    }
+
+    #[test]
+    fn test_quit_never_asks_in_secure_or_batch_mode() {
+        for flag in ["--secure", "-b"] {
+            let mut app = test_app(&[flag]);
+            app.set_reference_price("AAPL", Some(1.0));
+            assert!(app.dirty);
+            app.apply_action(Action::Quit);
+            assert!(app.should_quit(), "{flag}");
+            assert!(app.prompt.is_none());
+        }
+
+        // Or when told not to, and then the edits are kept, not dropped
+        let mut app = test_app(&["-s", "AAPL"]);
+        app.confirm_quit = false;
+        app.set_reference_price("AAPL", Some(95.0));
+        app.set_note("AAPL", Some("Entry after earnings".to_string()));
+        app.apply_action(Action::Quit);
+        assert!(app.should_quit());
+        assert!(app.prompt.is_none());
+
+        let dir = std::env::temp_dir().join(format!("stonktop-noconfirm-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        assert!(app.persist_all(&path).is_empty());
+        let loaded = Config::load(&path).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(loaded.watchlist.reference_prices.get("AAPL"), Some(&95.0));
+        assert_eq!(loaded.notes[0].text, "Entry after earnings");
+    }
+
+    #[test]
+    fn test_number_hotkeys_follow_the_active_view() {
+        let mut app = test_app(&["-s", "AAPL,MSFT,TSLA"]);
+        app.holdings = [holding("AAPL", 10.0, 50.0), holding("MSFT", 1.0, 200.0)]
+            .into_iter()
+            .map(|h| (h.symbol.clone(), h))
+            .collect();
+        app.quotes = vec![
+            quote("AAPL", 0.0, 0.0, None), // price 100, P/L% +100
+            quote("MSFT", 0.0, 0.0, None), // price 100, P/L% -50
+            quote("TSLA", 5.0, 5.0, None), // no position
+        ];
+
+        // Quotes view: 6 is volume, as labelled
+        app.apply_action(Action::SortColumn(6));
+        assert_eq!(app.sort_order, SortOrder::Volume);
+
+        // Holdings view: 8 is P/L%, positions first
+        app.set_view(View::Holdings);
+        app.apply_action(Action::SortColumn(8));
+        assert_eq!(app.holdings_sort_order, SortOrder::ProfitLossPercent);
+        let order: Vec<&str> = app.quotes.iter().map(|q| q.symbol.as_str()).collect();
+        assert_eq!(order, vec!["AAPL", "MSFT", "TSLA"]);
+
+        // 6 is COST, which doesn't sort; nothing changes
+        app.apply_action(Action::SortColumn(6));
+        assert_eq!(app.holdings_sort_order, SortOrder::ProfitLossPercent);
+
+        // Back in quotes the quotes sort was never touched
+        app.set_view(View::Quotes);
+        assert_eq!(app.active_sort().0, SortOrder::Volume);
+        app.set_sort_order(SortOrder::ProfitLoss);
+        assert_eq!(app.sort_order, SortOrder::Volume);
+    }
+
+    #[test]
+    fn test_holdings_sort_by_value_independent_of_quotes_sort() {
+        let mut app = test_app(&["-s", "AAPL,MSFT,TSLA,NVDA"]);
+        app.holdings = [
+            holding("AAPL", 1.0, 50.0),  // value 100
+            holding("MSFT", 10.0, 50.0), // value 1000
+            holding("NVDA", 5.0, 50.0),  // value 500
+        ]
+        .into_iter()
+        .map(|h| (h.symbol.clone(), h))
+        .collect();
+        app.quotes = vec![
+            quote("AAPL", 0.0, 0.0, None),
+            quote("MSFT", 0.0, 0.0, None),
+            quote("TSLA", 0.0, 0.0, None),
+            quote("NVDA", 0.0, 0.0, None),
+        ];
+        app.set_sort_order(SortOrder::Symbol);
+
+        // Holdings default to biggest positions first
+        app.toggle_holdings();
+        assert_eq!(
+            app.active_sort(),
+            (SortOrder::Value, SortDirection::Descending)
+        );
+        let order: Vec<&str> = app.quotes.iter().map(|q| q.symbol.as_str()).collect();
+        assert_eq!(order, vec!["MSFT", "NVDA", "AAPL", "TSLA"]);
+
+        // Reversing in holdings leaves the quotes sort alone
+        app.toggle_sort_direction();
+        app.toggle_holdings();
+        assert_eq!(
+            app.active_sort(),
+            (SortOrder::Symbol, SortDirection::Ascending)
+        );
+        let order: Vec<&str> = app.quotes.iter().map(|q| q.symbol.as_str()).collect();
+        assert_eq!(order, vec!["AAPL", "MSFT", "NVDA", "TSLA"]);
+    }
+
+    #[test]
+    fn test_holdings_sort_by_day_change() {
+        let mut app = test_app(&[]);
+        app.holdings = [holding("AAPL", 10.0, 50.0), holding("MSFT", 1.0, 50.0)]
+            .into_iter()
+            .map(|h| (h.symbol.clone(), h))
+            .collect();
+        app.quotes = vec![
+            quote("MSFT", 5.0, 5.0, None), // +5 today
+            quote("AAPL", 1.0, 1.0, None), // +10 today
+        ];
+        app.set_view(View::Holdings);
+        app.apply_action(Action::SortColumn(9));
+
+        assert_eq!(app.holdings_sort_order, SortOrder::DayChange);
+        let order: Vec<&str> = app.quotes.iter().map(|q| q.symbol.as_str()).collect();
+        assert_eq!(order, vec!["AAPL", "MSFT"]);
+    }
+
+    #[test]
+    fn test_group_weights_include_ungrouped_positions() {
+        let mut app = test_app(&[]);
+        app.group_map = [("AAPL", "Tech"), ("MSFT", "Tech"), ("BTC-USD", "Crypto")]
+            .into_iter()
+            .map(|(s, g)| (s.to_string(), g.to_string()))
+            .collect();
+        app.holdings = [
+            holding("AAPL", 3.0, 50.0),    // 300
+            holding("MSFT", 3.0, 50.0),    // 300
+            holding("BTC-USD", 2.0, 50.0), // 200
+            holding("XOM", 2.0, 50.0),     // 200, no group
+        ]
+        .into_iter()
+        .map(|h| (h.symbol.clone(), h))
+        .collect();
+        app.quotes = ["AAPL", "MSFT", "BTC-USD", "XOM", "NVDA"]
+            .iter()
+            .map(|s| quote(s, 0.0, 0.0, None))
+            .collect();
+
+        assert_eq!(
+            app.group_weights(),
+            vec![
+                ("Tech".to_string(), 60.0),
+                ("Crypto".to_string(), 20.0),
+                ("Other".to_string(), 20.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_map_uses_expanded_symbols() {
+        let mut config = Config::default();
+        config
+            .groups
+            .insert("crypto".to_string(), vec!["btc".to_string()]);
+        let app = App::new(&Args::parse_from(["stonktop"]), &config).unwrap();
+        assert_eq!(app.group_map["BTC-USD"], "crypto");
+    }
+
+    #[test]
+    fn test_holdings_and_groups_in_watchlist() {
+        use crate::config::HoldingConfig;
+
+        let mut config = Config::default();
+        config.watchlist.symbols = vec!["AAPL".to_string()];
+        config.holdings = ["AAPL", "TSLA"]
+            .into_iter()
+            .map(|symbol| HoldingConfig {
+                symbol: symbol.to_string(),
+                quantity: 1.0,
+                cost_basis: 1.0,
+                date: None,
+            })
+            .collect();
+        config
+            .groups
+            .insert("crypto".to_string(), vec!["btc".to_string()]);
+
+        let shown = |include_holdings, include_groups, show_holdings| {
+            let mut config = config.clone();
+            config.watchlist.include_holdings = include_holdings;
+            config.watchlist.include_groups = include_groups;
+            let mut app = App::new(&Args::parse_from(["stonktop"]), &config).unwrap();
+            app.quotes = app
+                .symbols
+                .iter()
+                .map(|s| quote(s, 0.0, 0.0, None))
+                .collect();
+            app.show_holdings = show_holdings;
+            let shown: Vec<String> = app
+                .display_quotes()
+                .iter()
+                .map(|q| q.symbol.clone())
+                .collect();
+            (app.symbols, shown)
+        };
+
+        let (symbols, main) = shown(true, true, false);
+        assert_eq!(symbols, ["AAPL", "TSLA", "BTC-USD"]);
+        assert_eq!(main, symbols);
+
+        // TSLA is still fetched, but only the holdings view lists it
+        let (symbols, main) = shown(false, true, false);
+        assert_eq!(symbols, ["AAPL", "TSLA", "BTC-USD"]);
+        assert_eq!(main, ["AAPL", "BTC-USD"]);
+        assert_eq!(shown(false, true, true).1, ["AAPL", "TSLA"]);
+
+        // Groups no longer add their members
+        let (symbols, main) = shown(true, false, false);
+        assert_eq!(symbols, ["AAPL", "TSLA"]);
+        assert_eq!(main, symbols);
+
+        let (symbols, main) = shown(false, false, false);
+        assert_eq!(symbols, ["AAPL", "TSLA"]);
+        assert_eq!(main, ["AAPL"]);
+
+        // An explicit -s list is shown as given
+        let mut config = config.clone();
+        config.watchlist.include_holdings = false;
+        let app = App::new(&Args::parse_from(["stonktop", "-s", "TSLA"]), &config).unwrap();
+        assert!(app.holdings_only_symbols.is_empty());
+    }
+
+    #[test]
+    fn test_initial_order_modes() {
+        let mut config = Config::default();
+        config
+            .groups
+            .insert("tech".to_string(), vec!["MSFT".to_string()]);
+        config
+            .groups
+            .insert("crypto".to_string(), vec!["btc".to_string()]);
+        let args = Args::parse_from(["stonktop", "-s", "MSFT,AAPL,btc,GOOGL"]);
+
+        let order = |initial_order| {
+            let mut config = config.clone();
+            config.display.initial_order = initial_order;
+            App::new(&args, &config).unwrap().symbols
+        };
+        assert_eq!(
+            order(InitialOrder::AsListed),
+            ["MSFT", "AAPL", "BTC-USD", "GOOGL"]
+        );
+        assert_eq!(
+            order(InitialOrder::Alpha),
+            ["AAPL", "BTC-USD", "GOOGL", "MSFT"]
+        );
+        // Groups by name, then the ungrouped rest as listed
+        assert_eq!(
+            order(InitialOrder::Grouped),
+            ["BTC-USD", "MSFT", "AAPL", "GOOGL"]
+        );
+    }
+
+    #[test]
+    fn test_refresh_interval_keys_step_within_bounds() {
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        // The config's interval applies unless --delay says otherwise
+        let mut config = Config::default();
+        config.general.refresh_interval = 8.0;
+        config.general.min_refresh_interval = 2.0;
+        let argv = ["stonktop"];
+        let mut app = App::new(&Args::parse_from(argv), &config).unwrap();
+        assert_eq!(app.refresh_interval, Duration::from_secs(8));
+
+        let faster = Action::from_key(KeyCode::Char('+'), KeyModifiers::SHIFT).unwrap();
+        app.apply_action(faster);
+        assert_eq!(app.refresh_interval, Duration::from_secs(4));
+        assert!(app.dirty);
+        // Halving again would undercut the 2s floor, and so would a third press
+        app.apply_action(faster);
+        app.apply_action(faster);
+        assert_eq!(app.refresh_interval, Duration::from_secs(2));
+
+        for _ in 0..12 {
+            app.apply_action(Action::SlowerRefresh);
+        }
+        assert_eq!(app.refresh_interval, Duration::from_secs(600));
+
+        let app = test_app(&["-d", "3"]);
+        assert_eq!(app.refresh_interval, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_full_volume_toggles_at_runtime() {
+        use crate::ui::format_volume;
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        let mut app = test_app(&["--full-volume"]);
+        assert_eq!(format_volume(1_234_567, app.full_volume), "1,234,567");
+
+        let (code, modifiers) = (KeyCode::Char('#'), KeyModifiers::SHIFT);
+        app.apply_action(Action::from_key(code, modifiers).unwrap());
+        assert!(!app.full_volume);
+        assert_eq!(format_volume(1_234_567, app.full_volume), "1.23M");
+
+        app.apply_action(Action::ToggleFullVolume);
+        assert!(app.full_volume);
+    }
+
+    #[test]
+    fn test_favorites_stay_on_top_across_sorts() {
+        let mut app = test_app(&[]);
+        app.quotes = vec![
+            quote("AAA", 1.0, 1.0, Some(300)),
+            quote("BBB", 3.0, 3.0, Some(100)),
+            quote("CCC", 2.0, 2.0, Some(200)),
+            quote("DDD", 4.0, 4.0, Some(400)),
+        ];
+        app.favorites = ["CCC", "AAA"].iter().map(|s| s.to_string()).collect();
+
+        let order =
+            |app: &App| -> Vec<String> { app.quotes.iter().map(|q| q.symbol.clone()).collect() };
+
+        app.sort_direction = SortDirection::Descending;
+        app.sort_quotes();
+        assert_eq!(app.sort_order, SortOrder::ChangePercent);
+        assert_eq!(order(&app), vec!["CCC", "AAA", "DDD", "BBB"]);
+
+        app.set_sort_order(SortOrder::MarketCap);
+        assert_eq!(order(&app), vec!["AAA", "CCC", "DDD", "BBB"]);
+
+        app.toggle_sort_direction();
+        assert_eq!(order(&app), vec!["CCC", "AAA", "BBB", "DDD"]);
+    }
+
+    #[test]
+    fn test_toggle_favorite_pins_selected_row() {
+        let mut app = test_app(&[]);
+        app.quotes = vec![
+            quote("AAA", 3.0, 3.0, None),
+            quote("BBB", 2.0, 2.0, None),
+            quote("CCC", 1.0, 1.0, None),
+        ];
+        app.set_sort_order(SortOrder::Change);
+        app.selected = 2;
+
+        app.apply_action(Action::ToggleFavorite);
+        assert!(app.favorites.contains("CCC"));
+        assert_eq!(app.quotes[0].symbol, "CCC");
+        assert_eq!(app.selected, 0);
+
+        app.apply_action(Action::ToggleFavorite);
+        assert!(app.favorites.is_empty());
+        assert_eq!(app.quotes[2].symbol, "CCC");
+        assert_eq!(app.selected, 2);
+    }
+
+    #[test]
+    fn test_name_width_is_clamped() {
+        for (configured, expected) in [(2, 8), (30, 30), (500, 60)] {
+            let mut config = Config::default();
+            config.display.name_width = configured;
+            let app = App::new(&Args::parse_from(["stonktop"]), &config).unwrap();
+            assert_eq!(app.name_width, expected);
+        }
+    }
+
+    #[test]
+    fn test_detail_view_needs_a_selection() {
+        let mut app = test_app(&[]);
+        app.apply_action(Action::ToggleDetail);
+        assert!(!app.show_detail);
+
+        app.quotes = vec![quote("AAPL", 0.0, 0.0, None)];
+        app.apply_action(Action::ToggleDetail);
+        assert!(app.show_detail);
+        app.apply_action(Action::ToggleDetail);
+        assert!(!app.show_detail);
+    }
+
+    #[test]
+    fn test_countdown_label() {
+        let start = Instant::now();
+        let interval = Duration::from_secs(5);
+
+        assert_eq!(
+            countdown_label(Some(start), interval, start, "…"),
+            "next: 5s"
+        );
+        assert_eq!(
+            countdown_label(
+                Some(start),
+                interval,
+                start + Duration::from_millis(2500),
+                "…"
+            ),
+            "next: 3s"
+        );
+        // Overdue refreshes clamp at zero rather than going negative
+        assert_eq!(
+            countdown_label(Some(start), interval, start + Duration::from_secs(9), "…"),
+            "next: 0s"
+        );
+        assert_eq!(countdown_label(None, interval, start, "…"), "refreshing…");
+        assert_eq!(
+            countdown_label(None, interval, start, "..."),
+            "refreshing..."
+        );
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let interval = Duration::from_secs(10);
+        assert_eq!(jittered(interval, 0.1, 0.0), Duration::from_secs(9));
+        assert_eq!(jittered(interval, 0.0, 0.99), interval);
+
+        for _ in 0..1000 {
+            let unit = random_unit();
+            assert!((0.0..1.0).contains(&unit));
+            let delay = jittered(interval, 0.1, unit);
+            assert!(delay >= Duration::from_secs(9) && delay <= Duration::from_secs(11));
+        }
+    }
+
+    #[test]
+    fn test_jitter_config_is_clamped() {
+        let mut config = Config::default();
+        config.general.refresh_jitter = 3.0;
+        let app = App::new(&Args::parse_from(["stonktop"]), &config).unwrap();
+        assert_eq!(app.refresh_jitter, MAX_REFRESH_JITTER);
+    }
+
+    #[test]
+    fn test_track_changes_marks_moved_symbols() {
+        let mut app = test_app(&[]);
+        app.quotes = vec![quote("MSFT", 1.0, 1.0, None), quote("AAPL", 1.0, 1.0, None)];
+        app.track_changes();
+        assert!(!app.has_unseen_change("AAPL"));
+
+        app.quotes = vec![quote("MSFT", 1.0, 1.0, None), quote("AAPL", 2.0, 2.0, None)];
+        app.track_changes();
+        assert!(app.has_unseen_change("AAPL"));
+        assert!(!app.has_unseen_change("MSFT"));
+
+        // Nothing is timed: however long the row sits unselected, the mark stays
+        app.selected = 0;
+        for _ in 0..100 {
+            app.mark_selected_seen();
+        }
+        assert!(app.has_unseen_change("AAPL"));
+
+        // Selecting the row clears it
+        app.selected = 1;
+        app.mark_selected_seen();
+        assert!(!app.has_unseen_change("AAPL"));
+
+        // So does the next refresh, unless the symbol moved again
+        app.quotes[1].price = 3.0;
+        app.track_changes();
+        assert!(app.has_unseen_change("AAPL"));
+        app.track_changes();
+        assert!(!app.has_unseen_change("AAPL"));
+    }
+
+    #[test]
+    fn test_sort_by_abs_change_percent() {
+        let mut app = test_app(&[]);
+        app.quotes = vec![
+            quote("FLAT", 1.0, 1.0, None),
+            quote("DOWN", -9.0, -9.0, None),
+            quote("UP", 7.0, 7.0, None),
+        ];
+        app.set_sort_order(SortOrder::AbsChangePercent);
+
+        let order: Vec<&str> = app.quotes.iter().map(|q| q.symbol.as_str()).collect();
+        assert_eq!(order, vec!["DOWN", "UP", "FLAT"]);
+    }
+
+    #[test]
+    fn test_sort_by_base_asset_clusters_pairs() {
+        let mut app = test_app(&[]);
+        app.quotes = vec![
+            quote("ETH-USD", 0.0, 0.0, None),
+            quote("BTC-USD", 0.0, 0.0, None),
+            quote("AAPL", 0.0, 0.0, None),
+            quote("BTC-EUR", 0.0, 0.0, None),
+        ];
+        app.sort_order = SortOrder::BaseAsset;
+        app.sort_direction = SortDirection::Ascending;
+        app.sort_quotes();
+
+        let order: Vec<&str> = app.quotes.iter().map(|q| q.symbol.as_str()).collect();
+        assert_eq!(order, vec!["AAPL", "BTC-EUR", "BTC-USD", "ETH-USD"]);
+    }
+
+    #[test]
+    fn test_sort_by_market_cap_puts_missing_last() {
+        let mut app = test_app(&[]);
+        app.quotes = vec![
+            quote("BTC-USD", 0.0, 0.0, None),
+            quote("SMALL", 0.0, 0.0, Some(1_000)),
+            quote("^GSPC", 0.0, 0.0, None),
+            quote("BIG", 0.0, 0.0, Some(9_000)),
+        ];
+        app.sort_order = SortOrder::MarketCap;
+
+        for direction in [SortDirection::Descending, SortDirection::Ascending] {
+            app.sort_direction = direction;
+            app.sort_quotes();
+            let order: Vec<&str> = app.quotes.iter().map(|q| q.symbol.as_str()).collect();
+            let expected_caps = match direction {
+                SortDirection::Descending => ["BIG", "SMALL"],
+                SortDirection::Ascending => ["SMALL", "BIG"],
+            };
+            assert_eq!(&order[..2], &expected_caps);
+            assert!(app.quotes[2..].iter().all(|q| q.market_cap.is_none()));
+        }
+    }
+
+    /// Collects notifications instead of showing them.
+    #[derive(Default, Clone)]
+    struct RecordingNotifier(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+    impl Notifier for RecordingNotifier {
+        fn notify(&self, title: &str, _body: &str) -> Result<()> {
+            self.0.lock().unwrap().push(title.to_string());
+            Ok(())
+        }
+    }
+
+    /// Collects copied text instead of touching the real clipboard.
+    #[derive(Default, Clone)]
+    struct RecordingClipboard(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+    impl Clipboard for RecordingClipboard {
+        fn copy(&self, text: &str) -> Result<()> {
+            self.0.lock().unwrap().push(text.to_string());
+            Ok(())
+        }
+    }
+
+    /// A clipboard on a headless box.
+    struct MissingClipboard;
+
+    impl Clipboard for MissingClipboard {
+        fn copy(&self, _text: &str) -> Result<()> {
+            anyhow::bail!("No clipboard available")
+        }
+    }
+
+    #[test]
+    fn test_copy_actions_use_the_clipboard() {
+        let mut app = test_app(&[]);
+        let clipboard = RecordingClipboard::default();
+        app.clipboard = Box::new(clipboard.clone());
+        app.quotes = vec![Quote {
+            name: "Apple Inc.".to_string(),
+            ..quote("AAPL", 2.0, 1.35, None)
+        }];
+
+        app.apply_action(Action::CopySymbol);
+        app.apply_action(Action::CopyQuote);
+        assert_eq!(
+            *clipboard.0.lock().unwrap(),
+            vec!["AAPL", "AAPL Apple Inc. $102.00 +2.00 (+1.35%)"]
+        );
+        assert!(app.error.is_none());
+
+        // No clipboard: say so and carry on
+        app.clipboard = Box::new(MissingClipboard);
+        app.apply_action(Action::CopySymbol);
+        assert!(app.error.as_deref().unwrap().contains("No clipboard"));
+
+        // Secure mode never copies
+        let mut app = test_app(&["--secure"]);
+        app.clipboard = Box::new(clipboard.clone());
+        app.quotes = vec![quote("MSFT", 0.0, 0.0, None)];
+        app.apply_action(Action::CopySymbol);
+        assert_eq!(clipboard.0.lock().unwrap().len(), 2);
+        assert!(!Action::CopySymbol.allowed_in_secure_mode());
+    }
+
+    #[test]
+    fn test_big_mover_notification_fires_once() {
+        let mut app = test_app(&[]);
+        let notifier = RecordingNotifier::default();
+        app.notifier = Box::new(notifier.clone());
+        app.move_watcher = Some(MoveWatcher::new(5.0));
+
+        for pct in [1.0, 6.0, 7.0] {
+            app.quotes = vec![quote("AAPL", pct, pct, None)];
+            app.notify_big_movers();
+        }
+
+        assert_eq!(*notifier.0.lock().unwrap(), vec!["AAPL +6.00%"]);
+    }
+
+    #[test]
+    fn test_quiet_hours_hold_notifications() {
+        let hhmm = |offset_hours: i64| {
+            (Local::now() + chrono::Duration::hours(offset_hours))
+                .format("%H:%M")
+                .to_string()
+        };
+        let run = |start: String, end: String| {
+            let mut app = test_app(&[]);
+            let notifier = RecordingNotifier::default();
+            app.notifier = Box::new(notifier.clone());
+            app.quiet_hours = Some(QuietHours::parse(&start, &end).unwrap());
+            app.move_watcher = Some(MoveWatcher::new(5.0));
+            for pct in [1.0, 6.0] {
+                app.quotes = vec![quote("AAPL", pct, pct, None)];
+                app.notify_big_movers();
+            }
+            let sent = notifier.0.lock().unwrap().len();
+            sent
+        };
+
+        // In the window (which may well wrap past midnight)
+        assert_eq!(run(hhmm(-1), hhmm(1)), 0);
+        // Out of it
+        assert_eq!(run(hhmm(1), hhmm(2)), 1);
+    }
+
+    #[test]
+    fn test_mixed_case_symbols_dedup() {
+        // Pairs keep their casing, so the first spelling listed wins
+        let app = test_app(&["-s", "aapl,AAPL,Aapl,BTC-USD,btc-usd,msft"]);
+        assert_eq!(app.symbols, vec!["AAPL", "BTC-USD", "MSFT"]);
+    }
+
+    #[test]
+    fn test_watchlist_net_change_equal_weight() {
+        let mut app = test_app(&[]);
+        app.quotes = vec![
+            quote("AAPL", 2.0, 2.0, Some(3_000)),
+            quote("MSFT", -1.0, -1.0, Some(1_000)),
+        ];
+
+        let (change, percent) = app.watchlist_net_change().unwrap();
+        assert!((change - 0.5).abs() < 1e-9);
+        assert!((percent - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_watchlist_net_change_market_cap_weight() {
+        let mut app = test_app(&[]);
+        app.net_change_weighting = Weighting::MarketCap;
+        app.quotes = vec![
+            quote("AAPL", 2.0, 2.0, Some(3_000)),
+            quote("MSFT", -1.0, -1.0, Some(1_000)),
+            quote("BTC-USD", 50.0, 10.0, None),
+        ];
+
+        // (3000 * 2 + 1000 * -1) / 4000, the capless crypto is ignored
+        let (_, percent) = app.watchlist_net_change().unwrap();
+        assert!((percent - 1.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_composite_change_weightings() {
+        let mut app = test_app(&[]);
+        app.holdings
+            .insert("MSFT".to_string(), holding("MSFT", 1.0, 1.0));
+        app.quotes = vec![
+            quote("AAPL", 2.0, 2.0, Some(3_000)),
+            quote("MSFT", -1.0, -1.0, Some(1_000)),
+            quote("BTC-USD", 50.0, 5.0, None),
+        ];
+
+        // Holdings count too: it's your whole market
+        let equal = app.composite_change(Weighting::Equal).unwrap();
+        assert!((equal - 2.0).abs() < 1e-9);
+        // (3000 * 2 + 1000 * -1) / 4000, capless crypto sits it out
+        let cap = app.composite_change(Weighting::MarketCap).unwrap();
+        assert!((cap - 1.25).abs() < 1e-9);
+
+        // What's on screen doesn't change the basket
+        app.set_view(View::Holdings);
+        app.search_query = Some("BTC".to_string());
+        app.top = Some(1);
+        assert_eq!(app.composite_change(Weighting::Equal), Some(equal));
+        assert_eq!(app.composite_change(Weighting::MarketCap), Some(cap));
+        // ...but the benchmark, fetched only for RS, isn't in it
+        app.hidden_symbols.insert("BTC-USD".to_string());
+        let without_btc = app.composite_change(Weighting::Equal).unwrap();
+        assert!((without_btc - 0.5).abs() < 1e-9);
+        // Nor is a quote only carried over from an earlier refresh
+        app.missing_streak.insert("MSFT".to_string(), 1);
+        assert_eq!(app.composite_change(Weighting::Equal), Some(2.0));
+        app.missing_streak.clear();
+
+        app.quotes.retain(|q| q.market_cap.is_none());
+        assert_eq!(app.composite_change(Weighting::MarketCap), None);
+        app.quotes.clear();
+        assert_eq!(app.composite_change(Weighting::Equal), None);
+    }
+
+    #[test]
+    fn test_composite_tracked_in_price_history() {
+        let mut app = test_app(&["-s", "AAPL,MSFT"]);
+        let batch = |aapl: f64| {
+            Ok(QuoteBatch {
+                quotes: vec![
+                    quote("AAPL", aapl, aapl, None),
+                    quote("MSFT", 0.0, 0.0, None),
+                ],
+                failures: Vec::new(),
+                warnings: Vec::new(),
+            })
+        };
+
+        // Off unless asked for
+        app.apply_fetch(batch(1.0));
+        assert!(!app.price_history.contains_key(COMPOSITE_KEY));
+
+        app.composite_index = Some(Weighting::Equal);
+        app.apply_fetch(batch(1.0));
+        app.apply_fetch(batch(3.0));
+        assert_eq!(app.price_history[COMPOSITE_KEY], vec![0.5, 1.5]);
+    }
+
+    #[test]
+    fn test_export_is_single_shot_by_default() {
+        assert_eq!(test_app(&["-b"]).max_iterations, 0);
+        assert_eq!(test_app(&["--export", "html"]).max_iterations, 1);
+        assert_eq!(test_app(&["--export", "csv", "-n", "3"]).max_iterations, 3);
+    }
+
+    #[test]
+    fn test_percent_precision_flag_overrides_and_clamps() {
+        assert_eq!(test_app(&[]).percent_precision, 2);
+        assert_eq!(test_app(&["--percent-precision", "3"]).percent_precision, 3);
+        assert_eq!(
+            test_app(&["--percent-precision", "12"]).percent_precision,
+            6
+        );
+    }
+
+    fn holding(symbol: &str, quantity: f64, cost_basis: f64) -> Holding {
+        Holding {
+            symbol: symbol.to_string(),
+            quantity,
+            cost_basis,
+            purchase_date: None,
+        }
+    }
+
+    #[test]
+    fn test_panic_warning_follows_portfolio_day_change() {
+        let mut config = Config::default();
+        config.alerts.panic_pct = Some(-5.0);
+        let mut app = App::new(&Args::parse_from(["stonktop", "-s", "AAPL"]), &config).unwrap();
+        app.holdings
+            .insert("AAPL".to_string(), holding("AAPL", 10.0, 50.0));
+        let fetch = |app: &mut App, change: f64| {
+            app.apply_fetch(Ok(QuoteBatch {
+                quotes: vec![quote("AAPL", change, change, None)],
+                ..Default::default()
+            }))
+        };
+
+        // Down 4% on a $1000 close: not yet
+        fetch(&mut app, -4.0);
+        assert_eq!(app.today_portfolio_change_percent(), Some(-4.0));
+        assert_eq!(app.panic_warning(), None);
+
+        fetch(&mut app, -6.0);
+        assert_eq!(app.panic_warning(), Some((-6.0, -5.0)));
+        assert!(app.dismiss_panic());
+        assert_eq!(app.panic_warning(), None);
+        fetch(&mut app, -6.5);
+        assert_eq!(app.panic_warning(), None);
+
+        // Worse again: back it comes
+        fetch(&mut app, -7.0);
+        let (level, _) = app.panic_warning().unwrap();
+        assert!((level + 7.0).abs() < 1e-9);
+
+        // Off without a threshold
+        assert!(!test_app(&[]).dismiss_panic());
+    }
+
+    #[test]
+    fn test_record_portfolio_value_caps_history() {
+        let mut app = test_app(&[]);
+        app.portfolio_history_len = 2;
+        app.holdings
+            .insert("AAPL".to_string(), holding("AAPL", 2.0, 100.0));
+
+        // Nothing priced yet, nothing recorded
+        app.record_portfolio_value();
+        assert!(app.portfolio_value_history.is_empty());
+
+        for price in [100.0, 110.0, 120.0] {
+            app.quotes = vec![Quote {
+                symbol: "AAPL".to_string(),
+                price,
+                ..Default::default()
+            }];
+            app.record_portfolio_value();
+        }
+
+        let values: Vec<f64> = app
+            .portfolio_value_history
+            .iter()
+            .map(|(_, v)| *v)
+            .collect();
+        assert_eq!(values, vec![220.0, 240.0]);
+    }
+
+    #[test]
+    fn test_portfolio_annualized_return_skips_undated() {
+        let mut app = test_app(&[]);
+        app.holdings
+            .insert("AAPL".to_string(), holding("AAPL", 1.0, 100.0));
+        app.quotes = vec![quote("AAPL", 10.0, 10.0, None)];
+        assert_eq!(app.portfolio_annualized_return(), None);
+
+        let two_years_ago = Local::now().date_naive() - chrono::Duration::days(730);
+        app.holdings.get_mut("AAPL").unwrap().purchase_date = Some(two_years_ago);
+        let r = app.portfolio_annualized_return().unwrap();
+        assert!((r - annualized_return(100.0, 110.0, 730).unwrap()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_watchlist_net_change_skips_holdings() {
+        let mut app = test_app(&[]);
+        app.holdings
+            .insert("AAPL".to_string(), holding("AAPL", 1.0, 1.0));
+        app.quotes = vec![quote("AAPL", 2.0, 2.0, None)];
+
+        assert!(app.watchlist_net_change().is_none());
+    }
 }
@@ -0,0 +1,119 @@
+//! `--check`: load everything, fetch nothing, and say what would happen.
+//!
+//! For config authors who'd rather find the typo before the opening bell.
+
+use crate::api::{expand_symbol, is_valid_symbol};
+use crate::app::App;
+use crate::config::Config;
+use crate::models::FetchFailure;
+use std::fmt::Write;
+
+/// Describe the resolved setup: symbols as they'll be fetched, groups,
+/// holdings and alerts. Returns the report and the problems found in it.
+/// `probe` holds the symbols a test fetch couldn't get, if one was made.
+pub fn report(app: &App, config: &Config, probe: Option<&[FetchFailure]>) -> (String, Vec<String>) {
+    let mut out = String::new();
+    let mut problems = Vec::new();
+
+    // Writing to a String can't fail, so the results are ignored throughout
+    let _ = writeln!(out, "Symbols ({}):", app.symbols.len());
+    for symbol in &app.symbols {
+        let note = if app.hidden_symbols.contains(symbol) {
+            "  (hidden: benchmark or synthetic component)"
+        } else {
+            ""
+        };
+        let _ = writeln!(out, "  {}{}", symbol, note);
+        if !is_valid_symbol(symbol) {
+            problems.push(format!("Invalid symbol: {:?}", symbol));
+        }
+    }
+    if app.symbols.is_empty() {
+        problems.push("No symbols to watch".to_string());
+    }
+
+    let mut groups: Vec<_> = config.groups.iter().collect();
+    groups.sort_by_key(|(name, _)| name.as_str());
+    if !groups.is_empty() {
+        let _ = writeln!(out, "\nGroups:");
+    }
+    for (name, members) in groups {
+        let members: Vec<String> = members.iter().map(|s| expand_symbol(s)).collect();
+        let _ = writeln!(out, "  {}: {}", name, members.join(", "));
+    }
+
+    if !app.synthetics.is_empty() {
+        let _ = writeln!(out, "\nSynthetics:");
+    }
+    for synthetic in &app.synthetics {
+        let _ = writeln!(
+            out,
+            "  {} = {} / {}",
+            synthetic.name, synthetic.numerator, synthetic.denominator
+        );
+    }
+
+    let mut holdings: Vec<_> = app.holdings.iter().collect();
+    holdings.sort_by_key(|(symbol, _)| symbol.as_str());
+    if !holdings.is_empty() {
+        let _ = writeln!(out, "\nHoldings:");
+    }
+    for (symbol, holding) in holdings {
+        let _ = writeln!(
+            out,
+            "  {:<10} {} @ {:.2}",
+            symbol, holding.quantity, holding.cost_basis
+        );
+    }
+
+    let alerts = &config.alerts;
+    if alerts.notify_on_move_pct.is_some() || !alerts.rules.is_empty() {
+        let _ = writeln!(out, "\nAlerts:");
+    }
+    if let Some(pct) = alerts.notify_on_move_pct {
+        let _ = writeln!(out, "  any symbol moving {}%", pct);
+    }
+    for rule in &alerts.rules {
+        let symbol = expand_symbol(&rule.symbol);
+        let _ = writeln!(
+            out,
+            "  {:<10} {} {}",
+            symbol,
+            rule.condition.name(),
+            rule.condition.threshold()
+        );
+        if !app.symbols.contains(&symbol) {
+            problems.push(format!("Alert for {} which isn't watched", symbol));
+        }
+    }
+    if let (Some(start), Some(end)) = (&alerts.quiet_start, &alerts.quiet_end) {
+        let _ = writeln!(out, "  quiet {} - {}", start, end);
+    }
+
+    if let Some(failures) = probe {
+        let fetched = app.symbols.len().saturating_sub(failures.len());
+        let _ = writeln!(
+            out,
+            "\nProbe: {} of {} symbols fetched",
+            fetched,
+            app.symbols.len()
+        );
+        for failure in failures {
+            problems.push(format!(
+                "Couldn't fetch {}: {}",
+                failure.symbol, failure.reason
+            ));
+        }
+    }
+
+    if problems.is_empty() {
+        let _ = writeln!(out, "\nOK");
+    } else {
+        let _ = writeln!(out, "\nProblems ({}):", problems.len());
+        for problem in &problems {
+            let _ = writeln!(out, "  {}", problem);
+        }
+    }
+
+    (out, problems)
+}
@@ -2,7 +2,9 @@
 //!
 //! All the flags you need to customize your financial anxiety experience.
 
-use clap::{Parser, ValueEnum};
+use crate::api::is_valid_symbol;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, ValueEnum};
 use std::path::PathBuf;
 
 /// A top-like terminal UI for monitoring stock and cryptocurrency prices.
@@ -25,12 +27,15 @@ pub struct Args {
     pub symbols: Option<Vec<String>>,
 
     /// Refresh delay in seconds (like top -d)
-    #[arg(short = 'd', long, default_value = "5", env = "STONKTOP_DELAY")]
-    pub delay: f64,
+    ///
+    /// Defaults to [general] refresh_interval from the config, 5 without one
+    #[arg(short = 'd', long, env = "STONKTOP_DELAY")]
+    pub delay: Option<f64>,
 
     /// Number of iterations before exiting (like top -n)
     ///
-    /// 0 means infinite
+    /// 0 means infinite, except with --export, which stops after one
+    /// document unless given more
     #[arg(short = 'n', long, default_value = "0")]
     pub iterations: u64,
 
@@ -38,6 +43,20 @@ pub struct Args {
     #[arg(short = 'b', long)]
     pub batch: bool,
 
+    /// Print batch output in another format instead of the text table (implies --batch)
+    #[arg(long, value_enum)]
+    pub export: Option<ExportFormat>,
+
+    /// Print one compact line per refresh, e.g. for a status bar or tmux
+    /// (implies --batch)
+    #[arg(long, conflicts_with = "export")]
+    pub ticker: bool,
+
+    /// Only print batch rows that moved at least PCT percent since they were
+    /// last printed; the first iteration prints everything (implies --batch)
+    #[arg(long, value_name = "PCT")]
+    pub min_change_pct: Option<f64>,
+
     /// Secure mode - disables interactive commands
     #[arg(short = 'S', long)]
     pub secure: bool,
@@ -62,6 +81,87 @@ pub struct Args {
     #[arg(short = 'f', long, value_enum)]
     pub filter: Option<FilterType>,
 
+    /// Show only symbols listed on an exchange suffix (e.g. DE, T, L)
+    ///
+    /// Use "US" for tickers without a suffix
+    #[arg(long)]
+    pub exchange: Option<String>,
+
+    /// Decimal places for percentage columns (overrides config, 0-6)
+    #[arg(long)]
+    pub percent_precision: Option<usize>,
+
+    /// Blank lines between table rows (0 or 1, overrides config)
+    #[arg(long, value_parser = clap::value_parser!(u16).range(0..=1))]
+    pub row_spacing: Option<u16>,
+
+    /// Show volume as full grouped numbers (1,234,567) instead of K/M/B
+    #[arg(long)]
+    pub full_volume: bool,
+
+    /// Show crypto pairs in the display currency as just the coin (BTC, not BTC-USD)
+    #[arg(long)]
+    pub compact_crypto: bool,
+
+    /// Alternate row backgrounds for easier scanning
+    #[arg(long)]
+    pub zebra: bool,
+
+    /// Line up the decimal points in the PRICE column
+    #[arg(long)]
+    pub align_decimals: bool,
+
+    /// Draw separators between table columns and a rule under the table
+    #[arg(long)]
+    pub grid: bool,
+
+    /// Move quotes without a price to the failures list instead of showing $0.00 rows
+    #[arg(long)]
+    pub hide_invalid: bool,
+
+    /// Plain ASCII glyphs for terminals that mangle Unicode
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Prefix gains and losses with direction glyphs, not just color
+    #[arg(long)]
+    pub glyphs: bool,
+
+    /// Show each symbol's change relative to a benchmark (RS column)
+    #[arg(long)]
+    pub relative_strength: bool,
+
+    /// Benchmark for the RS column (implies --relative-strength, default ^GSPC)
+    #[arg(long, value_name = "SYMBOL")]
+    pub benchmark: Option<String>,
+
+    /// Advance the selection every SECS seconds, for unattended displays (any key stops it)
+    #[arg(long, value_name = "SECS")]
+    pub autoscroll: Option<f64>,
+
+    /// Milliseconds between redraws and key checks (50-1000). Slower saves
+    /// battery, faster makes the spinner and change markers smoother
+    #[arg(long, value_name = "MS", default_value = "100")]
+    pub tick_ms: u64,
+
+    /// Show a PREV column with the previous close (and the gap before the open)
+    #[arg(long)]
+    pub prev_close: bool,
+
+    /// Show a SESS% column with each symbol's change since stonktop started
+    #[arg(long)]
+    pub since_launch: bool,
+
+    /// Show an EXT% column with the pre-market/after-hours move (hidden during the day)
+    #[arg(long)]
+    pub extended_hours: bool,
+
+    /// Actions to apply at startup, separated by semicolons
+    ///
+    /// Example: "sort=price;view=holdings;filter=stocks"
+    #[arg(long, env = "STONKTOP_ON_START")]
+    pub on_start: Option<String>,
+
     /// Hide summary header
     #[arg(long)]
     pub no_header: bool,
@@ -85,6 +185,38 @@ pub struct Args {
     /// API timeout in seconds
     #[arg(long, default_value = "10")]
     pub timeout: u64,
+
+    /// User-Agent for API requests (overrides config)
+    #[arg(long, env = "STONKTOP_USER_AGENT")]
+    pub user_agent: Option<String>,
+
+    /// Print the key bindings and exit
+    #[arg(long)]
+    pub keys: bool,
+
+    /// Validate the config and print the resolved symbols, groups, holdings
+    /// and alerts, then exit (nonzero on problems). Nothing is fetched
+    /// unless --probe is given.
+    #[arg(long, visible_alias = "dry-run")]
+    pub check: bool,
+
+    /// With --check, fetch every symbol once and report the ones that don't
+    /// come back (typos, delisted tickers)
+    #[arg(long, requires = "check")]
+    pub probe: bool,
+
+    /// Serve the quotes as JSON at /quotes and /portfolio on ADDR
+    /// (e.g. 127.0.0.1:8787) instead of showing them, refreshing as usual
+    #[arg(long, value_name = "ADDR")]
+    pub serve: Option<std::net::SocketAddr>,
+
+    /// Read quotes from a JSON fixture file instead of the network
+    #[arg(long, value_name = "PATH")]
+    pub fixture: Option<PathBuf>,
+
+    /// Time indicator computation over N synthetic symbols and exit
+    #[arg(long, hide = true, value_name = "SYMBOLS")]
+    pub bench_indicators: Option<usize>,
 }
 
 /// Sort field options (similar to top's sort fields).
@@ -101,10 +233,16 @@ pub enum SortField {
     /// Sort by percentage change (default)
     #[default]
     ChangePercent,
+    /// Sort by size of the move, up or down (biggest movers)
+    AbsChangePercent,
     /// Sort by trading volume
     Volume,
     /// Sort by market capitalization
     MarketCap,
+    /// Group by exchange suffix (e.g. .DE, .T)
+    Exchange,
+    /// Group crypto pairs by base asset (e.g. BTC-USD with BTC-EUR)
+    BaseAsset,
 }
 
 impl From<SortField> for crate::models::SortOrder {
@@ -115,14 +253,17 @@ impl From<SortField> for crate::models::SortOrder {
             SortField::Price => crate::models::SortOrder::Price,
             SortField::Change => crate::models::SortOrder::Change,
             SortField::ChangePercent => crate::models::SortOrder::ChangePercent,
+            SortField::AbsChangePercent => crate::models::SortOrder::AbsChangePercent,
             SortField::Volume => crate::models::SortOrder::Volume,
             SortField::MarketCap => crate::models::SortOrder::MarketCap,
+            SortField::Exchange => crate::models::SortOrder::Exchange,
+            SortField::BaseAsset => crate::models::SortOrder::BaseAsset,
         }
     }
 }
 
 /// Filter options for quote types.
-#[derive(Debug, Clone, Copy, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum FilterType {
     /// Show only stocks
     Stocks,
@@ -134,6 +275,17 @@ pub enum FilterType {
     Index,
 }
 
+/// Batch output formats for other programs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// Styled HTML table, e.g. for an email body
+    Html,
+    /// One JSON array of quotes per line, every field included
+    Json,
+    /// CSV with a header row, every field included
+    Csv,
+}
+
 /// Color output mode.
 #[derive(Debug, Clone, Copy, ValueEnum, Default)]
 pub enum ColorMode {
@@ -150,12 +302,20 @@ impl Args {
     /// Parse command line arguments.
     /// Where your journey to financial enlightenment begins.
     pub fn parse_args() -> Self {
-        Args::parse()
+        let mut args = Args::parse();
+        if let Some(ref symbols) = args.symbols {
+            match normalize_symbols(symbols) {
+                Ok(symbols) => args.symbols = Some(symbols),
+                Err(message) => Args::command()
+                    .error(ErrorKind::ValueValidation, message)
+                    .exit(),
+            }
+        }
+        args
     }
 
     /// Check if colors should be enabled.
     /// Because red and green are the only colors that matter in finance.
-    #[allow(dead_code)] // Reserved for when we implement --no-feelings mode
     pub fn use_colors(&self) -> bool {
         match self.color {
             ColorMode::Always => true,
@@ -168,9 +328,36 @@ impl Args {
     }
 }
 
+/// Tidy up a pasted symbol list: split on commas, semicolons and
+/// whitespace as well, drop the empty bits, and uppercase what's left.
+/// "aapl; GOOGL, msft" is three symbols, not one with spaces in it.
+pub fn normalize_symbols(raw: &[String]) -> Result<Vec<String>, String> {
+    let symbols: Vec<String> = raw
+        .iter()
+        .flat_map(|part| part.split(|c: char| c == ',' || c == ';' || c.is_whitespace()))
+        .filter(|symbol| !symbol.is_empty())
+        .map(str::to_ascii_uppercase)
+        .collect();
+
+    let invalid: Vec<String> = symbols
+        .iter()
+        .filter(|symbol| !is_valid_symbol(symbol))
+        .map(|symbol| format!("{:?}", symbol))
+        .collect();
+    if !invalid.is_empty() {
+        return Err(format!(
+            "invalid symbol(s) {}: use letters, digits and - . ^ = only, up to 20 characters",
+            invalid.join(", ")
+        ));
+    }
+    if symbols.is_empty() {
+        return Err("no symbols given to --symbols".to_string());
+    }
+    Ok(symbols)
+}
+
 /// Check if stdout is a terminal.
 /// Spoiler: it probably is, unless you're piping your tears to /dev/null.
-#[allow(dead_code)] // Used by use_colors which is reserved for future features
 fn atty_check() -> bool {
     // Simple check - in production you might use the `atty` crate
     std::env::var("TERM").is_ok()
@@ -183,7 +370,7 @@ mod tests {
     #[test]
     fn test_default_args() {
         let args = Args::parse_from(["stonktop"]);
-        assert_eq!(args.delay, 5.0);
+        assert_eq!(args.delay, None);
         assert_eq!(args.iterations, 0);
         assert!(!args.batch);
     }
@@ -201,10 +388,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_normalize_messy_symbol_lists() {
+        let raw = |parts: &[&str]| parts.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            normalize_symbols(&raw(&["aapl; GOOGL", " msft "])).unwrap(),
+            vec!["AAPL", "GOOGL", "MSFT"]
+        );
+        assert_eq!(
+            normalize_symbols(&raw(&["btc-usd\t^gspc  eurusd=x;;brk.b"])).unwrap(),
+            vec!["BTC-USD", "^GSPC", "EURUSD=X", "BRK.B"]
+        );
+
+        let err = normalize_symbols(&raw(&["AAPL", "AA$PL", "x/y"])).unwrap_err();
+        assert!(err.contains("\"AA$PL\", \"X/Y\""), "{}", err);
+        assert!(!err.contains("\"AAPL\""), "{}", err);
+        assert!(normalize_symbols(&raw(&[" ; , "])).is_err());
+    }
+
     #[test]
     fn test_delay_and_iterations() {
         let args = Args::parse_from(["stonktop", "-d", "2.5", "-n", "10"]);
-        assert_eq!(args.delay, 2.5);
+        assert_eq!(args.delay, Some(2.5));
         assert_eq!(args.iterations, 10);
     }
+
+    #[test]
+    fn test_exchange_filter_and_sort() {
+        let args = Args::parse_from(["stonktop", "--exchange", "DE", "-o", "exchange"]);
+        assert_eq!(args.exchange.as_deref(), Some("DE"));
+        assert_eq!(
+            crate::models::SortOrder::from(args.sort),
+            crate::models::SortOrder::Exchange
+        );
+    }
 }
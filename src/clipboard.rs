@@ -0,0 +1,36 @@
+//! Copying to the system clipboard.
+//!
+//! So the ticker you're staring at can go straight into the group chat.
+
+use anyhow::{anyhow, Context, Result};
+use std::sync::Mutex;
+
+/// Somewhere text can be copied to.
+pub trait Clipboard: Send + Sync {
+    /// Replace the clipboard contents with `text`.
+    fn copy(&self, text: &str) -> Result<()>;
+}
+
+/// The system clipboard, opened on first use. Headless sessions (no X11,
+/// Wayland or desktop) get an error back instead of a crash.
+#[derive(Default)]
+pub struct SystemClipboard {
+    /// Kept open: on X11 the copied text only lives as long as its owner
+    clipboard: Mutex<Option<arboard::Clipboard>>,
+}
+
+impl Clipboard for SystemClipboard {
+    fn copy(&self, text: &str) -> Result<()> {
+        let mut guard = self
+            .clipboard
+            .lock()
+            .map_err(|_| anyhow!("Clipboard lock poisoned"))?;
+        let clipboard = match &mut *guard {
+            Some(clipboard) => clipboard,
+            None => guard.insert(arboard::Clipboard::new().context("No clipboard available")?),
+        };
+        clipboard
+            .set_text(text)
+            .context("Failed to copy to the clipboard")
+    }
+}
@@ -2,17 +2,28 @@
 //!
 //! Because hardcoding your portfolio would be too easy.
 
-use crate::models::Holding;
+use crate::alerts::AlertRule;
+use crate::api::expand_symbol;
+use crate::app::MAX_REFRESH_SECS;
+use crate::models::{ColorBasis, Holding, InitialOrder, PrimaryChange, Rounding, Weighting};
+use crate::notify::QuietHours;
+use crate::synthetic::Synthetic;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 /// Application configuration loaded from TOML file.
 /// Where you define which assets will keep you up at night.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
+    /// Other config files whose symbols, groups and holdings are merged in,
+    /// relative to this file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<PathBuf>,
+
     /// General settings
     #[serde(default)]
     pub general: GeneralConfig,
@@ -36,6 +47,75 @@ pub struct Config {
     /// Groups of symbols
     #[serde(default)]
     pub groups: HashMap<String, Vec<String>>,
+
+    /// Alert and notification settings
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+
+    /// Ratio rows computed from two symbols, e.g. "ETH/BTC" = "ETH-USD/BTC-USD"
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub synthetics: HashMap<String, String>,
+
+    /// Per-symbol notes, shown in the detail view
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<NoteConfig>,
+
+    /// Fixed symbol-cell colors (hex) for symbols that should stand out
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub symbol_colors: HashMap<String, String>,
+}
+
+/// Alert and notification settings.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AlertsConfig {
+    /// Send a desktop notification when a symbol moves this many percent either way
+    #[serde(default)]
+    pub notify_on_move_pct: Option<f64>,
+
+    /// Per-symbol alert rules
+    #[serde(default)]
+    pub rules: Vec<AlertRule>,
+
+    /// Start of the nightly no-notification window, "HH:MM" local time
+    #[serde(default)]
+    pub quiet_start: Option<String>,
+
+    /// End of the no-notification window (exclusive); may be past midnight
+    #[serde(default)]
+    pub quiet_end: Option<String>,
+
+    /// Full-screen warning when the portfolio is down this many percent on the day
+    #[serde(default)]
+    pub panic_pct: Option<f64>,
+
+    /// Tag alerts "(stale)" when the quote behind them is older than this (seconds)
+    #[serde(default)]
+    pub stale_after: Option<f64>,
+}
+
+impl AlertsConfig {
+    /// The quiet window, if both ends are set.
+    pub fn quiet_hours(&self) -> Result<Option<QuietHours>> {
+        match (&self.quiet_start, &self.quiet_end) {
+            (Some(start), Some(end)) => Ok(Some(QuietHours::parse(start, end)?)),
+            (None, None) => Ok(None),
+            _ => anyhow::bail!("quiet_start and quiet_end need to be set together"),
+        }
+    }
+
+    /// Check the thresholds. A `nan` would load fine and then never fire.
+    pub fn validate(&self) -> Result<()> {
+        for (name, value) in [
+            ("notify_on_move_pct", self.notify_on_move_pct),
+            ("panic_pct", self.panic_pct),
+            ("stale_after", self.stale_after),
+        ] {
+            if let Some(value) = value.filter(|v| !v.is_finite()) {
+                anyhow::bail!("{} must be a number, got {}", name, value);
+            }
+        }
+        Ok(())
+    }
 }
 
 /// General application settings.
@@ -52,6 +132,58 @@ pub struct GeneralConfig {
     /// Default currency for display
     #[serde(default = "default_currency")]
     pub currency: String,
+
+    /// Actions applied at startup, e.g. "sort=price;view=holdings"
+    #[serde(default)]
+    pub on_start: Option<String>,
+
+    /// Faster refresh interval for just the held symbols while the holdings
+    /// view is up (unset = everything refreshes together)
+    #[serde(default)]
+    pub holdings_refresh_interval: Option<f64>,
+
+    /// Shortest refresh interval allowed, in seconds. Anything asked for
+    /// below it is clamped up (it can be raised, not lowered below 1s)
+    #[serde(default = "default_min_refresh_interval")]
+    pub min_refresh_interval: f64,
+
+    /// Random spread applied to each refresh delay, as a fraction (0.1 = ±10%)
+    #[serde(default = "default_refresh_jitter")]
+    pub refresh_jitter: f64,
+
+    /// Seconds an idle keep-alive connection stays in the pool
+    #[serde(default)]
+    pub pool_idle_timeout: Option<u64>,
+
+    /// Maximum idle keep-alive connections per host
+    #[serde(default)]
+    pub pool_max_idle_per_host: Option<usize>,
+
+    /// User-Agent for API requests (defaults to a desktop browser)
+    #[serde(default)]
+    pub user_agent: Option<String>,
+
+    /// Yahoo market region, for localized names and the default exchange
+    #[serde(default = "default_region")]
+    pub region: String,
+
+    /// Yahoo language for names and labels
+    #[serde(default = "default_lang")]
+    pub lang: String,
+
+    /// Consecutive refreshes where every symbol fails before showing a hard error (0 = never)
+    #[serde(default = "default_max_failed_refreshes")]
+    pub max_failed_refreshes: u32,
+
+    /// Consecutive refreshes a symbol can be missing from otherwise good
+    /// batches before it's listed as having no data (0 = never)
+    #[serde(default = "default_no_data_after")]
+    pub no_data_after: u32,
+
+    /// Ask before quitting with unsaved changes (like new reference prices).
+    /// Off saves them on the way out without asking
+    #[serde(default = "default_true")]
+    pub confirm_quit: bool,
 }
 
 impl Default for GeneralConfig {
@@ -60,26 +192,117 @@ impl Default for GeneralConfig {
             refresh_interval: default_refresh_interval(),
             timeout: default_timeout(),
             currency: default_currency(),
+            on_start: None,
+            holdings_refresh_interval: None,
+            min_refresh_interval: default_min_refresh_interval(),
+            refresh_jitter: default_refresh_jitter(),
+            pool_idle_timeout: None,
+            pool_max_idle_per_host: None,
+            user_agent: None,
+            region: default_region(),
+            lang: default_lang(),
+            max_failed_refreshes: default_max_failed_refreshes(),
+            no_data_after: default_no_data_after(),
+            confirm_quit: true,
+        }
+    }
+}
+
+impl GeneralConfig {
+    /// Check what serde can't. TOML has `nan` and `inf`, and those would
+    /// slip through the clamp on the jitter, or panic turning into a Duration.
+    pub fn validate(&self) -> Result<()> {
+        if !self.refresh_jitter.is_finite() {
+            anyhow::bail!(
+                "refresh_jitter must be a number, got {}",
+                self.refresh_jitter
+            );
+        }
+
+        let intervals = [
+            ("refresh_interval", Some(self.refresh_interval)),
+            ("min_refresh_interval", Some(self.min_refresh_interval)),
+            ("holdings_refresh_interval", self.holdings_refresh_interval),
+        ];
+        for (name, secs) in intervals {
+            let Some(secs) = secs else { continue };
+            if !(secs.is_finite() && secs <= MAX_REFRESH_SECS) {
+                anyhow::bail!(
+                    "{} must be a number of seconds up to {}, got {}",
+                    name,
+                    MAX_REFRESH_SECS,
+                    secs
+                );
+            }
         }
+        Ok(())
     }
 }
 
+fn default_region() -> String {
+    crate::api::DEFAULT_REGION.to_string()
+}
+
+fn default_lang() -> String {
+    crate::api::DEFAULT_LANG.to_string()
+}
+
 fn default_refresh_interval() -> f64 {
     5.0
 }
+fn default_min_refresh_interval() -> f64 {
+    1.0
+}
 fn default_timeout() -> u64 {
     10
 }
 fn default_currency() -> String {
     "USD".to_string()
 }
+fn default_refresh_jitter() -> f64 {
+    0.1
+}
+fn default_max_failed_refreshes() -> u32 {
+    3
+}
+fn default_no_data_after() -> u32 {
+    3
+}
 
 /// Watchlist configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WatchlistConfig {
     /// List of symbols to watch
     #[serde(default)]
     pub symbols: Vec<String>,
+
+    /// Symbols pinned to the top of the table, whatever the sort
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub favorites: Vec<String>,
+
+    /// Reference prices (e.g. your entry) for the REF% column, by symbol
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub reference_prices: HashMap<String, f64>,
+
+    /// List held symbols in the main view too, not just the holdings view
+    #[serde(default = "default_true")]
+    pub include_holdings: bool,
+
+    /// Add group members to the list; off, groups only label listed symbols
+    #[serde(default = "default_true")]
+    pub include_groups: bool,
+}
+
+impl Default for WatchlistConfig {
+    fn default() -> Self {
+        Self {
+            symbols: Vec::new(),
+            favorites: Vec::new(),
+            reference_prices: HashMap::new(),
+            include_holdings: true,
+            include_groups: true,
+        }
+    }
 }
 
 /// Single holding configuration.
@@ -87,10 +310,14 @@ pub struct WatchlistConfig {
 pub struct HoldingConfig {
     /// Ticker symbol
     pub symbol: String,
-    /// Number of shares/units
+    /// Number of shares/units: a number, or a string like "1.5k" or "0.5 BTC"
+    #[serde(deserialize_with = "deserialize_quantity")]
     pub quantity: f64,
     /// Cost basis per share
     pub cost_basis: f64,
+    /// Purchase date (YYYY-MM-DD), used for annualized returns
+    #[serde(default)]
+    pub date: Option<chrono::NaiveDate>,
 }
 
 impl From<HoldingConfig> for Holding {
@@ -99,8 +326,67 @@ impl From<HoldingConfig> for Holding {
             symbol: config.symbol,
             quantity: config.quantity,
             cost_basis: config.cost_basis,
+            purchase_date: config.date,
+        }
+    }
+}
+
+/// A quantity as written in the config: a plain number or a string form.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawQuantity {
+    Number(f64),
+    Text(String),
+}
+
+fn deserialize_quantity<'de, D>(deserializer: D) -> std::result::Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let quantity = match RawQuantity::deserialize(deserializer)? {
+        RawQuantity::Number(quantity) => quantity,
+        RawQuantity::Text(text) => parse_quantity(&text).map_err(serde::de::Error::custom)?,
+    };
+    if !(quantity.is_finite() && quantity > 0.0) {
+        return Err(serde::de::Error::custom(format!(
+            "quantity must be positive, got {}",
+            quantity
+        )));
+    }
+    Ok(quantity)
+}
+
+/// Parse a quantity like "1500", "1.5k", "2m" or "0.5 BTC". A trailing
+/// unit word is just for the reader and is ignored.
+fn parse_quantity(text: &str) -> std::result::Result<f64, String> {
+    let invalid = || {
+        format!(
+            "invalid quantity {:?} (expected e.g. 10, 1.5k or 0.5 BTC)",
+            text
+        )
+    };
+    let mut words = text.split_whitespace();
+    let number = words.next().ok_or_else(invalid)?;
+    if let Some(unit) = words.next() {
+        if words.next().is_some() || !unit.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(invalid());
         }
     }
+
+    let (digits, multiplier) = match number.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&number[..i], 1e3),
+        Some((i, 'm' | 'M')) => (&number[..i], 1e6),
+        _ => (number, 1.0),
+    };
+    let value: f64 = digits.parse().map_err(|_| invalid())?;
+    Ok(value * multiplier)
+}
+
+/// A note on a symbol, like why you bought it. Future you will want to know.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteConfig {
+    pub symbol: String,
+    pub text: String,
 }
 
 /// Display settings.
@@ -129,6 +415,100 @@ pub struct DisplayConfig {
     /// Sort in descending order
     #[serde(default = "default_true")]
     pub sort_descending: bool,
+
+    /// Weighting for the header's watchlist net change (equal, market_cap)
+    #[serde(default)]
+    pub net_change_weighting: Weighting,
+
+    /// Show a "my market" composite of every row's change, weighted this
+    /// way (equal, market_cap), with a sparkline of it across refreshes
+    #[serde(default)]
+    pub composite_index: Option<Weighting>,
+
+    /// Show the RS column: change percent minus the benchmark's
+    #[serde(default)]
+    pub relative_strength: bool,
+
+    /// Benchmark for the RS column
+    #[serde(default = "default_benchmark")]
+    pub benchmark: String,
+
+    /// Color rows by their own change or by it against the benchmark
+    /// (absolute, benchmark)
+    #[serde(default)]
+    pub color_basis: ColorBasis,
+
+    /// Show the PREV column: previous close, plus the gap before the open
+    #[serde(default)]
+    pub prev_close: bool,
+
+    /// Show the SESS% column: change since stonktop started (or S was pressed)
+    #[serde(default)]
+    pub since_launch: bool,
+
+    /// Show the EXT% column: pre-market/after-hours move, outside regular hours
+    #[serde(default)]
+    pub extended_hours: bool,
+
+    /// Change column(s) in the quotes table (both, change, change_percent)
+    #[serde(default)]
+    pub primary_change: PrimaryChange,
+
+    /// Decimal places for percentage columns
+    #[serde(default = "default_percent_precision")]
+    pub percent_precision: usize,
+
+    /// Rounding for monetary values (half_up, half_even, truncate)
+    #[serde(default)]
+    pub rounding: Rounding,
+
+    /// Base order of the symbol list (as-listed, alpha, grouped)
+    #[serde(default)]
+    pub initial_order: InitialOrder,
+
+    /// Number of portfolio value points kept for the sparkline
+    #[serde(default = "default_portfolio_history_len")]
+    pub portfolio_history_len: usize,
+
+    /// Blank lines between table rows (0 or 1)
+    #[serde(default)]
+    pub row_spacing: u16,
+
+    /// Alternate row backgrounds
+    #[serde(default)]
+    pub zebra: bool,
+
+    /// Line up the decimal points in the PRICE column
+    #[serde(default)]
+    pub align_decimals: bool,
+
+    /// Separators between table columns and a rule under the table
+    #[serde(default)]
+    pub grid: bool,
+
+    /// Treat quotes without a price as failures instead of showing $0.00 rows
+    #[serde(default)]
+    pub hide_invalid: bool,
+
+    /// Show volume as full grouped numbers instead of K/M/B suffixes
+    #[serde(default)]
+    pub full_volume: bool,
+
+    /// Drop the display currency from crypto pairs (BTC-USD shows as BTC)
+    #[serde(default)]
+    pub compact_crypto: bool,
+
+    /// Draw arrows, sparklines, borders and ellipses with Unicode glyphs
+    #[serde(default = "default_true")]
+    pub unicode: bool,
+
+    /// Prefix gain/loss cells with direction glyphs in addition to color
+    #[serde(default)]
+    pub change_glyphs: bool,
+
+    /// Characters of the name shown in the main table before truncating (8-60)
+    #[serde(default = "default_name_width")]
+    pub name_width: usize,
 }
 
 impl Default for DisplayConfig {
@@ -140,6 +520,29 @@ impl Default for DisplayConfig {
             show_separators: true,
             sort_by: "change_percent".to_string(),
             sort_descending: true,
+            net_change_weighting: Weighting::Equal,
+            composite_index: None,
+            primary_change: PrimaryChange::Both,
+            relative_strength: false,
+            benchmark: default_benchmark(),
+            color_basis: ColorBasis::default(),
+            prev_close: false,
+            since_launch: false,
+            extended_hours: false,
+            percent_precision: default_percent_precision(),
+            rounding: Rounding::default(),
+            initial_order: InitialOrder::default(),
+            portfolio_history_len: default_portfolio_history_len(),
+            row_spacing: 0,
+            zebra: false,
+            align_decimals: false,
+            grid: false,
+            hide_invalid: false,
+            full_volume: false,
+            compact_crypto: false,
+            unicode: true,
+            change_glyphs: false,
+            name_width: default_name_width(),
         }
     }
 }
@@ -147,6 +550,18 @@ impl Default for DisplayConfig {
 fn default_true() -> bool {
     true
 }
+fn default_benchmark() -> String {
+    "^GSPC".to_string()
+}
+fn default_percent_precision() -> usize {
+    2
+}
+fn default_name_width() -> usize {
+    20
+}
+fn default_portfolio_history_len() -> usize {
+    500
+}
 
 /// Color configuration using hex codes.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -202,16 +617,95 @@ fn default_border_color() -> String {
 
 impl Config {
     /// Load configuration from file.
-    pub fn load(path: &PathBuf) -> Result<Self> {
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::load_including(path, &mut Vec::new())
+    }
+
+    /// Load a config file and merge its includes, depth first.
+    /// `chain` holds the files currently being loaded, to catch cycles.
+    fn load_including(path: &Path, chain: &mut Vec<PathBuf>) -> Result<Self> {
         let content = fs::read_to_string(path)
             .with_context(|| format!("Failed to read config file: {}", path.display()))?;
 
-        let config: Config = toml::from_str(&content)
+        let mut config: Config = toml::from_str(&content)
             .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
 
+        config
+            .general
+            .validate()
+            .with_context(|| format!("Bad [general] in {}", path.display()))?;
+        config
+            .alerts
+            .validate()
+            .with_context(|| format!("Bad [alerts] in {}", path.display()))?;
+        config
+            .alerts
+            .quiet_hours()
+            .with_context(|| format!("Bad quiet hours in {}", path.display()))?;
+        config
+            .synthetics()
+            .with_context(|| format!("Bad [synthetics] in {}", path.display()))?;
+        for rule in &config.alerts.rules {
+            rule.condition.validate().with_context(|| {
+                format!("Bad alert rule for {} in {}", rule.symbol, path.display())
+            })?;
+        }
+
+        if config.include.is_empty() {
+            return Ok(config);
+        }
+
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        chain.push(canonical);
+
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        for include in config.include.clone() {
+            let include_path = base.join(&include);
+            let key = include_path
+                .canonicalize()
+                .unwrap_or_else(|_| include_path.clone());
+
+            if chain.contains(&key) {
+                let cycle: Vec<String> = chain
+                    .iter()
+                    .chain(std::iter::once(&key))
+                    .map(|p| p.display().to_string())
+                    .collect();
+                anyhow::bail!("Config include cycle: {}", cycle.join(" -> "));
+            }
+
+            let included = Self::load_including(&include_path, chain)
+                .with_context(|| format!("Included from {}", path.display()))?;
+            config.merge_included(included);
+        }
+
+        chain.pop();
         Ok(config)
     }
 
+    /// Merge the symbols, groups and holdings of an included config.
+    /// Everything else in an included file is ignored; the main file wins.
+    /// That includes notes: they're edited in the app and saved to the main
+    /// file, where an included copy would shadow the edit on the next load.
+    fn merge_included(&mut self, other: Config) {
+        for symbol in other.watchlist.symbols {
+            if !self.watchlist.symbols.contains(&symbol) {
+                self.watchlist.symbols.push(symbol);
+            }
+        }
+
+        for (name, symbols) in other.groups {
+            let group = self.groups.entry(name).or_default();
+            for symbol in symbols {
+                if !group.contains(&symbol) {
+                    group.push(symbol);
+                }
+            }
+        }
+
+        self.holdings.extend(other.holdings);
+    }
+
     /// Load configuration from default location or create default.
     pub fn load_or_default() -> Self {
         if let Some(path) = Self::default_config_path() {
@@ -235,7 +729,7 @@ impl Config {
     /// Save configuration to file.
     /// For when you finally decide to commit to your investment strategy.
     #[allow(dead_code)] // Config export feature - because backup plans are underrated
-    pub fn save(&self, path: &PathBuf) -> Result<()> {
+    pub fn save(&self, path: &Path) -> Result<()> {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).with_context(|| {
                 format!("Failed to create config directory: {}", parent.display())
@@ -244,13 +738,14 @@ impl Config {
 
         let content = toml::to_string_pretty(self).context("Failed to serialize configuration")?;
 
-        fs::write(path, content)
+        write_atomic(path, content.as_bytes())
             .with_context(|| format!("Failed to write config file: {}", path.display()))?;
 
         Ok(())
     }
 
-    /// Get all symbols from watchlist and holdings.
+    /// Get all symbols from watchlist, holdings and (unless turned off) groups.
+    /// Holdings are always fetched, for the holdings view.
     pub fn all_symbols(&self) -> Vec<String> {
         let mut symbols: Vec<String> = self.watchlist.symbols.clone();
 
@@ -262,7 +757,11 @@ impl Config {
         }
 
         // Add group symbols
-        for group_symbols in self.groups.values() {
+        let groups = self
+            .groups
+            .values()
+            .filter(|_| self.watchlist.include_groups);
+        for group_symbols in groups {
             for symbol in group_symbols {
                 if !symbols.contains(symbol) {
                     symbols.push(symbol.clone());
@@ -273,24 +772,225 @@ impl Config {
         symbols
     }
 
+    /// Held symbols that are only there for the holdings view: not on the
+    /// watchlist or in an included group, with `include_holdings` off.
+    pub fn holdings_only_symbols(&self) -> Vec<String> {
+        if self.watchlist.include_holdings {
+            return Vec::new();
+        }
+        // Compared the way App::new dedups: expanded, ignoring case, so
+        // `btc` on the watchlist lists a BTC-USD holding
+        let key = |symbol: &str| expand_symbol(symbol).to_ascii_uppercase();
+        let groups = self
+            .groups
+            .values()
+            .flatten()
+            .filter(|_| self.watchlist.include_groups);
+        let listed: HashSet<String> = self
+            .watchlist
+            .symbols
+            .iter()
+            .chain(groups)
+            .map(|symbol| key(symbol))
+            .collect();
+        self.holdings
+            .iter()
+            .map(|holding| &holding.symbol)
+            .filter(|symbol| !listed.contains(&key(symbol)))
+            .cloned()
+            .collect()
+    }
+
+    /// The `[synthetics]` rows, parsed and sorted by name.
+    pub fn synthetics(&self) -> Result<Vec<Synthetic>> {
+        let mut synthetics = self
+            .synthetics
+            .iter()
+            .map(|(name, definition)| Synthetic::parse(name, definition))
+            .collect::<Result<Vec<_>>>()?;
+        synthetics.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(synthetics)
+    }
+
     /// Get holdings as Holding structs.
     pub fn get_holdings(&self) -> Vec<Holding> {
         self.holdings.iter().cloned().map(Into::into).collect()
     }
 }
 
+/// Write a file atomically: write a sibling temp file, then rename it over the target.
+/// A crash halfway through leaves the previous file untouched instead of half a TOML.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    write_atomic_with(path, |file| Ok(file.write_all(content)?))
+}
+
+/// Write the favorites into the config file, leaving everything else in it
+/// (comments included) alone. Creates the file if there isn't one yet.
+pub fn save_favorites(path: &Path, favorites: &[String]) -> Result<()> {
+    let favorites: toml_edit::Array = favorites.iter().map(String::as_str).collect();
+    save_watchlist_entry(path, "favorites", toml_edit::value(favorites))
+}
+
+/// Write the reference prices into the config file, sorted by symbol,
+/// the same way as [`save_favorites`].
+pub fn save_reference_prices(path: &Path, prices: &HashMap<String, f64>) -> Result<()> {
+    let mut sorted: Vec<_> = prices.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+    let mut table = toml_edit::InlineTable::new();
+    for (symbol, price) in sorted {
+        table.insert(symbol, (*price).into());
+    }
+    save_watchlist_entry(path, "reference_prices", toml_edit::value(table))
+}
+
+/// Write the notes into the config file as `[[notes]]` tables, sorted by
+/// symbol, the same way as [`save_favorites`]. No notes, no `[[notes]]`.
+pub fn save_notes(path: &Path, notes: &HashMap<String, String>) -> Result<()> {
+    let mut sorted: Vec<_> = notes.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+    let mut tables = toml_edit::ArrayOfTables::new();
+    for (symbol, text) in sorted {
+        let mut table = toml_edit::Table::new();
+        table.insert("symbol", toml_edit::value(symbol.as_str()));
+        table.insert("text", toml_edit::value(text.as_str()));
+        tables.push(table);
+    }
+    edit_config_file(path, |doc| {
+        if tables.is_empty() {
+            doc.remove("notes");
+        } else {
+            doc.insert("notes", toml_edit::Item::ArrayOfTables(tables));
+        }
+        Ok(())
+    })
+}
+
+/// Write `[general] refresh_interval` into the config file, the same way
+/// as [`save_favorites`].
+pub fn save_refresh_interval(path: &Path, secs: f64) -> Result<()> {
+    save_entry(path, "general", "refresh_interval", toml_edit::value(secs))
+}
+
+/// Set one key under [watchlist] in the config file, keeping the rest as is.
+fn save_watchlist_entry(path: &Path, key: &str, item: toml_edit::Item) -> Result<()> {
+    save_entry(path, "watchlist", key, item)
+}
+
+/// Set one key under `[table]` in the config file, keeping the rest as is.
+fn save_entry(path: &Path, table: &str, key: &str, item: toml_edit::Item) -> Result<()> {
+    edit_config_file(path, |doc| {
+        let entries = doc
+            .entry(table)
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .with_context(|| format!("[{}] in the config file is not a table", table))?;
+        entries.insert(key, item);
+        Ok(())
+    })
+}
+
+/// Apply an edit to the config file's TOML document and write it back,
+/// comments and all. A missing file starts out empty.
+fn edit_config_file<F>(path: &Path, edit: F) -> Result<()>
+where
+    F: FnOnce(&mut toml_edit::DocumentMut) -> Result<()>,
+{
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("Failed to read config file: {}", path.display()))
+        }
+    };
+    let mut doc: toml_edit::DocumentMut = content
+        .parse()
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+    edit(&mut doc)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create config directory: {}", parent.display()))?;
+    }
+    write_atomic(path, doc.to_string().as_bytes())
+        .with_context(|| format!("Failed to write config file: {}", path.display()))
+}
+
+/// Atomic write where the caller streams the content into the temp file.
+fn write_atomic_with<F>(path: &Path, write: F) -> Result<()>
+where
+    F: FnOnce(&mut fs::File) -> Result<()>,
+{
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Not a file path: {}", path.display()))?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = fs::File::create(&tmp_path)
+        .with_context(|| format!("Failed to create temp file: {}", tmp_path.display()))
+        .and_then(|mut file| {
+            write(&mut file)?;
+            file.sync_all()
+                .with_context(|| format!("Failed to flush temp file: {}", tmp_path.display()))
+        })
+        .and_then(|_| {
+            fs::rename(&tmp_path, path)
+                .with_context(|| format!("Failed to replace {}", path.display()))
+        });
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
 /// Generate a sample configuration file content.
 pub fn sample_config() -> &'static str {
     r##"# Stonktop Configuration File
 # A top-like terminal UI for stock and crypto prices
 
+# Merge symbols, groups and holdings from other files (relative to this one)
+# include = ["crypto.toml", "tech.toml"]
+
 [general]
-# Refresh interval in seconds
+# Refresh interval in seconds (--delay overrides it; +/- change it live)
 refresh_interval = 5.0
 # API timeout in seconds
 timeout = 10
 # Default currency for display
 currency = "USD"
+# In the holdings view, refresh only the symbols you hold, this often (seconds).
+# The rest of the watchlist catches up at refresh_interval when it's on screen.
+# holdings_refresh_interval = 2.0
+# Refresh intervals below this (seconds) are raised to it, so a typo in
+# --delay doesn't turn into a hammering. Can't go below 1.0.
+min_refresh_interval = 1.0
+# Randomize each refresh delay by this fraction (0.1 = ±10%) so many
+# instances don't hit Yahoo in lockstep. 0 disables it.
+refresh_jitter = 0.1
+# Connection pool tuning for very frequent refreshes (advanced)
+# pool_idle_timeout = 90
+# pool_max_idle_per_host = 8
+# Override the browser User-Agent if requests start getting blocked
+# user_agent = "Mozilla/5.0 ..."
+# Yahoo region and language, for localized names outside the US
+region = "US"
+lang = "en-US"
+# Refreshes in a row where every symbol fails before a hard error (0 = never)
+max_failed_refreshes = 3
+# Refreshes in a row a symbol can go missing (delisted, or Yahoo forgot it)
+# before it's listed under "no data". Until it's back, it keeps its last
+# known price, marked stale. 0 never lists it.
+no_data_after = 3
+# Offer to save unsaved changes (reference prices, notes, the +/- refresh
+# interval) to this file on quit. false saves them without asking
+confirm_quit = true
+# Actions applied at startup (sort=<field>; reverse; view=<view>; filter=<type>)
+# on_start = "sort=price;view=holdings"
 
 [watchlist]
 # Symbols to track
@@ -303,18 +1003,33 @@ symbols = [
     "BTC-USD",
     "ETH-USD",
 ]
+# Pinned to the top whatever the sort (toggle with * in the app)
+# favorites = ["AAPL", "BTC-USD"]
+# Held symbols not listed above show up in the main view too; false keeps
+# them to the holdings view
+include_holdings = true
+# Group members not listed above join the list; false makes groups only
+# label symbols that are already on it
+include_groups = true
 
 # Portfolio holdings (optional)
 [[holdings]]
 symbol = "AAPL"
 quantity = 10
 cost_basis = 150.00
+# Optional purchase date for annualized returns
+date = "2023-06-01"
 
 [[holdings]]
 symbol = "BTC-USD"
 quantity = 0.5
 cost_basis = 30000.00
 
+# Notes for the detail view (edit with n in the app)
+# [[notes]]
+# symbol = "AAPL"
+# text = "Long-term hold, add below 150"
+
 [display]
 # Show summary header
 show_header = true
@@ -328,6 +1043,54 @@ show_separators = true
 sort_by = "change_percent"
 # Sort in descending order
 sort_descending = true
+# Header net change weighting: equal, market_cap
+net_change_weighting = "equal"
+# A personal benchmark in the header: the change of the whole watchlist,
+# holdings included, weighted equal or market_cap, with a sparkline
+# composite_index = "market_cap"
+# Change columns in the quotes table: both, change (dollars) or change_percent.
+# Verbose mode and the fundamentals view always show both.
+primary_change = "both"
+# RS column: each symbol's change % minus the benchmark's ("is it beating
+# the S&P today?"). The benchmark is fetched but not listed unless you watch it.
+relative_strength = false
+benchmark = "^GSPC"
+# Row colors: "absolute" (up is green) or "benchmark" (beating the benchmark
+# is green, so +0.5% on a +2% day is red). Fetches the benchmark and shows RS.
+color_basis = "absolute"
+# PREV column: yesterday's close, with the overnight gap during pre-market
+prev_close = false
+# SESS% column: change since stonktop started (S restarts it)
+since_launch = false
+# EXT% column: the pre-market/after-hours move, shown only outside regular hours
+extended_hours = false
+# Decimal places for percentage columns (0-6)
+percent_precision = 2
+# Portfolio value points kept (and persisted) for the sparkline
+portfolio_history_len = 500
+# Blank lines between table rows for readability (0 or 1)
+row_spacing = 0
+# Alternate row backgrounds
+zebra = false
+# Line up decimal points in the PRICE column ($150.00 over $0.000123)
+align_decimals = false
+# Column separators and a rule under the tables, for wide rows
+grid = false
+# Move quotes that come back without a price to the failures list instead of
+# showing $0.00 rows. Off by default so bad data doesn't go unnoticed.
+hide_invalid = false
+# Full grouped volume (1,234,567) instead of 1.23M
+full_volume = false
+# Show BTC-USD as just BTC. Pairs in another currency (BTC-EUR) keep their
+# suffix so they can't pass for dollars.
+compact_crypto = false
+# Name column width in the main table (8-60); the detail view shows it in full
+name_width = 20
+# Set to false for plain ASCII arrows, sparklines and borders on terminals
+# (or remote sessions) that mangle Unicode
+unicode = true
+# Prefix changes with direction glyphs so gains and losses don't rely on color
+change_glyphs = false
 
 [colors]
 # Colors in hex format
@@ -337,9 +1100,466 @@ neutral = "#ffffff"
 header = "#1e90ff"
 border = "#444444"
 
+# Symbols whose symbol cell always has this color, gain or loss
+# [symbol_colors]
+# TSLA = "#ff00ff"
+
+[alerts]
+# Desktop notification when any symbol moves this much (percent, either way)
+# notify_on_move_pct = 5.0
+# No notifications between these local times (the window may cross midnight)
+# quiet_start = "22:00"
+# quiet_end = "07:00"
+# Full-screen warning when the whole portfolio is down this much on the day;
+# any key dismisses it until the day gets another point worse
+# panic_pct = -5.0
+# Alerts still fire on the last known price when refreshes fail or the market
+# is shut; with this set, ones on quotes older than this many seconds say "(stale)"
+# stale_after = 900
+
+# Per-symbol rules: price_above, price_below, spread_above (basis points),
+# change_percent_above, change_percent_below (negative for drops)
+# [[alerts.rules]]
+# symbol = "AAPL"
+# condition = { price_below = 150.0 }
+# [[alerts.rules]]
+# symbol = "TSLA"
+# condition = { change_percent_below = -5.0 }
+
+# Ratio rows computed from two watched (or quietly fetched) symbols.
+# Shown in italics, and never counted in portfolio totals.
+# [synthetics]
+# "ETH/BTC" = "ETH-USD/BTC-USD"
+
 # Symbol groups (for organizing watchlists)
 [groups]
 tech = ["AAPL", "GOOGL", "MSFT", "NVDA"]
 crypto = ["BTC-USD", "ETH-USD", "SOL-USD"]
 "##
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("stonktop-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_include_merges_symbols_and_groups() {
+        let dir = temp_dir("include");
+        fs::write(
+            dir.join("crypto.toml"),
+            r#"
+            [watchlist]
+            symbols = ["BTC-USD", "AAPL"]
+
+            [groups]
+            crypto = ["BTC-USD", "ETH-USD"]
+            tech = ["NVDA"]
+
+            [[notes]]
+            symbol = "BTC-USD"
+            text = "from the include"
+            "#,
+        )
+        .unwrap();
+        let main = dir.join("config.toml");
+        fs::write(
+            &main,
+            r#"
+            include = ["crypto.toml"]
+
+            [watchlist]
+            symbols = ["AAPL"]
+
+            [groups]
+            tech = ["AAPL"]
+
+            [[notes]]
+            symbol = "AAPL"
+            text = "mine"
+            "#,
+        )
+        .unwrap();
+
+        let config = Config::load(&main).unwrap();
+        assert_eq!(config.watchlist.symbols, vec!["AAPL", "BTC-USD"]);
+        assert_eq!(config.groups["tech"], vec!["AAPL", "NVDA"]);
+        assert_eq!(config.groups["crypto"], vec!["BTC-USD", "ETH-USD"]);
+        // Notes are saved back to the main file, so only its own are loaded
+        let symbols: Vec<&str> = config.notes.iter().map(|n| n.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["AAPL"]);
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_include_cycle_and_missing_file_are_errors() {
+        let dir = temp_dir("include-errors");
+        fs::write(dir.join("a.toml"), r#"include = ["b.toml"]"#).unwrap();
+        fs::write(dir.join("b.toml"), r#"include = ["a.toml"]"#).unwrap();
+        fs::write(dir.join("c.toml"), r#"include = ["missing.toml"]"#).unwrap();
+
+        let err = format!("{:#}", Config::load(&dir.join("a.toml")).unwrap_err());
+        assert!(err.contains("include cycle"), "{}", err);
+
+        let err = format!("{:#}", Config::load(&dir.join("c.toml")).unwrap_err());
+        assert!(err.contains("missing.toml"), "{}", err);
+        assert!(err.contains("Included from"), "{}", err);
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_negative_alert_threshold_depends_on_condition() {
+        let dir = temp_dir("alert-thresholds");
+        let rule = |condition: &str| {
+            format!(
+                "[[alerts.rules]]\nsymbol = \"TSLA\"\ncondition = {{ {} }}\n",
+                condition
+            )
+        };
+        fs::write(dir.join("drop.toml"), rule("change_percent_below = -5.0")).unwrap();
+        fs::write(dir.join("price.toml"), rule("price_below = -5.0")).unwrap();
+
+        let config = Config::load(&dir.join("drop.toml")).unwrap();
+        assert_eq!(config.alerts.rules.len(), 1);
+
+        let err = format!("{:#}", Config::load(&dir.join("price.toml")).unwrap_err());
+        assert!(err.contains("TSLA"), "{}", err);
+        assert!(err.contains("price_below can't be negative"), "{}", err);
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_non_finite_jitter_is_rejected() {
+        let dir = temp_dir("jitter");
+        for (name, value) in [("nan", "nan"), ("inf", "inf"), ("ok", "0.2")] {
+            fs::write(
+                dir.join(format!("{}.toml", name)),
+                format!("[general]\nrefresh_jitter = {}\n", value),
+            )
+            .unwrap();
+        }
+
+        let config = Config::load(&dir.join("ok.toml")).unwrap();
+        assert_eq!(config.general.refresh_jitter, 0.2);
+        for name in ["nan", "inf"] {
+            let err = format!(
+                "{:#}",
+                Config::load(&dir.join(format!("{}.toml", name))).unwrap_err()
+            );
+            assert!(err.contains("refresh_jitter"), "{}", err);
+        }
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_out_of_range_refresh_intervals_are_rejected() {
+        let dir = temp_dir("intervals");
+        let fields = [
+            "refresh_interval",
+            "min_refresh_interval",
+            "holdings_refresh_interval",
+        ];
+        for field in fields {
+            for (name, value) in [("nan", "nan"), ("inf", "inf"), ("huge", "1e300")] {
+                let path = dir.join(format!("{}-{}.toml", field, name));
+                fs::write(&path, format!("[general]\n{} = {}\n", field, value)).unwrap();
+                let err = format!("{:#}", Config::load(&path).unwrap_err());
+                assert!(err.contains(field), "{}", err);
+            }
+
+            let path = dir.join(format!("{}-ok.toml", field));
+            fs::write(&path, format!("[general]\n{} = 30\n", field)).unwrap();
+            Config::load(&path).unwrap();
+        }
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_holdings_only_symbols_match_like_the_watchlist() {
+        let mut config = Config::default();
+        config.watchlist.include_holdings = false;
+        config.watchlist.symbols = vec!["aapl".to_string(), "btc".to_string()];
+        config
+            .groups
+            .insert("crypto".to_string(), vec!["eth.x".to_string()]);
+        config.holdings = ["AAPL", "BTC-USD", "ETH-USD", "TSLA"]
+            .into_iter()
+            .map(|symbol| HoldingConfig {
+                symbol: symbol.to_string(),
+                quantity: 1.0,
+                cost_basis: 1.0,
+                date: None,
+            })
+            .collect();
+
+        assert_eq!(config.holdings_only_symbols(), vec!["TSLA"]);
+        config.watchlist.include_groups = false;
+        assert_eq!(config.holdings_only_symbols(), vec!["ETH-USD", "TSLA"]);
+    }
+
+    #[test]
+    fn test_non_finite_alert_thresholds_are_rejected() {
+        let dir = temp_dir("alert-nan");
+        for (name, alerts) in [
+            ("move", "notify_on_move_pct = nan"),
+            ("panic", "panic_pct = -inf"),
+            ("stale", "stale_after = inf"),
+            (
+                "rule",
+                "[[alerts.rules]]\nsymbol = \"TSLA\"\ncondition = { price_above = nan }",
+            ),
+            ("ok", "notify_on_move_pct = 5.0\npanic_pct = 3.0"),
+        ] {
+            fs::write(
+                dir.join(format!("{}.toml", name)),
+                format!("[alerts]\n{}\n", alerts),
+            )
+            .unwrap();
+        }
+
+        let config = Config::load(&dir.join("ok.toml")).unwrap();
+        assert_eq!(config.alerts.notify_on_move_pct, Some(5.0));
+        for (name, field) in [
+            ("move", "notify_on_move_pct"),
+            ("panic", "panic_pct"),
+            ("stale", "stale_after"),
+            ("rule", "price_above"),
+        ] {
+            let err = format!(
+                "{:#}",
+                Config::load(&dir.join(format!("{}.toml", name))).unwrap_err()
+            );
+            assert!(err.contains(field), "{}", err);
+        }
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_quiet_hours_need_both_ends() {
+        let dir = temp_dir("quiet-hours");
+        fs::write(
+            dir.join("ok.toml"),
+            "[alerts]\nquiet_start = \"22:00\"\nquiet_end = \"07:00\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("half.toml"), "[alerts]\nquiet_start = \"22:00\"\n").unwrap();
+
+        let config = Config::load(&dir.join("ok.toml")).unwrap();
+        assert!(config.alerts.quiet_hours().unwrap().is_some());
+        let err = format!("{:#}", Config::load(&dir.join("half.toml")).unwrap_err());
+        assert!(err.contains("quiet_end"), "{}", err);
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_save_notes_round_trip() {
+        let dir = temp_dir("notes");
+        let path = dir.join("config.toml");
+        fs::write(
+            &path,
+            "# mine\n[watchlist]\nsymbols = [\"AAPL\"]\n\n[[notes]]\nsymbol = \"OLD\"\ntext = \"gone\"\n",
+        )
+        .unwrap();
+
+        let notes = HashMap::from([
+            ("MSFT".to_string(), "cloud".to_string()),
+            ("AAPL".to_string(), "iPhone \"cycle\"".to_string()),
+        ]);
+        save_notes(&path, &notes).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("# mine"));
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.watchlist.symbols, vec!["AAPL"]);
+        assert_eq!(
+            config.notes,
+            vec![
+                NoteConfig {
+                    symbol: "AAPL".to_string(),
+                    text: "iPhone \"cycle\"".to_string(),
+                },
+                NoteConfig {
+                    symbol: "MSFT".to_string(),
+                    text: "cloud".to_string(),
+                },
+            ]
+        );
+
+        save_notes(&path, &HashMap::new()).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("notes"));
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_save_reference_prices_round_trip() {
+        let dir = temp_dir("reference-prices");
+        let path = dir.join("config.toml");
+        fs::write(&path, "[watchlist]\nsymbols = [\"AAPL\"]\n").unwrap();
+
+        let prices = HashMap::from([("MSFT".to_string(), 310.5), ("AAPL".to_string(), 150.0)]);
+        save_reference_prices(&path, &prices).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("reference_prices = { AAPL = 150.0, MSFT = 310.5 }"));
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.watchlist.symbols, vec!["AAPL"]);
+        assert_eq!(config.watchlist.reference_prices, prices);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_save_refresh_interval_keeps_general() {
+        let dir = temp_dir("refresh-interval");
+        let path = dir.join("config.toml");
+        fs::write(
+            &path,
+            "[general]
+refresh_interval = 5.0 # tweak me
+timeout = 3
+",
+        )
+        .unwrap();
+
+        save_refresh_interval(&path, 2.5).unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.general.refresh_interval, 2.5);
+        assert_eq!(config.general.timeout, 3);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_holding_date_is_optional() {
+        let config: Config = toml::from_str(
+            r#"
+            [[holdings]]
+            symbol = "AAPL"
+            quantity = 10
+            cost_basis = 150.0
+            date = "2023-06-01"
+
+            [[holdings]]
+            symbol = "MSFT"
+            quantity = 1
+            cost_basis = 300.0
+            "#,
+        )
+        .unwrap();
+
+        let holdings = config.get_holdings();
+        assert_eq!(
+            holdings[0].purchase_date,
+            chrono::NaiveDate::from_ymd_opt(2023, 6, 1)
+        );
+        assert_eq!(holdings[1].purchase_date, None);
+    }
+
+    #[test]
+    fn test_parse_quantity_suffixes() {
+        assert_eq!(parse_quantity("1.5k"), Ok(1500.0));
+        assert_eq!(parse_quantity("2M"), Ok(2_000_000.0));
+        assert_eq!(parse_quantity("0.5 BTC"), Ok(0.5));
+        assert_eq!(parse_quantity(" 10 "), Ok(10.0));
+        assert!(parse_quantity("").is_err());
+        assert!(parse_quantity("1.5x").is_err());
+        assert!(parse_quantity("1 2 3").is_err());
+    }
+
+    #[test]
+    fn test_holding_quantity_strings_and_validation() {
+        let parse = |quantity: &str| {
+            toml::from_str::<Config>(&format!(
+                "[[holdings]]\nsymbol = \"AAPL\"\nquantity = {}\ncost_basis = 1.0\n",
+                quantity
+            ))
+        };
+        assert_eq!(parse(r#""1.5k""#).unwrap().holdings[0].quantity, 1500.0);
+        assert_eq!(parse("10").unwrap().holdings[0].quantity, 10.0);
+        assert_eq!(parse("0.25").unwrap().holdings[0].quantity, 0.25);
+
+        let error = parse("-5").unwrap_err().to_string();
+        assert!(error.contains("quantity must be positive"), "{}", error);
+        assert!(parse(r#""-1k""#).is_err());
+        assert!(parse("0").is_err());
+        let error = parse(r#""lots""#).unwrap_err().to_string();
+        assert!(error.contains("invalid quantity"), "{}", error);
+    }
+
+    #[test]
+    fn test_sample_config_parses() {
+        let config: Config = toml::from_str(sample_config()).unwrap();
+        assert!(!config.watchlist.symbols.is_empty());
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = temp_dir("atomic-ok");
+        let path = dir.join("state.json");
+        fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(!dir.join(".state.json.tmp").exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_write_atomic_failure_keeps_previous_file() {
+        let dir = temp_dir("atomic-fail");
+        let path = dir.join("state.json");
+        fs::write(&path, "precious").unwrap();
+
+        // Simulate dying halfway through the write
+        let result = write_atomic_with(&path, |file| {
+            file.write_all(b"half of a ")?;
+            anyhow::bail!("disk on fire")
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "precious");
+        assert!(!dir.join(".state.json.tmp").exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_save_favorites_keeps_comments() {
+        let dir = temp_dir("favorites");
+        let path = dir.join("config.toml");
+        fs::write(
+            &path,
+            "# my precious comments\n[watchlist]\nsymbols = [\"AAPL\", \"MSFT\"] # tech\n",
+        )
+        .unwrap();
+
+        save_favorites(&path, &["MSFT".to_string()]).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("# my precious comments"));
+        assert!(content.contains("# tech"));
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.watchlist.symbols, vec!["AAPL", "MSFT"]);
+        assert_eq!(config.watchlist.favorites, vec!["MSFT"]);
+
+        // A missing file is created
+        let fresh = dir.join("fresh.toml");
+        save_favorites(&fresh, &["BTC-USD".to_string()]).unwrap();
+        assert_eq!(
+            Config::load(&fresh).unwrap().watchlist.favorites,
+            vec!["BTC-USD"]
+        );
+        fs::remove_dir_all(&dir).ok();
+    }
+}
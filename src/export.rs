@@ -0,0 +1,500 @@
+//! Batch output in formats meant for other programs (and inboxes).
+//!
+//! The terminal table is for you. This is for the people you forward
+//! your losses to.
+
+use crate::app::App;
+use crate::cli::ExportFormat;
+use crate::models::{Quote, QuoteType};
+use crate::ui::{
+    format_market_cap, format_money, format_price, format_signed_money, format_volume,
+};
+use anyhow::{Context, Result};
+use std::fmt::Write;
+
+/// Render one batch iteration in the requested format. `first` is whether
+/// it opens the output: later CSV iterations carry on under the same header.
+pub fn render(app: &App, format: ExportFormat, first: bool) -> Result<String> {
+    match format {
+        ExportFormat::Html => Ok(render_html(app)),
+        ExportFormat::Json => render_json(app),
+        ExportFormat::Csv => render_csv(app, first),
+    }
+}
+
+/// The quotes on screen as a single line of JSON. Straight from `Quote`'s
+/// Serialize, so a new field shows up here without anyone remembering to.
+fn render_json(app: &App) -> Result<String> {
+    let quotes = app.display_quotes();
+    let mut out = serde_json::to_string(&quotes).context("Failed to serialize quotes")?;
+    out.push('\n');
+    Ok(out)
+}
+
+/// The quotes on screen as CSV rows with the same columns as the JSON,
+/// after the header when `header` is set.
+fn render_csv(app: &App, header: bool) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new());
+    if header {
+        writer
+            .write_record(csv_header()?)
+            .context("Failed to write the CSV header")?;
+    }
+    for quote in app.display_quotes() {
+        writer
+            .serialize(quote)
+            .with_context(|| format!("Failed to write {} as CSV", quote.symbol))?;
+    }
+    let bytes = writer.into_inner().context("Failed to finish the CSV")?;
+    String::from_utf8(bytes).context("CSV output is not UTF-8")
+}
+
+/// `Quote`'s field names in declaration order, as the csv crate would head
+/// them. Written by hand so an empty watchlist still gets a header.
+fn csv_header() -> Result<Vec<String>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .serialize(Quote::default())
+        .context("Failed to build the CSV header")?;
+    let bytes = writer
+        .into_inner()
+        .context("Failed to build the CSV header")?;
+    let mut reader = csv::Reader::from_reader(bytes.as_slice());
+    let header = reader
+        .headers()
+        .context("Failed to build the CSV header")?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    Ok(header)
+}
+
+/// The quotes on screen squeezed onto one line for a status bar:
+/// `AAPL 150.2 +1.2% | BTC 43k -0.5%`. Crypto pairs in the display
+/// currency go by their base asset, others keep their quote currency
+/// (`ETH-BTC`); `colors` paints each move green or red with ANSI escapes.
+pub fn ticker_line(app: &App, colors: bool) -> String {
+    app.display_quotes()
+        .into_iter()
+        .map(|quote| {
+            let symbol = match quote.quote_type {
+                QuoteType::Cryptocurrency => app.strip_display_currency(&quote.symbol),
+                _ => &quote.symbol,
+            };
+            let change = format!("{:+.1}%", quote.change_percent);
+            let change = match colors {
+                true if quote.change_percent > 0.0 => format!("\x1b[32m{}\x1b[0m", change),
+                true if quote.change_percent < 0.0 => format!("\x1b[31m{}\x1b[0m", change),
+                _ => change,
+            };
+            format!("{} {} {}", symbol, compact_price(quote.price), change)
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// A price in as few characters as still tell you something:
+/// 43k, 1.2M, 150.2, 3.42, 0.0012.
+fn compact_price(price: f64) -> String {
+    let magnitude = price.abs();
+    if magnitude >= 1_000_000.0 {
+        format!("{:.1}M", price / 1_000_000.0)
+    } else if magnitude >= 10_000.0 {
+        format!("{:.0}k", price / 1_000.0)
+    } else if magnitude >= 100.0 {
+        format!("{:.1}", price)
+    } else if magnitude >= 1.0 {
+        format!("{:.2}", price)
+    } else {
+        format!("{:.4}", price)
+    }
+}
+
+// Inline style attributes on every element, since Gmail and Outlook
+// strip or ignore <style> blocks and the colors would go with them.
+const TABLE_STYLE: &str = "border-collapse: collapse; font-family: monospace;";
+const TH_STYLE: &str = "padding: 2px 8px; border-bottom: 1px solid #ddd; \
+                        text-align: left; background: #333; color: #fff;";
+const TD_STYLE: &str = "padding: 2px 8px; border-bottom: 1px solid #ddd;";
+const NUM_STYLE: &str = "padding: 2px 8px; border-bottom: 1px solid #ddd; text-align: right;";
+const GAIN_STYLE: &str =
+    "padding: 2px 8px; border-bottom: 1px solid #ddd; text-align: right; color: #0a7d32;";
+const LOSS_STYLE: &str =
+    "padding: 2px 8px; border-bottom: 1px solid #ddd; text-align: right; color: #c62828;";
+
+/// A styled HTML table of the quotes (or holdings, in holdings view),
+/// with gains and losses colored.
+fn render_html(app: &App) -> String {
+    let mut out = String::new();
+
+    // Writing to a String can't fail, so the results are ignored throughout
+    let _ = writeln!(out, "<!DOCTYPE html>");
+    let _ = writeln!(out, "<html>");
+    let _ = writeln!(
+        out,
+        "<head><meta charset=\"utf-8\"><title>Stonktop</title></head>"
+    );
+    let _ = writeln!(out, "<body>");
+    let _ = writeln!(out, "<table style=\"{}\">", TABLE_STYLE);
+
+    if app.show_holdings {
+        write_header_row(
+            &mut out,
+            &[
+                "Symbol", "Name", "Price", "Qty", "Value", "Cost", "P/L", "P/L%",
+            ],
+        );
+        for quote in app.display_quotes() {
+            let Some(holding) = app.holdings.get(&quote.symbol) else {
+                continue;
+            };
+            let pnl = holding.profit_loss(quote.price);
+            let pnl_pct = holding.profit_loss_percent(quote.price);
+            let _ = writeln!(
+                out,
+                "<tr>{}{}{}{}{}{}{}{}</tr>",
+                td(&quote.symbol),
+                td(&quote.name),
+                td_num(&format_price(quote.price, quote.quote_type)),
+                td_num(&format!("{:.4}", holding.quantity)),
+                td_num(&format_money(
+                    holding.current_value(quote.price),
+                    app.rounding
+                )),
+                td_num(&format_money(holding.total_cost(), app.rounding)),
+                td_change(&format_signed_money(pnl, app.rounding), pnl),
+                td_change(&format_pct(pnl_pct, app.percent_precision), pnl_pct),
+            );
+        }
+    } else {
+        write_header_row(
+            &mut out,
+            &[
+                "Symbol", "Name", "Price", "Change", "Chg%", "Volume", "Mkt Cap",
+            ],
+        );
+        for quote in app.display_quotes() {
+            let _ = writeln!(out, "<tr>{}</tr>", quote_cells(app, quote));
+        }
+    }
+
+    let _ = writeln!(out, "</table>");
+    let _ = writeln!(out, "</body>");
+    let _ = writeln!(out, "</html>");
+    out
+}
+
+fn quote_cells(app: &App, quote: &Quote) -> String {
+    [
+        td(&quote.symbol),
+        td(&quote.name),
+        td_num(&format_price(quote.price, quote.quote_type)),
+        td_change(&format!("{:+.2}", quote.change), quote.change),
+        td_change(
+            &format_pct(quote.change_percent, app.percent_precision),
+            quote.change_percent,
+        ),
+        td_num(&format_volume(quote.volume, app.full_volume)),
+        td_num(&format_market_cap(quote.market_cap)),
+    ]
+    .concat()
+}
+
+fn write_header_row(out: &mut String, headers: &[&str]) {
+    let cells: String = headers
+        .iter()
+        .map(|h| format!("<th style=\"{}\">{}</th>", TH_STYLE, escape_html(h)))
+        .collect();
+    let _ = writeln!(out, "<tr>{}</tr>", cells);
+}
+
+fn td(text: &str) -> String {
+    td_styled(TD_STYLE, text)
+}
+
+fn td_num(text: &str) -> String {
+    td_styled(NUM_STYLE, text)
+}
+
+/// A number cell colored by the sign of `value`.
+fn td_change(text: &str, value: f64) -> String {
+    let style = if value > 0.0 {
+        GAIN_STYLE
+    } else if value < 0.0 {
+        LOSS_STYLE
+    } else {
+        NUM_STYLE
+    };
+    td_styled(style, text)
+}
+
+fn td_styled(style: &str, text: &str) -> String {
+    format!("<td style=\"{}\">{}</td>", style, escape_html(text))
+}
+
+fn format_pct(value: f64, precision: usize) -> String {
+    format!("{:+.*}%", precision, value)
+}
+
+/// Escape the characters that mean something in HTML text and attributes.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Args;
+    use crate::config::Config;
+    use crate::models::Holding;
+    use clap::Parser;
+
+    fn test_app() -> App {
+        let mut app = App::new(&Args::parse_from(["stonktop"]), &Config::default()).unwrap();
+        app.quotes = vec![
+            Quote {
+                symbol: "T".to_string(),
+                name: "AT&T <Inc>".to_string(),
+                price: 20.0,
+                change: 0.5,
+                change_percent: 2.5,
+                ..Default::default()
+            },
+            Quote {
+                symbol: "XOM".to_string(),
+                name: "Exxon \"Mobil\"".to_string(),
+                price: 100.0,
+                change: -1.0,
+                change_percent: -1.0,
+                ..Default::default()
+            },
+        ];
+        app
+    }
+
+    /// Every field `Quote` serializes, sorted.
+    fn quote_fields() -> Vec<String> {
+        let value = serde_json::to_value(Quote::default()).unwrap();
+        let mut fields: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+        // serde_json's map is sorted unless preserve_order is on; CSV isn't
+        fields.sort();
+        fields
+    }
+
+    #[test]
+    fn test_json_export_has_every_quote_field() {
+        let json = render(&test_app(), ExportFormat::Json, true).unwrap();
+        assert_eq!(json.lines().count(), 1);
+
+        let quotes: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(quotes.len(), 2);
+        for quote in &quotes {
+            let mut fields: Vec<String> = quote.as_object().unwrap().keys().cloned().collect();
+            fields.sort();
+            assert_eq!(fields, quote_fields());
+        }
+        // Spot checks, in case Quote and the export ever stop sharing a source
+        for field in [
+            "symbol",
+            "change_percent",
+            "market_cap",
+            "extended_price",
+            "synthetic",
+        ] {
+            assert!(quote_fields().iter().any(|f| f == field), "{}", field);
+        }
+        assert_eq!(quotes[0]["name"], "AT&T <Inc>");
+        assert_eq!(quotes[1]["change_percent"], -1.0);
+    }
+
+    #[test]
+    fn test_csv_export_has_every_quote_field() {
+        let csv = render(&test_app(), ExportFormat::Csv, true).unwrap();
+        let mut lines = csv.lines();
+
+        let mut header: Vec<String> = lines
+            .next()
+            .unwrap()
+            .split(',')
+            .map(str::to_string)
+            .collect();
+        assert_eq!(header[0], "symbol");
+        header.sort();
+        assert_eq!(header, quote_fields());
+
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("T,AT&T <Inc>,20.0,0.5,2.5,"));
+        // Quotes in a name get doubled, not dropped
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("XOM,\"Exxon \"\"Mobil\"\"\",100.0,"));
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn test_csv_header_once_per_run_even_when_empty() {
+        let mut app = test_app();
+        let first = render(&app, ExportFormat::Csv, true).unwrap();
+        let next = render(&app, ExportFormat::Csv, false).unwrap();
+        assert_eq!(first.lines().count(), 3);
+        // Later iterations only add rows, so the whole run is one CSV
+        assert_eq!(next.lines().count(), 2);
+        assert!(next.starts_with("T,"));
+
+        app.quotes.clear();
+        let empty = render(&app, ExportFormat::Csv, true).unwrap();
+        assert_eq!(empty, first.lines().next().unwrap().to_string() + "\n");
+        assert!(empty.starts_with("symbol,name,price,"));
+        assert_eq!(render(&app, ExportFormat::Csv, false).unwrap(), "");
+    }
+
+    #[test]
+    fn test_ticker_line() {
+        let mut app = test_app();
+        app.quotes = vec![
+            Quote {
+                symbol: "AAPL".to_string(),
+                price: 150.24,
+                change_percent: 1.23,
+                ..Default::default()
+            },
+            Quote {
+                symbol: "BTC-USD".to_string(),
+                price: 43_210.0,
+                change_percent: -0.5,
+                quote_type: QuoteType::Cryptocurrency,
+                ..Default::default()
+            },
+            Quote {
+                symbol: "DOGE-USD".to_string(),
+                price: 0.08123,
+                quote_type: QuoteType::Cryptocurrency,
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(
+            ticker_line(&app, false),
+            "AAPL 150.2 +1.2% | BTC 43k -0.5% | DOGE 0.0812 +0.0%"
+        );
+
+        // Pairs in another currency keep it, so they don't read as dollars
+        let mut pairs = test_app();
+        pairs.quotes = [("ETH-BTC", 0.05), ("BTC-EUR", 39_000.0)]
+            .into_iter()
+            .map(|(symbol, price)| Quote {
+                symbol: symbol.to_string(),
+                price,
+                quote_type: QuoteType::Cryptocurrency,
+                ..Default::default()
+            })
+            .collect();
+        assert_eq!(
+            ticker_line(&pairs, false),
+            "ETH-BTC 0.0500 +0.0% | BTC-EUR 39k +0.0%"
+        );
+        assert_eq!(
+            ticker_line(&app, true),
+            "AAPL 150.2 \x1b[32m+1.2%\x1b[0m | BTC 43k \x1b[31m-0.5%\x1b[0m | DOGE 0.0812 +0.0%"
+        );
+
+        app.top = Some(1);
+        assert_eq!(ticker_line(&app, false), "AAPL 150.2 +1.2%");
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_html_export_is_well_formed() {
+        let html = render(&test_app(), ExportFormat::Html, true).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.trim_end().ends_with("</html>"));
+        for tag in ["html", "head", "body", "table", "tr"] {
+            assert_eq!(
+                html.matches(&format!("<{}", tag)).count(),
+                html.matches(&format!("</{}>", tag)).count(),
+                "unbalanced <{}>",
+                tag
+            );
+        }
+        // Header plus one row per quote
+        assert_eq!(html.matches("<tr>").count(), 3);
+
+        assert!(html.contains("AT&amp;T &lt;Inc&gt;</td>"));
+        assert!(html.contains("Exxon &quot;Mobil&quot;</td>"));
+        // Colors ride on the cells themselves: email clients drop <style>
+        assert!(!html.contains("<style") && !html.contains("class="));
+        assert!(html.contains("color: #0a7d32;\">+2.50%</td>"));
+        assert!(html.contains("color: #c62828;\">-1.00%</td>"));
+    }
+
+    #[test]
+    fn test_html_export_holdings() {
+        let mut app = test_app();
+        app.show_holdings = true;
+        app.holdings.insert(
+            "XOM".to_string(),
+            Holding {
+                symbol: "XOM".to_string(),
+                quantity: 2.0,
+                cost_basis: 150.0,
+                purchase_date: None,
+            },
+        );
+
+        let html = render(&app, ExportFormat::Html, true).unwrap();
+
+        // Only positions get a row
+        assert_eq!(html.matches("<tr>").count(), 2);
+        assert!(html.contains("\">P/L%</th>"));
+        assert!(html.contains("color: #c62828;\">-100.00</td>"));
+        assert!(!html.contains("AT&amp;T"));
+    }
+
+    #[test]
+    fn test_html_export_uses_configured_rounding() {
+        let mut app = test_app();
+        app.show_holdings = true;
+        app.rounding = crate::models::Rounding::HalfUp;
+        app.holdings.insert(
+            "T".to_string(),
+            Holding {
+                symbol: "T".to_string(),
+                quantity: 0.05025,
+                cost_basis: 0.0,
+                purchase_date: None,
+            },
+        );
+
+        // 0.05025 * 20 = 1.005
+        let html = render(&app, ExportFormat::Html, true).unwrap();
+        assert!(html.contains("text-align: right;\">1.01</td>"));
+
+        app.rounding = crate::models::Rounding::Truncate;
+        let html = render(&app, ExportFormat::Html, true).unwrap();
+        assert!(html.contains("text-align: right;\">1.00</td>"));
+    }
+}
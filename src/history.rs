@@ -0,0 +1,188 @@
+//! Portfolio value history that survives restarts.
+//!
+//! A diary of your net worth, one refresh at a time. Reading it is optional.
+
+use crate::config::write_atomic;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name for the persisted portfolio value series.
+pub const PORTFOLIO_HISTORY_FILE: &str = "portfolio_history.json";
+
+/// File name for the persisted daily net worth snapshots.
+pub const PORTFOLIO_SNAPSHOTS_FILE: &str = "portfolio_snapshots.json";
+
+/// Days of snapshots kept, about ten years.
+pub const MAX_SNAPSHOTS: usize = 3650;
+
+/// A single point in the portfolio value series.
+pub type ValuePoint = (DateTime<Utc>, f64);
+
+/// End-of-day-ish portfolio value: the last value seen on that date.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub date: NaiveDate,
+    pub value: f64,
+}
+
+/// Get the path of a state file stored next to the config file.
+pub fn state_file(config_path: &Path, name: &str) -> PathBuf {
+    config_path
+        .parent()
+        .map(|dir| dir.join(name))
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Load a persisted value series. A missing file is just an empty history.
+pub fn load_value_history(path: &Path) -> Result<Vec<ValuePoint>> {
+    load_json(path)
+}
+
+/// Save a value series as JSON.
+pub fn save_value_history(path: &Path, history: &[ValuePoint]) -> Result<()> {
+    save_json(path, history)
+}
+
+/// Load the daily snapshots. A missing file means no snapshots yet.
+pub fn load_snapshots(path: &Path) -> Result<Vec<Snapshot>> {
+    load_json(path)
+}
+
+/// Save the daily snapshots as JSON.
+pub fn save_snapshots(path: &Path, snapshots: &[Snapshot]) -> Result<()> {
+    save_json(path, snapshots)
+}
+
+fn load_json<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read history file: {}", path.display()))?;
+
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse history file: {}", path.display()))
+}
+
+fn save_json<T: Serialize>(path: &Path, items: &[T]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create state directory: {}", parent.display()))?;
+    }
+
+    let content = serde_json::to_string(items).context("Failed to serialize history")?;
+
+    write_atomic(path, content.as_bytes())
+        .with_context(|| format!("Failed to write history file: {}", path.display()))
+}
+
+/// Record today's portfolio value, replacing any earlier snapshot from the
+/// same day so there is one point per day. Keeps at most `max_len` days.
+pub fn record_snapshot(snapshots: &mut Vec<Snapshot>, date: NaiveDate, value: f64, max_len: usize) {
+    match snapshots.last_mut() {
+        Some(last) if last.date == date => last.value = value,
+        _ => snapshots.push(Snapshot { date, value }),
+    }
+    if snapshots.len() > max_len {
+        let excess = snapshots.len() - max_len;
+        snapshots.drain(..excess);
+    }
+}
+
+/// Extract the value series from snapshot records, oldest first.
+pub fn value_series(snapshots: &[Snapshot]) -> Vec<f64> {
+    let mut sorted: Vec<&Snapshot> = snapshots.iter().collect();
+    sorted.sort_by_key(|s| s.date);
+    sorted.into_iter().map(|s| s.value).collect()
+}
+
+/// Append a point and drop the oldest ones beyond `max_len`.
+pub fn push_capped(history: &mut Vec<ValuePoint>, point: ValuePoint, max_len: usize) {
+    history.push(point);
+    if history.len() > max_len {
+        let excess = history.len() - max_len;
+        history.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_capped_drops_oldest() {
+        let mut history = Vec::new();
+        for i in 0..5 {
+            push_capped(&mut history, (Utc::now(), i as f64), 3);
+        }
+        let values: Vec<f64> = history.iter().map(|(_, v)| *v).collect();
+        assert_eq!(values, vec![2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_value_history_round_trip() {
+        let dir = std::env::temp_dir().join(format!("stonktop-history-{}", std::process::id()));
+        let path = dir.join(PORTFOLIO_HISTORY_FILE);
+        let history = vec![(Utc::now(), 1000.0), (Utc::now(), 1010.5)];
+
+        save_value_history(&path, &history).unwrap();
+        let loaded = load_value_history(&path).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(loaded, history);
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, d).unwrap()
+    }
+
+    #[test]
+    fn test_record_snapshot_keeps_one_per_day() {
+        let mut snapshots = Vec::new();
+        record_snapshot(&mut snapshots, day(1), 100.0, 10);
+        record_snapshot(&mut snapshots, day(1), 105.0, 10);
+        record_snapshot(&mut snapshots, day(2), 110.0, 10);
+        assert_eq!(
+            snapshots,
+            vec![
+                Snapshot {
+                    date: day(1),
+                    value: 105.0
+                },
+                Snapshot {
+                    date: day(2),
+                    value: 110.0
+                },
+            ]
+        );
+
+        record_snapshot(&mut snapshots, day(3), 120.0, 2);
+        assert_eq!(snapshots[0].date, day(2));
+    }
+
+    #[test]
+    fn test_value_series_from_snapshots() {
+        // Hand-edited files may be out of order; the chart should not be
+        let snapshots: Vec<Snapshot> = serde_json::from_str(
+            r#"[{"date":"2024-03-02","value":110.0},
+                {"date":"2024-03-01","value":100.0},
+                {"date":"2024-03-03","value":95.5}]"#,
+        )
+        .unwrap();
+        assert_eq!(value_series(&snapshots), vec![100.0, 110.0, 95.5]);
+        assert!(value_series(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_state_file_lives_next_to_config() {
+        let path = state_file(
+            Path::new("/tmp/stonktop/config.toml"),
+            PORTFOLIO_HISTORY_FILE,
+        );
+        assert_eq!(path, PathBuf::from("/tmp/stonktop/portfolio_history.json"));
+    }
+}
@@ -0,0 +1,260 @@
+//! Technical indicators over price series.
+//!
+//! Squiggly lines that explain, with great confidence and in hindsight,
+//! exactly why the price did what it did.
+
+use std::time::{Duration, Instant};
+
+/// Default RSI lookback.
+pub const RSI_PERIOD: usize = 14;
+/// Default MACD fast/slow/signal periods.
+pub const MACD_PERIODS: (usize, usize, usize) = (12, 26, 9);
+/// Default Bollinger lookback and band width in standard deviations.
+pub const BOLLINGER_PERIOD: usize = 20;
+pub const BOLLINGER_K: f64 = 2.0;
+
+/// MACD line, signal line and their difference at the latest point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Macd {
+    pub macd: f64,
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+/// Bollinger bands at the latest point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bollinger {
+    pub upper: f64,
+    pub middle: f64,
+    pub lower: f64,
+}
+
+/// Simple moving average of the last `period` values.
+pub fn calculate_sma(values: &[f64], period: usize) -> Option<f64> {
+    if period == 0 || values.len() < period {
+        return None;
+    }
+    let window = &values[values.len() - period..];
+    Some(window.iter().sum::<f64>() / period as f64)
+}
+
+/// Exponential moving average series, seeded with the SMA of the first
+/// `period` values. The result starts at index `period - 1` of the input.
+pub fn ema_series(values: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || values.len() < period {
+        return Vec::new();
+    }
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let seed = values[..period].iter().sum::<f64>() / period as f64;
+
+    let mut series = Vec::with_capacity(values.len() - period + 1);
+    series.push(seed);
+    for value in &values[period..] {
+        let prev = series[series.len() - 1];
+        series.push(prev + alpha * (value - prev));
+    }
+    series
+}
+
+/// Relative strength index (Wilder smoothing) at the latest point.
+pub fn calculate_rsi(values: &[f64], period: usize) -> Option<f64> {
+    if period == 0 || values.len() <= period {
+        return None;
+    }
+
+    let mut gains = 0.0;
+    let mut losses = 0.0;
+    for pair in values[..=period].windows(2) {
+        let delta = pair[1] - pair[0];
+        if delta > 0.0 {
+            gains += delta;
+        } else {
+            losses -= delta;
+        }
+    }
+    let mut avg_gain = gains / period as f64;
+    let mut avg_loss = losses / period as f64;
+
+    for pair in values[period..].windows(2) {
+        let delta = pair[1] - pair[0];
+        let (gain, loss) = if delta > 0.0 {
+            (delta, 0.0)
+        } else {
+            (0.0, -delta)
+        };
+        avg_gain = (avg_gain * (period - 1) as f64 + gain) / period as f64;
+        avg_loss = (avg_loss * (period - 1) as f64 + loss) / period as f64;
+    }
+
+    if avg_loss == 0.0 {
+        return Some(if avg_gain == 0.0 { 50.0 } else { 100.0 });
+    }
+    let rs = avg_gain / avg_loss;
+    Some(100.0 - 100.0 / (1.0 + rs))
+}
+
+/// MACD at the latest point.
+pub fn calculate_macd(values: &[f64], fast: usize, slow: usize, signal: usize) -> Option<Macd> {
+    if fast == 0 || fast >= slow {
+        return None;
+    }
+    let fast_ema = ema_series(values, fast);
+    let slow_ema = ema_series(values, slow);
+    if slow_ema.is_empty() {
+        return None;
+    }
+
+    // Align the fast series with the (shorter) slow one
+    let offset = slow - fast;
+    let macd_line: Vec<f64> = slow_ema
+        .iter()
+        .zip(&fast_ema[offset..])
+        .map(|(slow, fast)| fast - slow)
+        .collect();
+
+    let signal_line = ema_series(&macd_line, signal);
+    let macd = *macd_line.last()?;
+    let signal = *signal_line.last()?;
+    Some(Macd {
+        macd,
+        signal,
+        histogram: macd - signal,
+    })
+}
+
+/// Bollinger bands over the last `period` values.
+pub fn calculate_bollinger(values: &[f64], period: usize, k: f64) -> Option<Bollinger> {
+    let middle = calculate_sma(values, period)?;
+    let window = &values[values.len() - period..];
+    let variance = window.iter().map(|v| (v - middle).powi(2)).sum::<f64>() / period as f64;
+    let band = k * variance.sqrt();
+    Some(Bollinger {
+        upper: middle + band,
+        middle,
+        lower: middle - band,
+    })
+}
+
+/// Timing for one indicator across all synthetic series.
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub name: &'static str,
+    pub total: Duration,
+    pub per_series: Duration,
+}
+
+/// Deterministic random-walk price series, so runs are comparable.
+pub fn synthetic_series(seed: u64, points: usize) -> Vec<f64> {
+    let mut state = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+    let mut price = 100.0;
+    (0..points)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            // Top 53 bits -> [0, 1), then a step of up to ±2%
+            let unit = (state >> 11) as f64 / (1u64 << 53) as f64;
+            price *= 1.0 + (unit - 0.5) * 0.04;
+            price
+        })
+        .collect()
+}
+
+/// Time RSI, MACD and Bollinger computation over `symbols` synthetic series.
+pub fn bench_indicators(symbols: usize, points: usize) -> Vec<BenchResult> {
+    let series: Vec<Vec<f64>> = (0..symbols as u64)
+        .map(|seed| synthetic_series(seed, points))
+        .collect();
+
+    let (fast, slow, signal) = MACD_PERIODS;
+    vec![
+        time("rsi", &series, |s| {
+            calculate_rsi(s, RSI_PERIOD);
+        }),
+        time("macd", &series, |s| {
+            calculate_macd(s, fast, slow, signal);
+        }),
+        time("bollinger", &series, |s| {
+            calculate_bollinger(s, BOLLINGER_PERIOD, BOLLINGER_K);
+        }),
+    ]
+}
+
+fn time(name: &'static str, series: &[Vec<f64>], f: impl Fn(&[f64])) -> BenchResult {
+    let start = Instant::now();
+    for s in series {
+        f(std::hint::black_box(s));
+    }
+    let total = start.elapsed();
+    BenchResult {
+        name,
+        total,
+        per_series: total / series.len().max(1) as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sma() {
+        assert_eq!(calculate_sma(&[1.0, 2.0, 3.0, 4.0], 2), Some(3.5));
+        assert_eq!(calculate_sma(&[1.0], 2), None);
+        assert_eq!(calculate_sma(&[1.0], 0), None);
+    }
+
+    #[test]
+    fn test_rsi_extremes() {
+        let rising: Vec<f64> = (0..30).map(|i| i as f64).collect();
+        assert_eq!(calculate_rsi(&rising, 14), Some(100.0));
+
+        let flat = vec![10.0; 30];
+        assert_eq!(calculate_rsi(&flat, 14), Some(50.0));
+
+        let falling: Vec<f64> = (0..30).map(|i| 100.0 - i as f64).collect();
+        assert!(calculate_rsi(&falling, 14).unwrap() < 1e-9);
+
+        assert_eq!(calculate_rsi(&rising[..14], 14), None);
+    }
+
+    #[test]
+    fn test_macd_flat_series_is_zero() {
+        let flat = vec![50.0; 60];
+        let macd = calculate_macd(&flat, 12, 26, 9).unwrap();
+        assert!(macd.macd.abs() < 1e-9);
+        assert!(macd.histogram.abs() < 1e-9);
+
+        // Not enough data for the signal line
+        assert!(calculate_macd(&flat[..30], 12, 26, 9).is_none());
+    }
+
+    #[test]
+    fn test_macd_uptrend_is_positive() {
+        let rising: Vec<f64> = (0..60).map(|i| 100.0 + i as f64).collect();
+        assert!(calculate_macd(&rising, 12, 26, 9).unwrap().macd > 0.0);
+    }
+
+    #[test]
+    fn test_bollinger() {
+        let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let bands = calculate_bollinger(&values, 8, 2.0).unwrap();
+        assert_eq!(bands.middle, 5.0);
+        assert_eq!(bands.upper, 9.0);
+        assert_eq!(bands.lower, 1.0);
+    }
+
+    #[test]
+    fn test_synthetic_series_is_deterministic() {
+        assert_eq!(synthetic_series(7, 50), synthetic_series(7, 50));
+        assert_ne!(synthetic_series(7, 50), synthetic_series(8, 50));
+        assert!(synthetic_series(1, 100).iter().all(|p| *p > 0.0));
+    }
+
+    #[test]
+    fn test_bench_reports_each_indicator() {
+        let results = bench_indicators(3, 100);
+        let names: Vec<_> = results.iter().map(|r| r.name).collect();
+        assert_eq!(names, vec!["rsi", "macd", "bollinger"]);
+    }
+}
@@ -6,41 +6,97 @@
 //! the thrill of watching your portfolio fluctuate directly to your
 //! command line. Now you can lose money AND look like a hacker!
 
+mod action;
+mod alerts;
 mod api;
 mod app;
+mod check;
 mod cli;
+mod clipboard;
 mod config;
+mod export;
+mod history;
+mod indicators;
 mod models;
+mod notify;
+mod provider;
+mod serve;
+mod synthetic;
 mod ui;
 
+use action::Action;
 use anyhow::Result;
-use app::App;
+use app::{App, PromptKind};
 use cli::Args;
 use config::Config;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Parse command line arguments
     let args = Args::parse_args();
 
-    // Load configuration
-    let config = if let Some(ref path) = args.config {
-        Config::load(path)?
-    } else {
-        Config::load_or_default()
+    // Developer benchmark, no network or terminal involved
+    if let Some(symbols) = args.bench_indicators {
+        run_bench_indicators(symbols);
+        return Ok(());
+    }
+
+    if args.keys {
+        print!("{}", action::format_bindings());
+        return Ok(());
+    }
+
+    // Load configuration. A check reports a broken default config instead
+    // of shrugging it off and carrying on with defaults.
+    let default_path = Config::default_config_path().filter(|path| path.exists());
+    let config = match (&args.config, default_path) {
+        (Some(path), _) => Config::load(path)?,
+        (None, Some(path)) if args.check => Config::load(&path)?,
+        _ => Config::load_or_default(),
     };
 
     // Create application state
     let mut app = App::new(&args, &config)?;
 
+    // Apply the startup sequence, if any (CLI wins over config)
+    if let Some(sequence) = args.on_start.as_ref().or(config.general.on_start.as_ref()) {
+        let actions = action::parse_start_sequence(sequence)?;
+        app.apply_start_sequence(&actions);
+    }
+
+    // Validate and describe, then leave before touching the terminal (or
+    // the network, unless a probe was asked for)
+    if args.check {
+        let failures = if args.probe {
+            Some(app.probe_symbols().await?)
+        } else {
+            None
+        };
+        let (report, problems) = check::report(&app, &config, failures.as_deref());
+        print!("{}", report);
+        if !problems.is_empty() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Pick up where the last session left off
+    let config_path = args.config.clone().or_else(Config::default_config_path);
+    if let Some(ref path) = config_path {
+        app.load_portfolio_history(path);
+    }
+
     // Check if we have any symbols to watch
     if app.symbols.is_empty() {
         eprintln!("Error: No symbols to watch.");
@@ -58,25 +114,74 @@ async fn main() -> Result<()> {
         std::process::exit(1);
     }
 
-    // Run in batch mode or interactive mode
-    if app.batch_mode {
+    // Run as a JSON server, in batch mode or interactive mode
+    let result = if let Some(addr) = args.serve {
+        serve::run(&mut app, addr).await
+    } else if app.batch_mode {
         run_batch(&mut app).await
     } else {
         run_interactive(&mut app).await
+    };
+
+    // Save persisted state on the way out, whichever mode we ran in. A
+    // read-only config directory gets one warning, not a failed exit.
+    if let Some(ref path) = config_path {
+        let errors = app.persist_all(path);
+        if !errors.is_empty() {
+            eprintln!(
+                "Warning: Couldn't save session state next to {}; it's lost for this run:",
+                path.display()
+            );
+            for error in errors {
+                eprintln!("  {:#}", error);
+            }
+        }
+    }
+
+    result
+}
+
+/// Print indicator timings over synthetic price series.
+fn run_bench_indicators(symbols: usize) {
+    let points = 100;
+    println!(
+        "Indicator benchmark: {} symbols x {} points",
+        symbols, points
+    );
+    for result in indicators::bench_indicators(symbols, points) {
+        println!(
+            "{:<10} total {:>10.3?}  per symbol {:>10.3?}",
+            result.name, result.total, result.per_series
+        );
     }
 }
 
 /// Run in batch mode (non-interactive, like top -b).
 async fn run_batch(app: &mut App) -> Result<()> {
+    let mut first = true;
     loop {
         app.refresh().await?;
-        ui::render_batch(app);
+        match app.export {
+            _ if app.ticker => println!("{}", export::ticker_line(app, app.ansi_colors)),
+            Some(format) => print!("{}", export::render(app, format, first)?),
+            None => {
+                let rows = app.batch_rows_to_print();
+                // Nothing moved enough: no table beats an empty one
+                if rows.as_ref().is_none_or(|rows| !rows.is_empty()) {
+                    ui::render_batch(app, rows.as_ref());
+                }
+            }
+        }
+        if let Some(problem) = app.offline.as_ref().or(app.error.as_ref()) {
+            eprintln!("{}", problem);
+        }
+        first = false;
 
         if app.should_quit() {
             break;
         }
 
-        tokio::time::sleep(app.refresh_interval).await;
+        tokio::time::sleep(app.refresh_delay()).await;
     }
 
     Ok(())
@@ -91,11 +196,9 @@ async fn run_interactive(app: &mut App) -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Initial fetch
-    app.refresh().await?;
-
-    // Main loop
-    let result = run_app(&mut terminal, app).await;
+    // Main loop, which also kicks off the first fetch
+    let tick_rate = app.tick_rate;
+    let result = run_app(&mut terminal, app, tick_rate).await;
 
     // Restore terminal
     disable_raw_mode()?;
@@ -113,27 +216,33 @@ async fn run_interactive(app: &mut App) -> Result<()> {
 async fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
+    tick_rate: Duration,
 ) -> Result<()> {
-    let tick_rate = Duration::from_millis(100);
+    let (results_tx, mut results_rx) = mpsc::unbounded_channel();
 
     loop {
+        // Pick up whatever the background fetch brought back
+        while let Ok(result) = results_rx.try_recv() {
+            app.apply_fetch(result);
+        }
+
         // Draw UI
+        app.advance_autoscroll(Instant::now());
+        app.mark_selected_seen();
         terminal.draw(|f| ui::render(f, app))?;
 
         // Handle events with timeout
-        if crossterm::event::poll(tick_rate)? {
-            if let Event::Key(key) = event::read()? {
-                // Skip if secure mode and it's a modifying command
-                if app.secure_mode {
-                    match key.code {
-                        KeyCode::Char('q') | KeyCode::Esc => app.quit(),
-                        KeyCode::Up | KeyCode::Char('k') => app.select_up(),
-                        KeyCode::Down | KeyCode::Char('j') => app.select_down(),
-                        _ => {}
+        if let Some(key) = next_key(tick_rate, event::poll, event::read)? {
+            app.stop_autoscroll();
+            // Skip if secure mode and it's a modifying command
+            if app.secure_mode {
+                if let Some(action) = Action::from_key(key.code, key.modifiers) {
+                    if action.allowed_in_secure_mode() {
+                        app.apply_action(action);
                     }
-                } else {
-                    handle_key_event(app, key.code, key.modifiers);
                 }
+            } else {
+                handle_key_event(app, key.code, key.modifiers);
             }
         }
 
@@ -142,78 +251,153 @@ async fn run_app(
             break;
         }
 
-        // Refresh data if needed
+        // Refresh data if needed, without waiting for it
         if app.needs_refresh() {
-            app.refresh().await?;
+            app.spawn_refresh(&results_tx);
         }
     }
 
     Ok(())
 }
 
+/// Wait up to one tick for a key press. `poll` and `read` are crossterm's,
+/// except in tests.
+fn next_key(
+    tick_rate: Duration,
+    poll: impl FnOnce(Duration) -> io::Result<bool>,
+    read: impl FnOnce() -> io::Result<Event>,
+) -> Result<Option<KeyEvent>> {
+    if !poll(tick_rate)? {
+        return Ok(None);
+    }
+    match read()? {
+        Event::Key(key) => Ok(Some(key)),
+        _ => Ok(None),
+    }
+}
+
 /// Handle keyboard input.
 fn handle_key_event(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+    // The panic warning covers everything, so it takes the first key
+    if app.dismiss_panic() {
+        return;
+    }
+
+    // A prompt takes every key until it's submitted or cancelled
+    if app.prompt.is_some() {
+        // Typing past a rejected entry is acknowledgement enough
+        app.dismiss_error();
+    }
+    if let Some(ref mut prompt) = app.prompt {
+        if prompt.kind == PromptKind::ConfirmQuit {
+            // Ctrl-C is an answer too: get out, and don't touch the config
+            if code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
+                app.answer_quit(false);
+                return;
+            }
+            match code {
+                KeyCode::Char('y' | 'Y') => app.answer_quit(true),
+                KeyCode::Char('n' | 'N') => app.answer_quit(false),
+                KeyCode::Esc => app.cancel_prompt(),
+                _ => {}
+            }
+            return;
+        }
+        match code {
+            KeyCode::Enter => app.submit_prompt(),
+            KeyCode::Esc => app.cancel_prompt(),
+            KeyCode::Backspace => {
+                prompt.buffer.pop();
+                app.prompt_changed();
+            }
+            KeyCode::Char(c) => {
+                prompt.buffer.push(c);
+                app.prompt_changed();
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    // The detail view follows the selection; anything else closes it
+    if app.show_detail {
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => app.select_up(),
+            KeyCode::Down | KeyCode::Char('j') => app.select_down(),
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => app.running = false,
+            _ => app.show_detail = false,
+        }
+        return;
+    }
+
     // Close help overlay on any key
     if app.show_help {
         app.show_help = false;
         return;
     }
 
-    // Clear error on any key
-    if app.error.is_some() {
-        app.error = None;
+    // Clear a transient error on any key (the offline banner stays put)
+    if app.dismiss_error() {
         return;
     }
 
-    match code {
-        // Quit
-        KeyCode::Char('q') | KeyCode::Esc => app.quit(),
-        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => app.quit(),
+    if let Some(action) = Action::from_key(code, modifiers) {
+        app.apply_action(action);
+    }
+}
 
-        // Navigation
-        KeyCode::Up | KeyCode::Char('k') => app.select_up(),
-        KeyCode::Down | KeyCode::Char('j') => app.select_down(),
-        KeyCode::Home | KeyCode::Char('g') => app.select_top(),
-        KeyCode::End | KeyCode::Char('G') => app.select_bottom(),
-        KeyCode::PageUp => {
-            for _ in 0..10 {
-                app.select_up();
-            }
-        }
-        KeyCode::PageDown => {
-            for _ in 0..10 {
-                app.select_down();
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::cell::Cell;
 
-        // Sorting
-        KeyCode::Char('s') => app.next_sort_order(),
-        KeyCode::Char('r') => app.toggle_sort_direction(),
-        KeyCode::Char('1') => app.set_sort_order(models::SortOrder::Symbol),
-        KeyCode::Char('2') => app.set_sort_order(models::SortOrder::Name),
-        KeyCode::Char('3') => app.set_sort_order(models::SortOrder::Price),
-        KeyCode::Char('4') => app.set_sort_order(models::SortOrder::Change),
-        KeyCode::Char('5') => app.set_sort_order(models::SortOrder::ChangePercent),
-        KeyCode::Char('6') => app.set_sort_order(models::SortOrder::Volume),
-        KeyCode::Char('7') => app.set_sort_order(models::SortOrder::MarketCap),
-
-        // Display toggles
-        KeyCode::Char('H') => app.toggle_holdings(),
-        KeyCode::Char('f') => app.toggle_fundamentals(),
-        KeyCode::Char('h') | KeyCode::Char('?') => app.toggle_help(),
-
-        // Refresh
-        KeyCode::Char(' ') | KeyCode::Char('R') => {
-            app.last_refresh = None; // Force refresh on next tick
-        }
+    #[test]
+    fn test_tick_rate_reaches_the_key_poll() {
+        let tick_rate = |args: &[&str]| {
+            let mut argv = vec!["stonktop"];
+            argv.extend_from_slice(args);
+            App::new(&Args::parse_from(argv), &Config::default())
+                .unwrap()
+                .tick_rate
+        };
+        assert_eq!(tick_rate(&[]), Duration::from_millis(100));
+        assert_eq!(tick_rate(&["--tick-ms", "10"]), Duration::from_millis(50));
+        assert_eq!(tick_rate(&["--tick-ms", "5000"]), Duration::from_secs(1));
 
-        // Groups
-        KeyCode::Tab => {
-            if !app.groups.is_empty() {
-                app.active_group = (app.active_group + 1) % app.groups.len();
-            }
-        }
+        let waited = Cell::new(None);
+        let poll = |timeout| {
+            waited.set(Some(timeout));
+            Ok(false)
+        };
+        let read = || -> io::Result<Event> { unreachable!("nothing to read") };
+        let key = next_key(tick_rate(&["--tick-ms", "250"]), poll, read).unwrap();
+        assert!(key.is_none());
+        assert_eq!(waited.get(), Some(Duration::from_millis(250)));
+
+        let key = next_key(
+            Duration::from_millis(100),
+            |_| Ok(true),
+            || Ok(Event::Key(KeyEvent::from(KeyCode::Char('q')))),
+        )
+        .unwrap();
+        assert_eq!(key.map(|k| k.code), Some(KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn test_ctrl_c_answers_the_quit_prompt() {
+        let mut app = App::new(&Args::parse_from(["stonktop"]), &Config::default()).unwrap();
+        app.prompt = Some(app::Prompt {
+            kind: PromptKind::ConfirmQuit,
+            buffer: String::new(),
+        });
+
+        // A plain 'c' isn't an answer
+        handle_key_event(&mut app, KeyCode::Char('c'), KeyModifiers::NONE);
+        assert!(app.prompt.is_some() && !app.should_quit());
 
-        _ => {}
+        handle_key_event(&mut app, KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert!(app.prompt.is_none());
+        assert!(app.should_quit());
     }
 }
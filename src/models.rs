@@ -2,7 +2,7 @@
 //!
 //! Structs that hold the numbers you'll obsessively refresh.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 /// Represents a financial quote for a stock or cryptocurrency.
@@ -37,6 +37,12 @@ pub struct Quote {
     pub avg_volume: u64,
     /// Market capitalization
     pub market_cap: Option<u64>,
+    /// Best bid, when the source provides one
+    #[serde(default)]
+    pub bid: Option<f64>,
+    /// Best ask, when the source provides one
+    #[serde(default)]
+    pub ask: Option<f64>,
     /// Currency of the quote
     pub currency: String,
     /// Exchange where the security is traded
@@ -45,8 +51,14 @@ pub struct Quote {
     pub quote_type: QuoteType,
     /// Market state (PRE, REGULAR, POST, CLOSED)
     pub market_state: MarketState,
+    /// Last pre-market or after-hours trade, when the source provides one
+    #[serde(default)]
+    pub extended_price: Option<f64>,
     /// Timestamp of the quote
     pub timestamp: DateTime<Utc>,
+    /// Computed from other quotes (see `[synthetics]`) rather than fetched
+    #[serde(default)]
+    pub synthetic: bool,
 }
 
 impl Default for Quote {
@@ -66,15 +78,48 @@ impl Default for Quote {
             volume: 0,
             avg_volume: 0,
             market_cap: None,
+            bid: None,
+            ask: None,
             currency: "USD".to_string(),
             exchange: String::new(),
             quote_type: QuoteType::Equity,
             market_state: MarketState::Closed,
+            extended_price: None,
             timestamp: Utc::now(),
+            synthetic: false,
         }
     }
 }
 
+/// A symbol that couldn't be fetched, and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchFailure {
+    pub symbol: String,
+    pub reason: String,
+}
+
+/// Everything one refresh brought back: the quotes, and the symbols that didn't make it.
+#[derive(Debug, Clone, Default)]
+pub struct QuoteBatch {
+    pub quotes: Vec<Quote>,
+    pub failures: Vec<FetchFailure>,
+    /// Problems that didn't cost a quote, like a truncated response we salvaged
+    pub warnings: Vec<String>,
+}
+
+impl Quote {
+    /// Bid/ask spread in basis points of the mid price.
+    /// None when either side is missing or the book looks broken.
+    pub fn spread_bps(&self) -> Option<f64> {
+        let (bid, ask) = (self.bid?, self.ask?);
+        if bid <= 0.0 || ask < bid {
+            return None;
+        }
+        let mid = (bid + ask) / 2.0;
+        Some((ask - bid) / mid * 10_000.0)
+    }
+}
+
 /// Type of financial instrument.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum QuoteType {
@@ -134,6 +179,110 @@ pub struct Holding {
     pub quantity: f64,
     /// Average cost basis per share
     pub cost_basis: f64,
+    /// When the position was opened, if known
+    #[serde(default)]
+    pub purchase_date: Option<NaiveDate>,
+}
+
+/// Holding periods shorter than this aren't annualized.
+/// A 3% pop over two days is not a 400% annual return, no matter how it feels.
+pub const MIN_ANNUALIZE_DAYS: i64 = 30;
+
+/// Compound annual growth rate, in percent, from a start and end value over `days`.
+/// Returns None for periods too short to annualize meaningfully or a zero start.
+pub fn annualized_return(start: f64, end: f64, days: i64) -> Option<f64> {
+    if days < MIN_ANNUALIZE_DAYS || start <= 0.0 || end < 0.0 {
+        return None;
+    }
+    let years = days as f64 / 365.25;
+    Some(((end / start).powf(1.0 / years) - 1.0) * 100.0)
+}
+
+/// Percent change from a reference price (e.g. your entry) to `price`.
+pub fn reference_change_percent(price: f64, reference: f64) -> Option<f64> {
+    if reference <= 0.0 {
+        return None;
+    }
+    Some((price - reference) / reference * 100.0)
+}
+
+/// Pre-market gap: the pre-market price against the previous close, in
+/// percent, previewing the opening gap. Only before the open, and only when
+/// there's a pre-market trade; `price` is still yesterday's regular close then.
+pub fn gap_percent(quote: &Quote) -> Option<f64> {
+    match quote.market_state {
+        MarketState::Pre => reference_change_percent(quote.extended_price?, quote.previous_close),
+        MarketState::Regular | MarketState::Post | MarketState::Closed => None,
+    }
+}
+
+/// Pre-market or after-hours move from the regular-session price, in percent.
+/// Only while those sessions are on; during the day it's just noise.
+pub fn extended_change_percent(quote: &Quote) -> Option<f64> {
+    match quote.market_state {
+        MarketState::Pre | MarketState::Post => {
+            reference_change_percent(quote.extended_price?, quote.price)
+        }
+        MarketState::Regular | MarketState::Closed => None,
+    }
+}
+
+/// How close to a 52-week extreme counts as making a new one, in percent.
+/// Yahoo's year range lags a bar behind, so "at the high" is rarely exact.
+const NEAR_EXTREME_PCT: f64 = 1.0;
+
+/// Advance/decline counts and 52-week extremes across a set of quotes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Breadth {
+    pub advancing: usize,
+    pub declining: usize,
+    pub unchanged: usize,
+    /// Quotes within [`NEAR_EXTREME_PCT`] of their 52-week high
+    pub new_highs: usize,
+    /// Quotes within [`NEAR_EXTREME_PCT`] of their 52-week low
+    pub new_lows: usize,
+}
+
+impl Breadth {
+    /// Count a set of quotes.
+    pub fn of<'a>(quotes: impl IntoIterator<Item = &'a Quote>) -> Self {
+        let mut breadth = Self::default();
+        for quote in quotes {
+            if quote.change_percent > 0.0 {
+                breadth.advancing += 1;
+            } else if quote.change_percent < 0.0 {
+                breadth.declining += 1;
+            } else {
+                breadth.unchanged += 1;
+            }
+            let near = |extreme: f64| {
+                extreme > 0.0 && (quote.price - extreme).abs() / extreme * 100.0 <= NEAR_EXTREME_PCT
+            };
+            if near(quote.year_high) || (quote.year_high > 0.0 && quote.price > quote.year_high) {
+                breadth.new_highs += 1;
+            } else if near(quote.year_low) || (quote.price > 0.0 && quote.price < quote.year_low) {
+                breadth.new_lows += 1;
+            }
+        }
+        breadth
+    }
+
+    /// Advancers per decliner. None without decliners, since "5 to 0" isn't a ratio.
+    pub fn advance_decline_ratio(&self) -> Option<f64> {
+        (self.declining > 0).then(|| self.advancing as f64 / self.declining as f64)
+    }
+}
+
+/// Typical price `(high + low + last) / 3`, a poor man's VWAP built from
+/// the day range. Not a real VWAP (that needs every trade), but close enough
+/// to tell whether the price sits above or below where most of the day happened.
+pub fn vwap_proxy(quote: &Quote) -> Option<f64> {
+    let points = [quote.day_high, quote.day_low, quote.price];
+    // A missing range comes back as zeros, which would drag the average down
+    if points.iter().any(|p| *p <= 0.0 || !p.is_finite()) {
+        return None;
+    }
+    Some(points.iter().sum::<f64>() / 3.0)
 }
 
 impl Holding {
@@ -152,6 +301,13 @@ impl Holding {
         self.current_value(price) - self.total_cost()
     }
 
+    /// Calculate the annualized return given current price and today's date.
+    /// None when there's no purchase date or the position is too young.
+    pub fn annualized_return(&self, price: f64, today: NaiveDate) -> Option<f64> {
+        let days = (today - self.purchase_date?).num_days();
+        annualized_return(self.cost_basis, price, days)
+    }
+
     /// Calculate profit/loss percentage given current price.
     pub fn profit_loss_percent(&self, price: f64) -> f64 {
         if self.total_cost() == 0.0 {
@@ -162,6 +318,83 @@ impl Holding {
     }
 }
 
+/// How quotes are weighted when rolled up into a single aggregate number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Weighting {
+    /// Every symbol counts the same
+    #[default]
+    Equal,
+    /// Bigger companies move the needle more (symbols without a cap are skipped)
+    MarketCap,
+}
+
+/// Which change column the quotes table leads with. The other one comes
+/// back in verbose mode and the fundamentals view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimaryChange {
+    /// Dollars and percent side by side
+    #[default]
+    Both,
+    /// Dollars only
+    Change,
+    /// Percent only
+    ChangePercent,
+}
+
+/// What decides a row's gain/loss color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorBasis {
+    /// Up is green, down is red
+    #[default]
+    Absolute,
+    /// Beating the benchmark is green, even on a red day (and vice versa)
+    Benchmark,
+}
+
+/// How monetary values are rounded to cents for display and export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    /// Ties go away from zero, like the till at the corner shop
+    HalfUp,
+    /// Ties go to the even digit (banker's rounding)
+    #[default]
+    HalfEven,
+    /// Drop everything past the last decimal
+    Truncate,
+}
+
+impl Rounding {
+    /// Round `value` to `decimals` places. Binary noise is snapped away
+    /// first so 1.005 counts as a tie rather than 1.00499999...
+    pub fn round(self, value: f64, decimals: u32) -> f64 {
+        let factor = 10f64.powi(decimals as i32);
+        let scaled = (value * factor * 1e6).round() / 1e6;
+        let rounded = match self {
+            Rounding::HalfUp => scaled.round(),
+            Rounding::HalfEven => scaled.round_ties_even(),
+            Rounding::Truncate => scaled.trunc(),
+        };
+        rounded / factor
+    }
+}
+
+/// The base order of the symbol list, before any interactive sort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InitialOrder {
+    /// Command line first, then config, as written
+    #[default]
+    AsListed,
+    /// Alphabetical by symbol
+    Alpha,
+    /// By group name, ungrouped symbols last; as listed within each group
+    Grouped,
+}
+
 /// Sort order for displaying quotes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum SortOrder {
@@ -171,8 +404,20 @@ pub enum SortOrder {
     Price,
     Change,
     ChangePercent,
+    AbsChangePercent,
     Volume,
     MarketCap,
+    Exchange,
+    /// Crypto pairs clustered by base asset (BTC-USD next to BTC-EUR)
+    BaseAsset,
+    /// Holdings only: position value
+    Value,
+    /// Holdings only: profit/loss in currency
+    ProfitLoss,
+    /// Holdings only: profit/loss percent
+    ProfitLossPercent,
+    /// Holdings only: today's change in position value
+    DayChange,
 }
 
 impl SortOrder {
@@ -183,12 +428,50 @@ impl SortOrder {
             SortOrder::Name => SortOrder::Price,
             SortOrder::Price => SortOrder::Change,
             SortOrder::Change => SortOrder::ChangePercent,
-            SortOrder::ChangePercent => SortOrder::Volume,
+            SortOrder::ChangePercent => SortOrder::AbsChangePercent,
+            SortOrder::AbsChangePercent => SortOrder::Volume,
             SortOrder::Volume => SortOrder::MarketCap,
-            SortOrder::MarketCap => SortOrder::Symbol,
+            SortOrder::MarketCap => SortOrder::Exchange,
+            SortOrder::Exchange => SortOrder::BaseAsset,
+            SortOrder::BaseAsset => SortOrder::Value,
+            SortOrder::Value => SortOrder::ProfitLoss,
+            SortOrder::ProfitLoss => SortOrder::ProfitLossPercent,
+            SortOrder::ProfitLossPercent => SortOrder::DayChange,
+            SortOrder::DayChange => SortOrder::Symbol,
+        }
+    }
+
+    /// Direction a field starts in when you switch to it: A to Z for text,
+    /// biggest first for numbers.
+    pub fn default_direction(self) -> SortDirection {
+        match self {
+            SortOrder::Symbol | SortOrder::Name | SortOrder::Exchange | SortOrder::BaseAsset => {
+                SortDirection::Ascending
+            }
+            SortOrder::Price
+            | SortOrder::Change
+            | SortOrder::ChangePercent
+            | SortOrder::AbsChangePercent
+            | SortOrder::Volume
+            | SortOrder::MarketCap
+            | SortOrder::Value
+            | SortOrder::ProfitLoss
+            | SortOrder::ProfitLossPercent
+            | SortOrder::DayChange => SortDirection::Descending,
         }
     }
 
+    /// Whether this order needs a holding to mean anything.
+    pub fn holdings_only(&self) -> bool {
+        matches!(
+            self,
+            SortOrder::Value
+                | SortOrder::ProfitLoss
+                | SortOrder::ProfitLossPercent
+                | SortOrder::DayChange
+        )
+    }
+
     /// Get column header name.
     pub fn header(&self) -> &'static str {
         match self {
@@ -197,10 +480,69 @@ impl SortOrder {
             SortOrder::Price => "PRICE",
             SortOrder::Change => "CHANGE",
             SortOrder::ChangePercent => "CHG%",
+            SortOrder::AbsChangePercent => "|CHG%|",
             SortOrder::Volume => "VOLUME",
             SortOrder::MarketCap => "MKT CAP",
+            SortOrder::Exchange => "EXCH",
+            SortOrder::BaseAsset => "BASE",
+            SortOrder::Value => "VALUE",
+            SortOrder::ProfitLoss => "P/L",
+            SortOrder::ProfitLossPercent => "P/L%",
+            SortOrder::DayChange => "TODAY",
+        }
+    }
+}
+
+/// The holdings table's columns, left to right, with the sort order behind
+/// each one's number hotkey. Columns without one don't sort.
+pub const HOLDINGS_COLUMNS: [(&str, Option<SortOrder>); 10] = [
+    ("SYMBOL", Some(SortOrder::Symbol)),
+    ("NAME", Some(SortOrder::Name)),
+    ("PRICE", Some(SortOrder::Price)),
+    ("QTY", None),
+    ("VALUE", Some(SortOrder::Value)),
+    ("COST", None),
+    ("P/L", Some(SortOrder::ProfitLoss)),
+    ("P/L%", Some(SortOrder::ProfitLossPercent)),
+    ("TODAY", Some(SortOrder::DayChange)),
+    ("ANN%", None),
+];
+
+/// Which table the main area shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum View {
+    #[default]
+    Quotes,
+    Holdings,
+    Fundamentals,
+}
+
+impl View {
+    /// Sort orders behind the number hotkeys (1 first), matching the
+    /// columns this view actually shows. `None` is a column that doesn't sort.
+    pub fn hotkey_columns(&self) -> Vec<Option<SortOrder>> {
+        match self {
+            View::Quotes | View::Fundamentals => [
+                SortOrder::Symbol,
+                SortOrder::Name,
+                SortOrder::Price,
+                SortOrder::Change,
+                SortOrder::ChangePercent,
+                SortOrder::Volume,
+                SortOrder::MarketCap,
+                SortOrder::AbsChangePercent,
+            ]
+            .into_iter()
+            .map(Some)
+            .collect(),
+            View::Holdings => HOLDINGS_COLUMNS.iter().map(|(_, order)| *order).collect(),
         }
     }
+
+    /// Whether the view can be sorted by an order.
+    pub fn can_sort_by(&self, order: SortOrder) -> bool {
+        *self == View::Holdings || !order.holdings_only()
+    }
 }
 
 /// Sort direction.
@@ -219,3 +561,238 @@ impl SortDirection {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn test_reference_change_percent() {
+        assert_eq!(reference_change_percent(110.0, 100.0), Some(10.0));
+        assert_eq!(reference_change_percent(90.0, 100.0), Some(-10.0));
+        assert_eq!(reference_change_percent(90.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_breadth_counts() {
+        let quote = |change_percent: f64, price: f64| Quote {
+            change_percent,
+            price,
+            year_low: 50.0,
+            year_high: 100.0,
+            ..Default::default()
+        };
+        let quotes = [
+            quote(2.0, 99.5),  // up, at the high
+            quote(1.0, 101.0), // up, past a stale high
+            quote(3.0, 75.0),  // up
+            quote(-1.0, 50.2), // down, at the low
+            quote(0.0, 75.0),  // flat
+        ];
+
+        let breadth = Breadth::of(&quotes);
+        assert_eq!(
+            breadth,
+            Breadth {
+                advancing: 3,
+                declining: 1,
+                unchanged: 1,
+                new_highs: 2,
+                new_lows: 1,
+            }
+        );
+        assert_eq!(breadth.advance_decline_ratio(), Some(3.0));
+
+        // Nothing declining, or nothing at all, has no ratio
+        assert_eq!(Breadth::of(&quotes[..2]).advance_decline_ratio(), None);
+        let empty = Breadth::of(&[]);
+        assert_eq!(empty, Breadth::default());
+        assert_eq!(empty.advance_decline_ratio(), None);
+        // No 52-week range means no extremes, not a new low at $0
+        assert_eq!(Breadth::of(&[Quote::default()]).new_lows, 0);
+    }
+
+    #[test]
+    fn test_vwap_proxy() {
+        let quote = |price: f64, day_high: f64, day_low: f64| Quote {
+            price,
+            day_high,
+            day_low,
+            ..Default::default()
+        };
+        assert!(approx(
+            vwap_proxy(&quote(105.0, 110.0, 90.0)).unwrap(),
+            305.0 / 3.0
+        ));
+        assert!(approx(vwap_proxy(&quote(50.0, 50.0, 50.0)).unwrap(), 50.0));
+        // No day range yet
+        assert_eq!(vwap_proxy(&quote(105.0, 0.0, 0.0)), None);
+        assert_eq!(vwap_proxy(&quote(0.0, 110.0, 90.0)), None);
+    }
+
+    #[test]
+    fn test_default_direction_per_field() {
+        for order in [
+            SortOrder::Symbol,
+            SortOrder::Name,
+            SortOrder::Exchange,
+            SortOrder::BaseAsset,
+        ] {
+            assert_eq!(
+                order.default_direction(),
+                SortDirection::Ascending,
+                "{:?}",
+                order
+            );
+        }
+        for order in [
+            SortOrder::Price,
+            SortOrder::ChangePercent,
+            SortOrder::AbsChangePercent,
+            SortOrder::MarketCap,
+            SortOrder::Value,
+            SortOrder::DayChange,
+        ] {
+            assert_eq!(
+                order.default_direction(),
+                SortDirection::Descending,
+                "{:?}",
+                order
+            );
+        }
+    }
+
+    #[test]
+    fn test_gap_percent() {
+        let gap = |extended_price: Option<f64>, previous_close: f64, market_state| {
+            gap_percent(&Quote {
+                price: 100.0,
+                extended_price,
+                previous_close,
+                market_state,
+                ..Default::default()
+            })
+        };
+        let pre = MarketState::Pre;
+        assert!(approx(gap(Some(103.0), 100.0, pre).unwrap(), 3.0));
+        assert!(approx(gap(Some(98.5), 100.0, pre).unwrap(), -1.5));
+        assert_eq!(gap(Some(100.0), 100.0, pre), Some(0.0));
+        // No pre-market trade yet, or no close to gap from
+        assert_eq!(gap(None, 100.0, pre), None);
+        assert_eq!(gap(Some(103.0), 0.0, pre), None);
+        // Outside pre-market there's no gap to preview
+        assert_eq!(gap(Some(103.0), 100.0, MarketState::Regular), None);
+        assert_eq!(gap(Some(103.0), 100.0, MarketState::Post), None);
+    }
+
+    #[test]
+    fn test_extended_change_percent_by_market_state() {
+        let extended = |market_state| {
+            extended_change_percent(&Quote {
+                price: 100.0,
+                extended_price: Some(102.0),
+                market_state,
+                ..Default::default()
+            })
+        };
+        assert!(approx(extended(MarketState::Pre).unwrap(), 2.0));
+        assert!(approx(extended(MarketState::Post).unwrap(), 2.0));
+        assert_eq!(extended(MarketState::Regular), None);
+        assert_eq!(extended(MarketState::Closed), None);
+
+        // Nothing traded outside the session yet
+        let quote = Quote {
+            price: 100.0,
+            market_state: MarketState::Post,
+            ..Default::default()
+        };
+        assert_eq!(extended_change_percent(&quote), None);
+    }
+
+    #[test]
+    fn test_annualized_return_one_year() {
+        // Years are 365.25 days, so 365 days is a hair short of one and
+        // CAGR lands within a hundredth of the simple return
+        let r = annualized_return(100.0, 110.0, 365).unwrap();
+        assert!((r - 10.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_annualized_return_multi_year() {
+        // Doubling over two years is ~41.42% a year
+        let r = annualized_return(100.0, 200.0, 730).unwrap();
+        assert!((r - 41.42).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_annualized_return_half_year_compounds_up() {
+        // +10% in half a year annualizes to ~21%
+        let r = annualized_return(100.0, 110.0, 183).unwrap();
+        assert!((r - 20.9).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_annualized_return_loss() {
+        let r = annualized_return(100.0, 50.0, 730).unwrap();
+        assert!(r < 0.0 && r > -100.0);
+    }
+
+    #[test]
+    fn test_annualized_return_short_period_skipped() {
+        assert_eq!(annualized_return(100.0, 103.0, 2), None);
+        assert_eq!(
+            annualized_return(100.0, 103.0, MIN_ANNUALIZE_DAYS - 1),
+            None
+        );
+        assert!(annualized_return(100.0, 103.0, MIN_ANNUALIZE_DAYS).is_some());
+    }
+
+    #[test]
+    fn test_holding_annualized_return_without_date() {
+        let holding = Holding {
+            symbol: "AAPL".to_string(),
+            quantity: 1.0,
+            cost_basis: 100.0,
+            purchase_date: None,
+        };
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        assert_eq!(holding.annualized_return(150.0, today), None);
+
+        let dated = Holding {
+            purchase_date: NaiveDate::from_ymd_opt(2024, 1, 1),
+            ..holding
+        };
+        let r = dated.annualized_return(150.0, today).unwrap();
+        assert!(approx(r, annualized_return(100.0, 150.0, 366).unwrap()));
+    }
+
+    #[test]
+    fn test_rounding_half_up_boundaries() {
+        assert_eq!(Rounding::HalfUp.round(1.005, 2), 1.01);
+        assert_eq!(Rounding::HalfUp.round(1.015, 2), 1.02);
+        assert_eq!(Rounding::HalfUp.round(-1.005, 2), -1.01);
+        assert_eq!(Rounding::HalfUp.round(1.0049, 2), 1.0);
+    }
+
+    #[test]
+    fn test_rounding_half_even_boundaries() {
+        assert_eq!(Rounding::HalfEven.round(1.005, 2), 1.0);
+        assert_eq!(Rounding::HalfEven.round(1.015, 2), 1.02);
+        assert_eq!(Rounding::HalfEven.round(1.025, 2), 1.02);
+        assert_eq!(Rounding::HalfEven.round(-1.005, 2), -1.0);
+        assert_eq!(Rounding::HalfEven.round(1.0051, 2), 1.01);
+    }
+
+    #[test]
+    fn test_rounding_truncate_boundaries() {
+        assert_eq!(Rounding::Truncate.round(1.005, 2), 1.0);
+        assert_eq!(Rounding::Truncate.round(1.999, 2), 1.99);
+        assert_eq!(Rounding::Truncate.round(-1.009, 2), -1.0);
+        // 0.29 * 100 is 28.999999999999996 in binary; still 0.29
+        assert_eq!(Rounding::Truncate.round(0.29, 2), 0.29);
+    }
+}
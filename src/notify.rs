@@ -0,0 +1,194 @@
+//! Desktop notifications.
+//!
+//! For when staring at the terminal all day isn't enough and you want
+//! your OS to interrupt you with bad news too.
+
+use anyhow::{Context, Result};
+use chrono::NaiveTime;
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+
+/// Something that can put a message in front of the user.
+pub trait Notifier: Send + Sync {
+    /// Show a notification.
+    fn notify(&self, title: &str, body: &str) -> Result<()>;
+}
+
+/// Notifications through the platform's own tooling
+/// (`notify-send` on Linux, `osascript` on macOS).
+#[derive(Debug, Default)]
+pub struct DesktopNotifier;
+
+impl Notifier for DesktopNotifier {
+    fn notify(&self, title: &str, body: &str) -> Result<()> {
+        let mut command = if cfg!(target_os = "macos") {
+            let script = format!(
+                "display notification {:?} with title {:?}",
+                body.replace('"', "'"),
+                title.replace('"', "'")
+            );
+            let mut cmd = Command::new("osascript");
+            cmd.arg("-e").arg(script);
+            cmd
+        } else if cfg!(unix) {
+            let mut cmd = Command::new("notify-send");
+            cmd.arg(title).arg(body);
+            cmd
+        } else {
+            // No dependency-free backend here; stay quiet rather than fail
+            return Ok(());
+        };
+
+        // Called from the async event loop, and notify-send can hang on a
+        // busy (or missing) notification daemon: start it and move on
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        // Someone has to wait on it, or it lingers as a zombie
+        std::thread::spawn(move || child.wait());
+
+        Ok(())
+    }
+}
+
+/// A daily window, in local time, when notifications stay quiet.
+/// The end is exclusive, and a window may run past midnight (22:00-07:00).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Parse a window from two "HH:MM" times.
+    pub fn parse(start: &str, end: &str) -> Result<Self> {
+        let time = |s: &str| {
+            NaiveTime::parse_from_str(s.trim(), "%H:%M")
+                .with_context(|| format!("Invalid time {:?}, expected HH:MM", s))
+        };
+        Ok(Self {
+            start: time(start)?,
+            end: time(end)?,
+        })
+    }
+
+    /// Whether `time` falls inside the window. Equal ends make an empty window.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            // Spans midnight
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Watches for symbols crossing a percent-move threshold.
+///
+/// Fires once when a symbol's move goes beyond the threshold and re-arms
+/// only after it falls back inside, so a symbol hovering at -5.01% doesn't
+/// ping you every refresh.
+#[derive(Debug)]
+pub struct MoveWatcher {
+    threshold: f64,
+    /// Whether each symbol was beyond the threshold at the last check
+    beyond: HashMap<String, bool>,
+}
+
+impl MoveWatcher {
+    /// Create a watcher for moves of at least `threshold` percent either way.
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold: threshold.abs(),
+            beyond: HashMap::new(),
+        }
+    }
+
+    /// Record a symbol's current move. Returns true if it just crossed the threshold.
+    /// A symbol already past the threshold when first seen doesn't count as a crossing.
+    pub fn check(&mut self, symbol: &str, change_percent: f64) -> bool {
+        let is_beyond = change_percent.abs() >= self.threshold;
+        match self.beyond.insert(symbol.to_string(), is_beyond) {
+            Some(was_beyond) => is_beyond && !was_beyond,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_watcher_fires_on_crossing() {
+        let mut watcher = MoveWatcher::new(5.0);
+        assert!(!watcher.check("AAPL", 1.0));
+        assert!(watcher.check("AAPL", 5.5));
+    }
+
+    #[test]
+    fn test_move_watcher_debounces_until_rearmed() {
+        let mut watcher = MoveWatcher::new(5.0);
+        watcher.check("AAPL", 0.0);
+        assert!(watcher.check("AAPL", -6.0));
+        assert!(!watcher.check("AAPL", -7.0));
+        assert!(!watcher.check("AAPL", -5.01));
+
+        // Back inside the band re-arms it
+        assert!(!watcher.check("AAPL", -2.0));
+        assert!(watcher.check("AAPL", 5.0));
+    }
+
+    #[test]
+    fn test_move_watcher_ignores_moves_already_past_at_start() {
+        let mut watcher = MoveWatcher::new(5.0);
+        assert!(!watcher.check("TSLA", 12.0));
+        assert!(!watcher.check("TSLA", 13.0));
+    }
+
+    #[test]
+    fn test_move_watcher_tracks_symbols_independently() {
+        let mut watcher = MoveWatcher::new(3.0);
+        watcher.check("AAPL", 0.0);
+        watcher.check("MSFT", 0.0);
+        assert!(watcher.check("AAPL", 4.0));
+        assert!(!watcher.check("MSFT", 1.0));
+        assert!(watcher.check("MSFT", -3.5));
+    }
+
+    fn at(time: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(time, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_quiet_hours_same_day() {
+        let quiet = QuietHours::parse("12:00", "13:30").unwrap();
+        assert!(quiet.contains(at("12:00")));
+        assert!(quiet.contains(at("13:29")));
+        assert!(!quiet.contains(at("13:30")));
+        assert!(!quiet.contains(at("11:59")));
+    }
+
+    #[test]
+    fn test_quiet_hours_span_midnight() {
+        let quiet = QuietHours::parse("22:00", "07:00").unwrap();
+        assert!(quiet.contains(at("23:30")));
+        assert!(quiet.contains(at("00:00")));
+        assert!(quiet.contains(at("06:59")));
+        assert!(!quiet.contains(at("07:00")));
+        assert!(!quiet.contains(at("12:00")));
+        assert!(!quiet.contains(at("21:59")));
+
+        let never = QuietHours::parse("08:00", "08:00").unwrap();
+        assert!(!never.contains(at("08:00")));
+    }
+
+    #[test]
+    fn test_quiet_hours_reject_bad_times() {
+        let err = QuietHours::parse("25:00", "07:00").unwrap_err();
+        assert!(err.to_string().contains("25:00"));
+        assert!(QuietHours::parse("22:00", "7pm").is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! Where quotes come from.
+//!
+//! Usually Yahoo. Sometimes a JSON file, for when you want the market to
+//! behave exactly as scripted for once.
+
+use crate::api::YahooFinanceClient;
+use crate::models::{FetchFailure, MarketState, Quote, QuoteBatch, QuoteType};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A source of quotes.
+pub trait QuoteProvider: Send + Sync {
+    /// Fetch quotes for the given symbols. Symbols that can't be fetched are
+    /// reported as failures rather than failing the whole batch.
+    fn get_quotes<'a>(&'a self, symbols: &'a [String]) -> BoxFuture<'a, Result<QuoteBatch>>;
+}
+
+impl QuoteProvider for YahooFinanceClient {
+    fn get_quotes<'a>(&'a self, symbols: &'a [String]) -> BoxFuture<'a, Result<QuoteBatch>> {
+        Box::pin(YahooFinanceClient::get_quotes(self, symbols))
+    }
+}
+
+/// One entry in a fixture file. Only `symbol` and `price` are required.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FixtureQuote {
+    pub symbol: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub price: f64,
+    /// Defaults to the starting price, i.e. no change
+    #[serde(default)]
+    pub previous_close: Option<f64>,
+    #[serde(default)]
+    pub volume: u64,
+    #[serde(default)]
+    pub market_cap: Option<u64>,
+    #[serde(default)]
+    pub quote_type: QuoteType,
+    #[serde(default)]
+    pub market_state: Option<MarketState>,
+    /// Pre-market/after-hours price, for the EXT% column
+    #[serde(default)]
+    pub extended_price: Option<f64>,
+    /// Price added on every fetch after the first, for animation testing
+    #[serde(default)]
+    pub step: f64,
+}
+
+/// Deterministic quotes read from a JSON fixture instead of the network.
+pub struct FixtureProvider {
+    quotes: Vec<FixtureQuote>,
+    fetches: AtomicU64,
+}
+
+impl FixtureProvider {
+    /// Load a fixture file: a JSON array of [`FixtureQuote`]s.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read fixture file: {}", path.display()))?;
+        let quotes = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse fixture file: {}", path.display()))?;
+        Ok(Self::new(quotes))
+    }
+
+    pub fn new(quotes: Vec<FixtureQuote>) -> Self {
+        Self {
+            quotes,
+            fetches: AtomicU64::new(0),
+        }
+    }
+
+    /// Symbols in the fixture, in file order.
+    pub fn symbols(&self) -> Vec<String> {
+        self.quotes.iter().map(|q| q.symbol.clone()).collect()
+    }
+
+    /// Build the quotes for the `fetch`th fetch (0-based).
+    fn quotes_at(&self, symbols: &[String], fetch: u64) -> QuoteBatch {
+        let mut batch = QuoteBatch::default();
+        for symbol in symbols {
+            match self
+                .quotes
+                .iter()
+                .find(|q| q.symbol.eq_ignore_ascii_case(symbol))
+            {
+                Some(fixture) => batch.quotes.push(fixture.quote_at(fetch)),
+                None => batch.failures.push(FetchFailure {
+                    symbol: symbol.clone(),
+                    reason: "Not in fixture".to_string(),
+                }),
+            }
+        }
+        batch
+    }
+}
+
+impl FixtureQuote {
+    fn quote_at(&self, fetch: u64) -> Quote {
+        let price = self.price + self.step * fetch as f64;
+        let previous_close = self.previous_close.unwrap_or(self.price);
+        let change = price - previous_close;
+        let change_percent = if previous_close > 0.0 {
+            change / previous_close * 100.0
+        } else {
+            0.0
+        };
+
+        Quote {
+            symbol: self.symbol.to_uppercase(),
+            name: self.name.clone().unwrap_or_else(|| self.symbol.clone()),
+            price,
+            change,
+            change_percent,
+            previous_close,
+            volume: self.volume,
+            market_cap: self.market_cap,
+            quote_type: self.quote_type,
+            market_state: self.market_state.unwrap_or(MarketState::Regular),
+            extended_price: self.extended_price,
+            timestamp: Utc::now(),
+            ..Default::default()
+        }
+    }
+}
+
+impl QuoteProvider for FixtureProvider {
+    fn get_quotes<'a>(&'a self, symbols: &'a [String]) -> BoxFuture<'a, Result<QuoteBatch>> {
+        let fetch = self.fetches.fetch_add(1, Ordering::Relaxed);
+        Box::pin(async move { Ok(self.quotes_at(symbols, fetch)) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> FixtureProvider {
+        FixtureProvider::new(
+            serde_json::from_str(
+                r#"[
+                    {"symbol": "AAPL", "name": "Apple Inc.", "price": 100.0,
+                     "previous_close": 80.0, "step": 5.0},
+                    {"symbol": "BTC-USD", "price": 50000.0, "quote_type": "Cryptocurrency"},
+                    {"symbol": "TSLA", "price": 200.0, "market_state": "Post",
+                     "extended_price": 190.0}
+                ]"#,
+            )
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_fixture_prices_step_per_fetch() {
+        let provider = fixture();
+        let symbols = vec!["AAPL".to_string()];
+
+        let first = provider.get_quotes(&symbols).await.unwrap().quotes;
+        assert_eq!(first[0].price, 100.0);
+        assert_eq!(first[0].change_percent, 25.0);
+
+        let second = provider.get_quotes(&symbols).await.unwrap().quotes;
+        assert_eq!(second[0].price, 105.0);
+    }
+
+    #[tokio::test]
+    async fn test_fixture_only_returns_requested_symbols() {
+        let provider = fixture();
+        let symbols = vec!["btc-usd".to_string(), "NOPE".to_string()];
+
+        let batch = provider.get_quotes(&symbols).await.unwrap();
+        assert_eq!(batch.failures[0].symbol, "NOPE");
+        let quotes = batch.quotes;
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].symbol, "BTC-USD");
+        assert_eq!(quotes[0].quote_type, QuoteType::Cryptocurrency);
+        assert_eq!(quotes[0].change, 0.0);
+        assert_eq!(provider.symbols(), vec!["AAPL", "BTC-USD", "TSLA"]);
+    }
+
+    #[tokio::test]
+    async fn test_fixture_extended_hours_price() {
+        let provider = fixture();
+        let symbols = vec!["TSLA".to_string(), "AAPL".to_string()];
+
+        let quotes = provider.get_quotes(&symbols).await.unwrap().quotes;
+        assert_eq!(quotes[0].market_state, MarketState::Post);
+        assert_eq!(quotes[0].extended_price, Some(190.0));
+        assert_eq!(quotes[1].extended_price, None);
+    }
+}
@@ -0,0 +1,304 @@
+//! `--serve`: the current quotes as JSON over a tiny local HTTP server.
+//!
+//! For status bars and wall dashboards that want prices without a terminal.
+//! Not a web framework, just enough HTTP/1.1 to answer a GET.
+
+use crate::app::App;
+use crate::models::Quote;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Requests bigger than this are not a price check.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// A client gets this long to finish sending its headers before it's dropped.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the server hands out, rebuilt after every refresh.
+#[derive(Debug, Default, Clone)]
+pub struct Snapshot {
+    quotes: String,
+    portfolio: String,
+}
+
+/// Body of `/quotes`.
+#[derive(Serialize)]
+struct QuotesResponse<'a> {
+    updated: String,
+    quotes: Vec<&'a Quote>,
+}
+
+/// Body of `/portfolio`.
+#[derive(Serialize)]
+struct PortfolioResponse {
+    updated: String,
+    total_value: f64,
+    total_cost: f64,
+    total_pnl: f64,
+    today_change: f64,
+    holdings: Vec<HoldingRow>,
+}
+
+#[derive(Serialize)]
+struct HoldingRow {
+    symbol: String,
+    quantity: f64,
+    cost_basis: f64,
+    price: f64,
+    value: f64,
+    pnl: f64,
+    pnl_percent: f64,
+}
+
+impl Snapshot {
+    /// Serialize the app's current quotes and holdings.
+    pub fn of(app: &App) -> Result<Self> {
+        let updated = chrono::Utc::now().to_rfc3339();
+        let quotes = QuotesResponse {
+            updated: updated.clone(),
+            quotes: app
+                .quotes
+                .iter()
+                .filter(|q| !app.hidden_symbols.contains(&q.symbol))
+                .collect(),
+        };
+
+        // Rounded the same way the table shows them
+        let money = |value: f64| app.rounding.round(value, 2);
+        let mut holdings: Vec<HoldingRow> = app
+            .quotes
+            .iter()
+            .filter(|q| !q.synthetic)
+            .filter_map(|quote| {
+                let holding = app.holdings.get(&quote.symbol)?;
+                Some(HoldingRow {
+                    symbol: quote.symbol.clone(),
+                    quantity: holding.quantity,
+                    cost_basis: holding.cost_basis,
+                    price: quote.price,
+                    value: money(holding.current_value(quote.price)),
+                    pnl: money(holding.profit_loss(quote.price)),
+                    pnl_percent: holding.profit_loss_percent(quote.price),
+                })
+            })
+            .collect();
+        holdings.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        let portfolio = PortfolioResponse {
+            updated,
+            total_value: money(app.total_portfolio_value()),
+            total_cost: money(app.total_portfolio_cost()),
+            total_pnl: money(app.total_portfolio_pnl()),
+            today_change: money(app.today_portfolio_change()),
+            holdings,
+        };
+
+        Ok(Self {
+            quotes: serde_json::to_string(&quotes)?,
+            portfolio: serde_json::to_string(&portfolio)?,
+        })
+    }
+}
+
+/// Refresh on the normal schedule and serve each result until Ctrl-C.
+pub async fn run(app: &mut App, addr: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to listen on {}", addr))?;
+    eprintln!(
+        "Serving /quotes and /portfolio on http://{}",
+        listener.local_addr()?
+    );
+
+    let snapshot = Arc::new(RwLock::new(Snapshot::default()));
+    let server = tokio::spawn(serve(listener, Arc::clone(&snapshot)));
+
+    let refresh_loop = async {
+        loop {
+            app.refresh().await?;
+            let fresh = Snapshot::of(app)?;
+            *snapshot.write().unwrap_or_else(|e| e.into_inner()) = fresh;
+            if let Some(problem) = app.offline.as_ref().or(app.error.as_ref()) {
+                eprintln!("{}", problem);
+            }
+            if app.should_quit() {
+                return Ok::<_, anyhow::Error>(());
+            }
+            tokio::time::sleep(app.refresh_delay()).await;
+        }
+    };
+
+    let result = tokio::select! {
+        result = refresh_loop => result,
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    };
+    server.abort();
+    result
+}
+
+/// Read up to the end of the request headers (or the size limit).
+async fn read_request(stream: &mut TcpStream) -> Result<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || request.len() + n > MAX_REQUEST_BYTES {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    Ok(request)
+}
+
+/// Answer requests from the latest snapshot, one task per connection.
+pub async fn serve(listener: TcpListener, snapshot: Arc<RwLock<Snapshot>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let snapshot = Arc::clone(&snapshot);
+        tokio::spawn(async move {
+            // A client that hangs up mid-request is its own problem
+            let _ = handle(stream, &snapshot).await;
+        });
+    }
+}
+
+async fn handle(mut stream: TcpStream, snapshot: &RwLock<Snapshot>) -> Result<()> {
+    // Idle and trickling clients don't get to hold a task and socket forever
+    let request = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream))
+        .await
+        .context("Client took too long to send a request")??;
+
+    let request = String::from_utf8_lossy(&request);
+    let mut parts = request.lines().next().unwrap_or("").split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = target.split('?').next().unwrap_or("");
+
+    let (status, body) = if method != "GET" {
+        (
+            "405 Method Not Allowed",
+            r#"{"error":"GET only"}"#.to_string(),
+        )
+    } else {
+        let snapshot = snapshot.read().unwrap_or_else(|e| e.into_inner());
+        match path {
+            // Nothing published yet: an empty 200 would be a parse error downstream
+            "/quotes" | "/portfolio" if snapshot.quotes.is_empty() => (
+                "503 Service Unavailable",
+                r#"{"error":"no quotes yet, the first refresh is still running"}"#.to_string(),
+            ),
+            "/quotes" => ("200 OK", snapshot.quotes.clone()),
+            "/portfolio" => ("200 OK", snapshot.portfolio.clone()),
+            _ => (
+                "404 Not Found",
+                r#"{"error":"try /quotes or /portfolio"}"#.to_string(),
+            ),
+        }
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Args;
+    use crate::config::Config;
+    use crate::models::Holding;
+    use clap::Parser;
+
+    async fn get(addr: SocketAddr, path: &str) -> (u16, serde_json::Value) {
+        let response = reqwest::get(format!("http://{}{}", addr, path))
+            .await
+            .unwrap();
+        let status = response.status().as_u16();
+        (status, response.json().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_serve_fixture_quotes() {
+        let fixture =
+            std::env::temp_dir().join(format!("stonktop-serve-{}.json", std::process::id()));
+        std::fs::write(
+            &fixture,
+            r#"[{"symbol": "AAPL", "price": 150.0, "previous_close": 100.0},
+                {"symbol": "MSFT", "price": 300.0}]"#,
+        )
+        .unwrap();
+        let args = Args::parse_from(["stonktop", "--fixture", fixture.to_str().unwrap()]);
+        let mut app = App::new(&args, &Config::default()).unwrap();
+        std::fs::remove_file(&fixture).ok();
+        app.holdings.insert(
+            "AAPL".to_string(),
+            Holding {
+                symbol: "AAPL".to_string(),
+                quantity: 2.0,
+                cost_basis: 100.0,
+                purchase_date: None,
+            },
+        );
+        app.refresh().await.unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let snapshot = Arc::new(RwLock::new(Snapshot::of(&app).unwrap()));
+        tokio::spawn(serve(listener, snapshot));
+
+        let (status, quotes) = get(addr, "/quotes").await;
+        assert_eq!(status, 200);
+        assert!(quotes["updated"].is_string());
+        let quotes = quotes["quotes"].as_array().unwrap();
+        assert_eq!(quotes.len(), 2);
+        let aapl = quotes.iter().find(|q| q["symbol"] == "AAPL").unwrap();
+        assert_eq!(aapl["price"], 150.0);
+        assert_eq!(aapl["change_percent"], 50.0);
+
+        let (status, portfolio) = get(addr, "/portfolio?pretty=no").await;
+        assert_eq!(status, 200);
+        assert_eq!(portfolio["total_value"], 300.0);
+        assert_eq!(portfolio["total_pnl"], 100.0);
+        assert_eq!(portfolio["holdings"][0]["symbol"], "AAPL");
+        assert_eq!(portfolio["holdings"][0]["pnl_percent"], 50.0);
+        assert_eq!(portfolio["holdings"].as_array().unwrap().len(), 1);
+
+        let (status, error) = get(addr, "/nope").await;
+        assert_eq!(status, 404);
+        assert!(error["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_idle_client_is_dropped() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, Arc::new(RwLock::new(Snapshot::default()))));
+
+        // Connects, then never sends a byte
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        let mut buf = [0u8; 16];
+        let read = tokio::time::timeout(REQUEST_TIMEOUT * 2, client.read(&mut buf)).await;
+        assert!(matches!(read, Ok(Ok(0)) | Ok(Err(_))), "{:?}", read);
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_unavailable_before_first_snapshot() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, Arc::new(RwLock::new(Snapshot::default()))));
+
+        for path in ["/quotes", "/portfolio"] {
+            let (status, body) = get(addr, path).await;
+            assert_eq!(status, 503, "{}", path);
+            assert!(body["error"].is_string());
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Synthetic rows computed from other quotes.
+//!
+//! ETH/BTC isn't a ticker Yahoo will hand you, but ETH-USD and BTC-USD are,
+//! and division still works.
+
+use crate::api::{base_asset, expand_symbol};
+use crate::models::{reference_change_percent, Quote, QuoteType};
+use anyhow::Result;
+
+/// A ratio row, like `ETH/BTC` from `ETH-USD` over `BTC-USD`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Synthetic {
+    /// What the row is called in the table
+    pub name: String,
+    pub numerator: String,
+    pub denominator: String,
+}
+
+impl Synthetic {
+    /// Parse a `"NUMERATOR/DENOMINATOR"` definition. Components get the
+    /// usual shortcut expansion, so `"eth/btc"` works too.
+    pub fn parse(name: &str, definition: &str) -> Result<Self> {
+        let Some((numerator, denominator)) = definition.split_once('/') else {
+            anyhow::bail!(
+                "Synthetic {} should look like \"ETH-USD/BTC-USD\", got {:?}",
+                name,
+                definition
+            );
+        };
+        let (numerator, denominator) = (numerator.trim(), denominator.trim());
+        if numerator.is_empty() || denominator.is_empty() || denominator.contains('/') {
+            anyhow::bail!(
+                "Synthetic {} needs exactly two symbols: {:?}",
+                name,
+                definition
+            );
+        }
+        Ok(Self {
+            name: name.to_string(),
+            numerator: expand_symbol(numerator),
+            denominator: expand_symbol(denominator),
+        })
+    }
+
+    /// Compute the row from its components. None until both have a price.
+    pub fn quote(&self, quotes: &[Quote]) -> Option<Quote> {
+        let find = |symbol: &str| {
+            quotes
+                .iter()
+                .find(|q| !q.synthetic && q.symbol.eq_ignore_ascii_case(symbol))
+                .filter(|q| q.price > 0.0 && q.price.is_finite())
+        };
+        let (numerator, denominator) = (find(&self.numerator)?, find(&self.denominator)?);
+
+        let price = numerator.price / denominator.price;
+        let previous_close = if denominator.previous_close > 0.0 {
+            numerator.previous_close / denominator.previous_close
+        } else {
+            0.0
+        };
+        let change_percent = reference_change_percent(price, previous_close).unwrap_or(0.0);
+        let both_crypto = [numerator, denominator]
+            .iter()
+            .all(|q| q.quote_type == QuoteType::Cryptocurrency);
+
+        Some(Quote {
+            symbol: self.name.clone(),
+            name: format!("{} / {}", numerator.symbol, denominator.symbol),
+            price,
+            change: if previous_close > 0.0 {
+                price - previous_close
+            } else {
+                0.0
+            },
+            change_percent,
+            previous_close,
+            // Priced in units of the denominator: ETH/BTC is in BTC
+            currency: base_asset(&denominator.symbol).to_string(),
+            quote_type: if both_crypto {
+                QuoteType::Cryptocurrency
+            } else {
+                numerator.quote_type
+            },
+            market_state: numerator.market_state,
+            timestamp: numerator.timestamp.min(denominator.timestamp),
+            synthetic: true,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(symbol: &str, price: f64, previous_close: f64) -> Quote {
+        Quote {
+            symbol: symbol.to_string(),
+            price,
+            previous_close,
+            quote_type: QuoteType::Cryptocurrency,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ratio_from_components() {
+        let eth_btc = Synthetic::parse("ETH/BTC", "eth/btc").unwrap();
+        assert_eq!(eth_btc.numerator, "ETH-USD");
+        assert_eq!(eth_btc.denominator, "BTC-USD");
+
+        let quotes = [
+            quote("BTC-USD", 60_000.0, 50_000.0),
+            quote("ETH-USD", 3_000.0, 2_000.0),
+        ];
+        let row = eth_btc.quote(&quotes).unwrap();
+        assert_eq!(row.symbol, "ETH/BTC");
+        assert_eq!(row.name, "ETH-USD / BTC-USD");
+        assert!((row.price - 0.05).abs() < 1e-12);
+        assert!((row.previous_close - 0.04).abs() < 1e-12);
+        assert!((row.change_percent - 25.0).abs() < 1e-9);
+        assert_eq!(row.currency, "BTC");
+        assert_eq!(row.quote_type, QuoteType::Cryptocurrency);
+        assert!(row.synthetic);
+    }
+
+    #[test]
+    fn test_missing_or_unpriced_component_gives_no_row() {
+        let eth_btc = Synthetic::parse("ETH/BTC", "ETH-USD/BTC-USD").unwrap();
+        assert!(eth_btc.quote(&[quote("ETH-USD", 3_000.0, 0.0)]).is_none());
+        let zero = [quote("ETH-USD", 3_000.0, 0.0), quote("BTC-USD", 0.0, 0.0)];
+        assert!(eth_btc.quote(&zero).is_none());
+    }
+
+    #[test]
+    fn test_bad_definitions() {
+        assert!(Synthetic::parse("X", "ETH-USD").is_err());
+        assert!(Synthetic::parse("X", "ETH-USD/").is_err());
+        assert!(Synthetic::parse("X", "A/B/C").is_err());
+    }
+}
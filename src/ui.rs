@@ -3,16 +3,27 @@
 //! Making financial data look pretty since 2024.
 //! (The data itself? Still ugly. That's not our fault.)
 
-use crate::app::App;
-use crate::models::SortOrder;
+use crate::action;
+use crate::alerts::PANIC_REARM_STEP;
+use crate::app::{find_ignore_case, App, COMPOSITE_KEY};
+use crate::history;
+use crate::indicators::{calculate_macd, calculate_rsi, calculate_sma, MACD_PERIODS, RSI_PERIOD};
+use crate::models::{
+    extended_change_percent, gap_percent, vwap_proxy, MarketState, Quote, QuoteType, Rounding,
+    SortDirection, SortOrder, View, HOLDINGS_COLUMNS,
+};
+use chrono::{DateTime, Utc};
 use num_format::{Locale, ToFormattedString};
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    symbols,
     text::{Line, Span},
-    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Sparkline, Table, TableState, Wrap},
     Frame,
 };
+use std::collections::HashSet;
+use std::time::Instant;
 
 /// Colors for the UI.
 pub struct UiColors {
@@ -21,6 +32,7 @@ pub struct UiColors {
     pub neutral: Color,
     pub header_bg: Color,
     pub selected_bg: Color,
+    pub stripe_bg: Color,
     pub border: Color,
 }
 
@@ -32,14 +44,145 @@ impl Default for UiColors {
             neutral: Color::White,
             header_bg: Color::DarkGray,
             selected_bg: Color::Rgb(40, 40, 60),
+            stripe_bg: Color::Rgb(28, 28, 28),
             border: Color::DarkGray,
         }
     }
 }
 
+/// Parse a `#rrggbb` color. Anything else is `None`, for callers to ignore.
+pub(crate) fn parse_hex_color(hex: &str) -> Option<Color> {
+    let digits = hex.trim().strip_prefix('#')?;
+    if digits.len() != 6 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+    Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Glyphs that not every terminal gets right, so they come in two profiles.
+struct Glyphs {
+    up: &'static str,
+    down: &'static str,
+    /// Neither up nor down
+    flat: &'static str,
+    /// Arrow keys in the help overlay
+    up_key: &'static str,
+    down_key: &'static str,
+    /// Marker for rows that just changed
+    changed: &'static str,
+    /// Marker for pinned rows
+    favorite: &'static str,
+    /// Marker for rows with a note
+    note: &'static str,
+    /// Frames of the "fetching" spinner
+    spinner: &'static [&'static str],
+    ellipsis: &'static str,
+    bars: symbols::bar::Set<'static>,
+    border: symbols::border::Set<'static>,
+}
+
+const UNICODE_GLYPHS: Glyphs = Glyphs {
+    up: "▲",
+    down: "▼",
+    flat: "=",
+    up_key: "↑",
+    down_key: "↓",
+    changed: "•",
+    favorite: "★",
+    note: "📝",
+    spinner: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+    ellipsis: "…",
+    bars: symbols::bar::NINE_LEVELS,
+    border: symbols::border::PLAIN,
+};
+
+/// For terminals (and remote sessions) that turn box drawing into mojibake.
+const ASCII_GLYPHS: Glyphs = Glyphs {
+    up: "^",
+    down: "v",
+    flat: "=",
+    up_key: "Up",
+    down_key: "Dn",
+    changed: "+",
+    favorite: "*",
+    note: "n",
+    spinner: &["|", "/", "-", "\\"],
+    ellipsis: "...",
+    bars: symbols::bar::Set {
+        full: "#",
+        seven_eighths: "#",
+        three_quarters: "=",
+        five_eighths: "=",
+        half: "-",
+        three_eighths: "-",
+        one_quarter: "_",
+        one_eighth: "_",
+        empty: " ",
+    },
+    border: symbols::border::Set {
+        top_left: "+",
+        top_right: "+",
+        bottom_left: "+",
+        bottom_right: "+",
+        vertical_left: "|",
+        vertical_right: "|",
+        horizontal_top: "-",
+        horizontal_bottom: "-",
+    },
+};
+
+impl Glyphs {
+    fn for_app(app: &App) -> &'static Glyphs {
+        if app.unicode {
+            &UNICODE_GLYPHS
+        } else {
+            &ASCII_GLYPHS
+        }
+    }
+
+    fn direction(&self, direction: SortDirection) -> &'static str {
+        match direction {
+            SortDirection::Ascending => self.up,
+            SortDirection::Descending => self.down,
+        }
+    }
+
+    fn trend(&self, value: f64) -> &'static str {
+        if value > 0.0 {
+            self.up
+        } else if value < 0.0 {
+            self.down
+        } else {
+            self.flat
+        }
+    }
+
+    /// Prefix a gain/loss cell with its direction when `--glyphs` is on,
+    /// for anyone who can't (or won't) tell red from green.
+    fn signed(&self, app: &App, value: f64, text: String) -> String {
+        if app.change_glyphs {
+            format!("{} {}", self.trend(value), text)
+        } else {
+            text
+        }
+    }
+
+    /// Spinner frame for the given tick; one frame per tick, round and round.
+    fn spinner_frame(&self, tick: usize) -> &'static str {
+        self.spinner[tick % self.spinner.len()]
+    }
+
+    /// A block with the given borders drawn in this profile's line style.
+    fn block(&self, borders: Borders) -> Block<'static> {
+        Block::default().borders(borders).border_set(self.border)
+    }
+}
+
 /// Render the main UI.
 pub fn render(frame: &mut Frame, app: &App) {
     let colors = UiColors::default();
+    let glyphs = Glyphs::for_app(app);
 
     // Create layout
     let chunks = Layout::default()
@@ -56,7 +199,17 @@ pub fn render(frame: &mut Frame, app: &App) {
 
     // Render main table
     if app.show_holdings {
-        render_holdings_table(frame, app, chunks[1], &colors);
+        let table_area = if !app.holdings.is_empty() {
+            let parts = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(5)])
+                .split(chunks[1]);
+            render_portfolio_charts(frame, app, parts[0], &colors);
+            parts[1]
+        } else {
+            chunks[1]
+        };
+        render_holdings_table(frame, app, table_area, &colors);
     } else {
         render_quotes_table(frame, app, chunks[1], &colors);
     }
@@ -64,24 +217,58 @@ pub fn render(frame: &mut Frame, app: &App) {
     // Render footer
     render_footer(frame, app, chunks[2], &colors);
 
+    if app.show_detail {
+        if let Some(quote) = app.selected_quote() {
+            render_detail_view(frame, app, quote, &colors, glyphs);
+        }
+    }
+
     // Render help overlay if active
     if app.show_help {
-        render_help_overlay(frame, &colors);
+        render_help_overlay(frame, &colors, glyphs);
+    }
+
+    // A bad enough day covers everything but outages and errors
+    if let Some((day_change, threshold)) = app.panic_warning() {
+        render_panic(frame, app, day_change, threshold, &colors, glyphs);
+    }
+
+    // The offline banner sits under any transient error
+    if let Some(ref offline) = app.offline {
+        render_offline(frame, offline, &colors, glyphs);
     }
 
     // Render error if present
     if let Some(ref error) = app.error {
-        render_error(frame, error, &colors);
+        render_error(frame, error, &colors, glyphs);
     }
 }
 
 /// Render the header with summary information.
 fn render_header(frame: &mut Frame, app: &App, area: Rect, colors: &UiColors) {
-    let gains = app.quotes.iter().filter(|q| q.change_percent > 0.0).count();
-    let losses = app.quotes.iter().filter(|q| q.change_percent < 0.0).count();
-    let unchanged = app.quotes.len() - gains - losses;
+    let quotes = app.display_quotes();
+    let now = Instant::now();
 
-    let header_text = if app.show_holdings {
+    let header_text = if app.show_holdings && app.holdings.is_empty() {
+        // Zeroed totals read like a wipeout; say what's missing instead
+        let dash = if app.unicode { "—" } else { "-" };
+        vec![
+            Line::from(vec![
+                Span::styled(
+                    "STONKTOP ",
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("- Portfolio View"),
+                activity_span(app, now),
+            ]),
+            Line::from(Span::styled(
+                format!("No holdings configured {} add [[holdings]] to config", dash),
+                Style::default().fg(Color::Yellow),
+            )),
+        ]
+    } else if app.show_holdings {
         let total_value = app.total_portfolio_value();
         let total_pnl = app.total_portfolio_pnl();
         let today_change = app.today_portfolio_change();
@@ -91,7 +278,7 @@ fn render_header(frame: &mut Frame, app: &App, area: Rect, colors: &UiColors) {
             0.0
         };
 
-        vec![
+        let mut lines = vec![
             Line::from(vec![
                 Span::styled(
                     "STONKTOP ",
@@ -100,11 +287,23 @@ fn render_header(frame: &mut Frame, app: &App, area: Rect, colors: &UiColors) {
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::raw("- Portfolio View"),
+                activity_span(app, now),
+                Span::styled(
+                    format_group_weights(&app.group_weights()),
+                    Style::default().add_modifier(Modifier::DIM),
+                ),
             ]),
             Line::from(vec![
-                Span::raw(format!("Value: ${:.2}  ", total_value)),
+                Span::raw(format!(
+                    "Value: ${}  ",
+                    format_money(total_value, app.rounding)
+                )),
                 Span::styled(
-                    format!("P/L: {:+.2} ({:+.2}%)  ", total_pnl, pnl_pct),
+                    format!(
+                        "P/L: {} ({:+.2}%)  ",
+                        format_signed_money(total_pnl, app.rounding),
+                        pnl_pct
+                    ),
                     Style::default().fg(if total_pnl >= 0.0 {
                         colors.gain
                     } else {
@@ -112,7 +311,7 @@ fn render_header(frame: &mut Frame, app: &App, area: Rect, colors: &UiColors) {
                     }),
                 ),
                 Span::styled(
-                    format!("Today: {:+.2}", today_change),
+                    format!("Today: {}", format_signed_money(today_change, app.rounding)),
                     Style::default().fg(if today_change >= 0.0 {
                         colors.gain
                     } else {
@@ -120,41 +319,180 @@ fn render_header(frame: &mut Frame, app: &App, area: Rect, colors: &UiColors) {
                     }),
                 ),
             ]),
-        ]
+        ];
+        if let Some(ann) = app.portfolio_annualized_return() {
+            lines[1].spans.push(Span::styled(
+                format!("  Annualized: {:+.2}%", ann),
+                Style::default().fg(change_color(ann, colors)),
+            ));
+        }
+        let stale = app.stale_holdings();
+        if !stale.is_empty() {
+            lines[1].spans.push(Span::styled(
+                format!("  Stale: {} (last known price)", stale.join(", ")),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        lines
     } else {
-        vec![
-            Line::from(vec![
-                Span::styled(
-                    "STONKTOP ",
-                    Style::default()
-                        .fg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD),
-                ),
-                Span::raw(format!("- {} symbols", app.quotes.len())),
-            ]),
-            Line::from(vec![
-                Span::styled(format!("{} ", gains), Style::default().fg(colors.gain)),
-                Span::raw("up  "),
-                Span::styled(format!("{} ", losses), Style::default().fg(colors.loss)),
-                Span::raw("down  "),
-                Span::raw(format!("{} unchanged  ", unchanged)),
-                Span::raw(format!("Updated: {}", app.time_since_refresh())),
-            ]),
-        ]
+        let breadth = app.market_breadth();
+        let mut summary = vec![
+            Span::styled(
+                format!("{} ", breadth.advancing),
+                Style::default().fg(colors.gain),
+            ),
+            Span::raw("up  "),
+            Span::styled(
+                format!("{} ", breadth.declining),
+                Style::default().fg(colors.loss),
+            ),
+            Span::raw("down  "),
+            Span::raw(format!("{} unchanged  ", breadth.unchanged)),
+        ];
+        if let Some(ratio) = breadth.advance_decline_ratio() {
+            summary.push(Span::raw(format!("A/D {:.2}  ", ratio)));
+        }
+        if breadth.new_highs + breadth.new_lows > 0 {
+            summary.push(Span::raw(format!(
+                "52w {} hi {} lo  ",
+                breadth.new_highs, breadth.new_lows
+            )));
+        }
+        if let Some((change, percent)) = app.watchlist_net_change() {
+            // "-$1.00" rather than "$-1.00"
+            let net = format_signed_money(change, app.rounding);
+            let (sign, amount) = net.split_at(1);
+            summary.push(Span::raw("Net: "));
+            summary.push(Span::styled(
+                format!("{}${} ({:+.2}%)  ", sign, amount, percent),
+                Style::default().fg(change_color(percent, colors)),
+            ));
+        }
+        summary.push(Span::raw(format!("Updated: {}", app.time_since_refresh())));
+        if !app.failures.is_empty() {
+            summary.push(Span::styled(
+                format!("  {} failed", app.failures.len()),
+                Style::default().fg(colors.loss),
+            ));
+        }
+        if !app.warnings.is_empty() {
+            summary.push(Span::styled(
+                format!("  {} partial", app.warnings.len()),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        let no_data = app.no_data_symbols();
+        if !no_data.is_empty() {
+            summary.push(Span::styled(
+                format!("  No data: {}", no_data.join(", ")),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+
+        let mut title = vec![
+            Span::styled(
+                "STONKTOP ",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!("- {} symbols", quotes.len())),
+            activity_span(app, now),
+        ];
+        title.extend(composite_spans(app, colors));
+        vec![Line::from(title), Line::from(summary)]
     };
 
     let header = Paragraph::new(header_text).block(
-        Block::default()
-            .borders(Borders::BOTTOM)
+        Glyphs::for_app(app)
+            .block(Borders::BOTTOM)
             .border_style(Style::default().fg(colors.border)),
     );
 
     frame.render_widget(header, area);
 }
 
+/// Refreshes of composite history the header sparkline covers.
+const COMPOSITE_SPARKLINE_LEN: usize = 20;
+
+/// The "my market" composite for the header: today's weighted change, and
+/// a sparkline of it once there are a few refreshes to draw.
+fn composite_spans(app: &App, colors: &UiColors) -> Vec<Span<'static>> {
+    let Some(percent) = app.composite_index.and_then(|w| app.composite_change(w)) else {
+        return Vec::new();
+    };
+    let mut spans = vec![
+        Span::styled(
+            "  My market ",
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!("{:+.*}%", app.percent_precision, percent),
+            Style::default()
+                .fg(change_color(percent, colors))
+                .add_modifier(Modifier::BOLD),
+        ),
+    ];
+    if let Some(history) = app.price_history.get(COMPOSITE_KEY) {
+        let recent = &history[history.len().saturating_sub(COMPOSITE_SPARKLINE_LEN)..];
+        if recent.len() >= 2 {
+            let bars = &Glyphs::for_app(app).bars;
+            spans.push(Span::styled(
+                format!(" {}", inline_sparkline(recent, bars)),
+                Style::default().fg(change_color(percent, colors)),
+            ));
+        }
+    }
+    spans
+}
+
+/// A series as one line of bar glyphs, for a sparkline inside text.
+fn inline_sparkline(values: &[f64], bars: &symbols::bar::Set) -> String {
+    let levels = [
+        bars.one_eighth,
+        bars.one_quarter,
+        bars.three_eighths,
+        bars.half,
+        bars.five_eighths,
+        bars.three_quarters,
+        bars.seven_eighths,
+        bars.full,
+    ];
+    sparkline_data(values)
+        .into_iter()
+        .map(|v| levels[((v - 1) * 8 / 100) as usize])
+        .collect()
+}
+
+/// Spinner for the header while a fetch is in flight, so a slow connection
+/// doesn't look like a hung terminal. Nothing when idle.
+fn activity_span(app: &App, now: Instant) -> Span<'static> {
+    let Some(step) = app.spinner_step(now) else {
+        return Span::raw("");
+    };
+    Span::styled(
+        format!(" {}", Glyphs::for_app(app).spinner_frame(step)),
+        Style::default().fg(Color::Cyan),
+    )
+}
+
+/// Exposure per group for the portfolio header, e.g. "  Tech 62%, Other 38%".
+/// Empty when there's only one bucket, since "Other 100%" says nothing.
+fn format_group_weights(weights: &[(String, f64)]) -> String {
+    if weights.len() < 2 {
+        return String::new();
+    }
+    let parts: Vec<String> = weights
+        .iter()
+        .map(|(group, pct)| format!("{} {:.0}%", group, pct))
+        .collect();
+    format!("  {}", parts.join(", "))
+}
+
 /// Render the quotes table.
 fn render_quotes_table(frame: &mut Frame, app: &App, area: Rect, colors: &UiColors) {
-    let header_cells = [
+    let glyphs = Glyphs::for_app(app);
+    let sort_cells = [
         ("SYMBOL", SortOrder::Symbol),
         ("NAME", SortOrder::Name),
         ("PRICE", SortOrder::Price),
@@ -165,7 +503,17 @@ fn render_quotes_table(frame: &mut Frame, app: &App, area: Rect, colors: &UiColo
     ]
     .iter()
     .map(|(name, order)| {
-        let style = if app.sort_order == *order {
+        // Biggest-movers sort lives on the CHG% column too
+        let active = app.sort_order == *order
+            || (*order == SortOrder::ChangePercent
+                && app.sort_order == SortOrder::AbsChangePercent);
+        let name = if active {
+            app.sort_order.header()
+        } else {
+            name
+        };
+
+        let style = if active {
             Style::default()
                 .fg(Color::Yellow)
                 .add_modifier(Modifier::BOLD)
@@ -173,65 +521,233 @@ fn render_quotes_table(frame: &mut Frame, app: &App, area: Rect, colors: &UiColo
             Style::default().fg(Color::White)
         };
 
-        let indicator = if app.sort_order == *order {
-            match app.sort_direction {
-                crate::models::SortDirection::Ascending => " ▲",
-                crate::models::SortDirection::Descending => " ▼",
-            }
+        if active {
+            let arrow = glyphs.direction(app.sort_direction);
+            Cell::from(format!("{} {}", name, arrow)).style(style)
         } else {
-            ""
-        };
-
-        Cell::from(format!("{}{}", name, indicator)).style(style)
+            Cell::from(name).style(style)
+        }
     });
+    let show_reference = !app.reference_prices.is_empty();
+    let reference_header = show_reference.then(|| Cell::from("REF%"));
+    let show_strength = app.benchmark.is_some();
+    let strength_header = show_strength.then(|| Cell::from("RS"));
+    let prev_header = app.show_prev_close.then(|| Cell::from("PREV"));
+    let session_header = app.show_since_launch.then(|| Cell::from("SESS%"));
+    // During regular hours there's nothing to put in it, so it stays out of the way
+    let show_extended = app.show_extended_hours
+        && app
+            .display_quotes()
+            .iter()
+            .any(|quote| extended_change_percent(quote).is_some());
+    let extended_header = show_extended.then(|| Cell::from("EXT%"));
+    let verbose_headers = VERBOSE_HEADERS
+        .iter()
+        .filter(|_| app.verbose)
+        .map(|name| Cell::from(*name));
+    let header_cells = std::iter::once(Cell::from(""))
+        .chain(sort_cells)
+        .chain(reference_header)
+        .chain(strength_header)
+        .chain(prev_header)
+        .chain(session_header)
+        .chain(extended_header)
+        .chain(verbose_headers);
+    let header_cells = grid_columns(
+        app,
+        keep_change_columns(app, header_cells.collect()),
+        || grid_separator(glyphs, colors),
+    );
 
     let header = Row::new(header_cells)
         .style(Style::default().bg(colors.header_bg))
         .height(1);
 
-    let rows = app.quotes.iter().enumerate().map(|(i, quote)| {
-        let is_selected = i == app.selected;
-        let change_color = if quote.change_percent > 0.0 {
-            colors.gain
-        } else if quote.change_percent < 0.0 {
-            colors.loss
-        } else {
-            colors.neutral
-        };
+    let now = Utc::now();
+    let search = app.search_query.as_deref();
+    let prices: Vec<String> = app
+        .display_quotes()
+        .iter()
+        .map(|quote| format_price(quote.price, quote.quote_type))
+        .collect();
+    let prices = if app.align_decimals {
+        align_decimal_points(prices)
+    } else {
+        prices
+    };
+    let price_width = prices
+        .iter()
+        .map(|price| price.chars().count() as u16 + 1)
+        .fold(12, u16::max);
+    let rows = app
+        .display_quotes()
+        .into_iter()
+        .enumerate()
+        .map(|(i, quote)| {
+            let change_fg = change_color(app.color_change(quote), colors);
+            let row_style = row_style(i == app.selected, i, app.zebra, colors);
 
-        let row_style = if is_selected {
-            Style::default().bg(colors.selected_bg)
-        } else {
-            Style::default()
-        };
+            // Gain/loss cells keep full intensity so direction stays readable
+            let mut base = market_state_style(quote.market_state);
+            if app.is_carried(&quote.symbol) {
+                // Last known price standing in for a symbol that went missing
+                base = base.add_modifier(Modifier::DIM | Modifier::ITALIC);
+            }
+            let favorite = if app.favorites.contains(&quote.symbol) {
+                glyphs.favorite
+            } else {
+                " "
+            };
+            let changed = if app.has_unseen_change(&quote.symbol) {
+                glyphs.changed
+            } else {
+                " "
+            };
+            let note = match app.note(&quote.symbol) {
+                Some(_) => glyphs.note,
+                None => "",
+            };
+            let marker = format!("{}{}{}", favorite, changed, note);
+            // Computed rows read differently from fetched ones
+            let mut symbol_style = if quote.synthetic {
+                base.add_modifier(Modifier::ITALIC)
+            } else {
+                base
+            };
+            if let Some(color) = app
+                .symbol_colors
+                .get(&quote.symbol)
+                .and_then(|hex| parse_hex_color(hex))
+            {
+                symbol_style = symbol_style.fg(color);
+            }
+            let mut cells = vec![
+                Cell::from(marker).style(Style::default().fg(Color::Yellow)),
+                Cell::from(highlight_match(&app.display_symbol(&quote.symbol), search))
+                    .style(symbol_style),
+                Cell::from(highlight_match(
+                    &truncate_string(&quote.name, app.name_width, glyphs.ellipsis),
+                    search,
+                ))
+                .style(base),
+                Cell::from(prices[i].clone()).style(base),
+                Cell::from(glyphs.signed(app, quote.change, format!("{:+.2}", quote.change)))
+                    .style(Style::default().fg(change_fg)),
+                Cell::from(glyphs.signed(
+                    app,
+                    quote.change_percent,
+                    format_percent(quote.change_percent, app.percent_precision),
+                ))
+                .style(Style::default().fg(change_fg)),
+                Cell::from(format_volume(quote.volume, app.full_volume)).style(base),
+                Cell::from(format_market_cap(quote.market_cap)).style(base),
+            ];
+            if show_reference {
+                cells.push(match app.reference_change(quote) {
+                    Some(pct) => Cell::from(format_percent(pct, app.percent_precision))
+                        .style(Style::default().fg(change_color(pct, colors))),
+                    None => Cell::from(""),
+                });
+            }
+            if show_strength {
+                cells.push(match app.relative_strength(quote) {
+                    Some(rs) => Cell::from(glyphs.signed(app, rs, format!("{:>+8.2}", rs)))
+                        .style(Style::default().fg(change_color(rs, colors))),
+                    None => Cell::from(""),
+                });
+            }
+            if app.show_prev_close {
+                cells.push(Cell::from(format_prev_close(quote, app.percent_precision)).style(base));
+            }
+            if app.show_since_launch {
+                cells.push(match app.session_change(quote) {
+                    Some(pct) => Cell::from(format_percent(pct, app.percent_precision))
+                        .style(Style::default().fg(change_color(pct, colors))),
+                    None => Cell::from(""),
+                });
+            }
+            if show_extended {
+                cells.push(match extended_change_percent(quote) {
+                    Some(pct) => Cell::from(format_percent(pct, app.percent_precision))
+                        .style(Style::default().fg(change_color(pct, colors))),
+                    None => Cell::from(""),
+                });
+            }
+            if app.verbose {
+                cells.extend(
+                    verbose_fields(quote, now)
+                        .into_iter()
+                        .map(|field| Cell::from(field).style(base)),
+                );
+            }
 
+            Row::new(grid_columns(app, keep_change_columns(app, cells), || {
+                grid_separator(glyphs, colors)
+            }))
+            .style(row_style)
+            .height(1 + app.row_spacing)
+        });
+
+    // Verbose mode lists what didn't load right under what did
+    let failure_rows = app.failures.iter().filter(|_| app.verbose).map(|failure| {
         let cells = vec![
-            Cell::from(quote.symbol.clone()),
-            Cell::from(truncate_string(&quote.name, 20)),
-            Cell::from(format_price(quote.price)),
-            Cell::from(format!("{:+.2}", quote.change)).style(Style::default().fg(change_color)),
-            Cell::from(format!("{:+.2}%", quote.change_percent))
-                .style(Style::default().fg(change_color)),
-            Cell::from(format_volume(quote.volume)),
-            Cell::from(format_market_cap(quote.market_cap)),
+            Cell::from("!").style(Style::default().fg(colors.loss)),
+            Cell::from(failure.symbol.clone()),
+            Cell::from(truncate_string(&failure.reason, 60, glyphs.ellipsis)),
         ];
-
-        Row::new(cells).style(row_style)
+        Row::new(grid_columns(app, cells, || grid_separator(glyphs, colors)))
+            .style(Style::default().fg(Color::DarkGray))
+            .height(1 + app.row_spacing)
     });
+    let rows = rows.chain(failure_rows);
 
-    let widths = [
-        Constraint::Length(10),
-        Constraint::Length(22),
-        Constraint::Length(12),
-        Constraint::Length(10),
+    let pct_width = percent_column_width(app.percent_precision);
+    // The marker column only grows for the note glyph when there are notes
+    let marker_width = if app.notes.is_empty() {
+        2
+    } else {
+        2 + Span::raw(glyphs.note).width() as u16
+    };
+    let mut widths = vec![
+        Constraint::Length(marker_width),
         Constraint::Length(10),
-        Constraint::Length(12),
+        Constraint::Length(app.name_width as u16 + 2),
+        Constraint::Length(price_width),
+        Constraint::Length(10 + glyph_width(app)),
+        Constraint::Length(pct_width + glyph_width(app)),
+        Constraint::Length(if app.full_volume { 14 } else { 12 }),
         Constraint::Length(12),
     ];
+    if show_reference {
+        widths.push(Constraint::Length(pct_width));
+    }
+    if show_strength {
+        widths.push(Constraint::Length(9 + glyph_width(app)));
+    }
+    if app.show_prev_close {
+        widths.push(Constraint::Length(13 + pct_width));
+    }
+    if app.show_since_launch {
+        widths.push(Constraint::Length(pct_width));
+    }
+    if show_extended {
+        widths.push(Constraint::Length(pct_width));
+    }
+    if app.verbose {
+        widths.extend([
+            Constraint::Length(8),
+            Constraint::Length(5),
+            Constraint::Length(14),
+            Constraint::Length(6),
+        ]);
+    }
 
+    let widths = grid_columns(app, keep_change_columns(app, widths), || {
+        Constraint::Length(1)
+    });
     let table = Table::new(rows, widths)
         .header(header)
-        .block(Block::default().borders(Borders::NONE))
+        .block(grid_block(app, glyphs, colors))
         .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
 
     let mut state = TableState::default();
@@ -240,91 +756,317 @@ fn render_quotes_table(frame: &mut Frame, app: &App, area: Rect, colors: &UiColo
     frame.render_stateful_widget(table, area, &mut state);
 }
 
+/// Split `text` into spans around the first match of the search query,
+/// with the match bold and underlined.
+fn highlight_match(text: &str, query: Option<&str>) -> Line<'static> {
+    let Some((start, end)) = query.and_then(|q| find_ignore_case(text, q)) else {
+        return Line::from(text.to_string());
+    };
+    let mut spans = Vec::with_capacity(3);
+    if start > 0 {
+        spans.push(Span::raw(text[..start].to_string()));
+    }
+    spans.push(Span::styled(
+        text[start..end].to_string(),
+        Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+    ));
+    if end < text.len() {
+        spans.push(Span::raw(text[end..].to_string()));
+    }
+    Line::from(spans)
+}
+
+/// Put a separator column between every pair of columns when `--grid` is
+/// on. Works on cells and widths alike, so the two stay in step.
+fn grid_columns<T>(app: &App, columns: Vec<T>, separator: impl Fn() -> T) -> Vec<T> {
+    if !app.grid {
+        return columns;
+    }
+    let mut out = Vec::with_capacity(columns.len() * 2);
+    for (i, column) in columns.into_iter().enumerate() {
+        if i > 0 {
+            out.push(separator());
+        }
+        out.push(column);
+    }
+    out
+}
+
+/// One cell of a grid's vertical rule.
+fn grid_separator(glyphs: &Glyphs, colors: &UiColors) -> Cell<'static> {
+    Cell::from(glyphs.border.vertical_left).style(Style::default().fg(colors.border))
+}
+
+/// Table block: a rule under the table with `--grid`, nothing otherwise.
+fn grid_block(app: &App, glyphs: &Glyphs, colors: &UiColors) -> Block<'static> {
+    if app.grid {
+        glyphs
+            .block(Borders::BOTTOM)
+            .border_style(Style::default().fg(colors.border))
+    } else {
+        Block::default().borders(Borders::NONE)
+    }
+}
+
+/// Drop whichever of CHANGE/CHG% the config says to hide from a quotes
+/// table row, given as marker, symbol, name, price, change, percent, ...
+fn keep_change_columns<T>(app: &App, mut columns: Vec<T>) -> Vec<T> {
+    const CHANGE: usize = 4;
+    const PERCENT: usize = 5;
+    let (show_change, show_percent) = app.change_columns();
+    if !show_percent && columns.len() > PERCENT {
+        columns.remove(PERCENT);
+    }
+    if !show_change && columns.len() > CHANGE {
+        columns.remove(CHANGE);
+    }
+    columns
+}
+
+/// Extra column headers shown in verbose mode.
+const VERBOSE_HEADERS: [&str; 4] = ["EXCH", "CCY", "TYPE", "AGE"];
+
+/// Exchange, currency, quote type and data age, for verbose mode.
+fn verbose_fields(quote: &Quote, now: DateTime<Utc>) -> [String; 4] {
+    [
+        quote.exchange.clone(),
+        quote.currency.clone(),
+        quote.quote_type.to_string(),
+        format!("{}s", data_age_secs(quote, now)),
+    ]
+}
+
+/// Seconds since the quote was stamped. Clock skew doesn't get to make it negative.
+fn data_age_secs(quote: &Quote, now: DateTime<Utc>) -> i64 {
+    (now - quote.timestamp).num_seconds().max(0)
+}
+
 /// Render the holdings/portfolio table.
 fn render_holdings_table(frame: &mut Frame, app: &App, area: Rect, colors: &UiColors) {
-    let header_cells = [
-        "SYMBOL", "NAME", "PRICE", "QTY", "VALUE", "COST", "P/L", "P/L%", "TODAY",
-    ]
-    .iter()
-    .map(|h| Cell::from(*h).style(Style::default().fg(Color::White)));
+    let glyphs = Glyphs::for_app(app);
+    let header_cells = HOLDINGS_COLUMNS.iter().map(|(h, order)| {
+        if *order == Some(app.holdings_sort_order) {
+            let arrow = glyphs.direction(app.holdings_sort_direction);
+            Cell::from(format!("{} {}", h, arrow)).style(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Cell::from(*h).style(Style::default().fg(Color::White))
+        }
+    });
+    let header_cells = grid_columns(app, header_cells.collect(), || {
+        grid_separator(glyphs, colors)
+    });
 
     let header = Row::new(header_cells)
         .style(Style::default().bg(colors.header_bg))
         .height(1);
 
-    let rows = app.quotes.iter().enumerate().filter_map(|(i, quote)| {
-        let holding = app.holdings.get(&quote.symbol)?;
-        let is_selected = i == app.selected;
-
-        let value = holding.current_value(quote.price);
-        let cost = holding.total_cost();
-        let pnl = holding.profit_loss(quote.price);
-        let pnl_pct = holding.profit_loss_percent(quote.price);
-        let today = holding.quantity * quote.change;
+    let today_date = chrono::Local::now().date_naive();
+    // Rows are exactly the held display quotes, so the row index is the selection index
+    let rows = app
+        .display_quotes()
+        .into_iter()
+        .filter_map(|quote| Some((quote, app.holdings.get(&quote.symbol)?)))
+        .enumerate()
+        .map(|(row, (quote, holding))| {
+            let value = holding.current_value(quote.price);
+            let cost = holding.total_cost();
+            let pnl = holding.profit_loss(quote.price);
+            let pnl_pct = holding.profit_loss_percent(quote.price);
+            let today = holding.quantity * quote.change;
 
-        let pnl_color = if pnl >= 0.0 { colors.gain } else { colors.loss };
-        let today_color = if today >= 0.0 {
-            colors.gain
-        } else {
-            colors.loss
-        };
+            let pnl_color = if pnl >= 0.0 { colors.gain } else { colors.loss };
+            let today_color = if today >= 0.0 {
+                colors.gain
+            } else {
+                colors.loss
+            };
 
-        let row_style = if is_selected {
-            Style::default().bg(colors.selected_bg)
-        } else {
-            Style::default()
-        };
+            let row_style = row_style(row == app.selected, row, app.zebra, colors);
 
-        let cells = vec![
-            Cell::from(quote.symbol.clone()),
-            Cell::from(truncate_string(&quote.name, 15)),
-            Cell::from(format_price(quote.price)),
-            Cell::from(format!("{:.4}", holding.quantity)),
-            Cell::from(format!("${:.2}", value)),
-            Cell::from(format!("${:.2}", cost)),
-            Cell::from(format!("{:+.2}", pnl)).style(Style::default().fg(pnl_color)),
-            Cell::from(format!("{:+.2}%", pnl_pct)).style(Style::default().fg(pnl_color)),
-            Cell::from(format!("{:+.2}", today)).style(Style::default().fg(today_color)),
-        ];
+            let cells = vec![
+                Cell::from(app.display_symbol(&quote.symbol)),
+                Cell::from(truncate_string(&quote.name, 15, glyphs.ellipsis)),
+                Cell::from(format_price(quote.price, quote.quote_type)),
+                Cell::from(format!("{:.4}", holding.quantity)),
+                Cell::from(format!("${}", format_money(value, app.rounding))),
+                Cell::from(format!("${}", format_money(cost, app.rounding))),
+                Cell::from(glyphs.signed(app, pnl, format_signed_money(pnl, app.rounding)))
+                    .style(Style::default().fg(pnl_color)),
+                Cell::from(glyphs.signed(
+                    app,
+                    pnl_pct,
+                    format_percent(pnl_pct, app.percent_precision),
+                ))
+                .style(Style::default().fg(pnl_color)),
+                Cell::from(glyphs.signed(app, today, format_signed_money(today, app.rounding)))
+                    .style(Style::default().fg(today_color)),
+                match holding.annualized_return(quote.price, today_date) {
+                    Some(ann) => Cell::from(format_percent(ann, app.percent_precision))
+                        .style(Style::default().fg(change_color(ann, colors))),
+                    None => Cell::from("-"),
+                },
+            ];
 
-        Some(Row::new(cells).style(row_style))
-    });
+            Row::new(grid_columns(app, cells, || grid_separator(glyphs, colors)))
+                .style(row_style)
+                .height(1 + app.row_spacing)
+        });
 
-    let widths = [
+    let widths = vec![
         Constraint::Length(10),
         Constraint::Length(17),
         Constraint::Length(10),
         Constraint::Length(10),
         Constraint::Length(12),
         Constraint::Length(12),
-        Constraint::Length(10),
-        Constraint::Length(10),
-        Constraint::Length(10),
+        Constraint::Length(10 + glyph_width(app)),
+        Constraint::Length(percent_column_width(app.percent_precision) + glyph_width(app)),
+        Constraint::Length(10 + glyph_width(app)),
+        Constraint::Length(percent_column_width(app.percent_precision)),
     ];
 
+    let widths = grid_columns(app, widths, || Constraint::Length(1));
     let table = Table::new(rows, widths)
         .header(header)
-        .block(Block::default().borders(Borders::NONE));
+        .block(grid_block(app, glyphs, colors));
 
     frame.render_widget(table, area);
 }
 
+/// Render the portfolio charts above the holdings table: this session's
+/// value on the left, net worth per recorded day on the right.
+fn render_portfolio_charts(frame: &mut Frame, app: &App, area: Rect, colors: &UiColors) {
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let recent: Vec<f64> = app
+        .portfolio_value_history
+        .iter()
+        .map(|(_, v)| *v)
+        .collect();
+    let glyphs = Glyphs::for_app(app);
+    render_value_sparkline(frame, "Portfolio", &recent, halves[0], colors, glyphs);
+
+    let daily = history::value_series(&app.portfolio_snapshots);
+    let title = format!("Net worth {}d", daily.len());
+    render_value_sparkline(frame, &title, &daily, halves[1], colors, glyphs);
+}
+
+/// Render a value series as a titled sparkline, or a placeholder while
+/// there are fewer than two points to draw.
+fn render_value_sparkline(
+    frame: &mut Frame,
+    label: &str,
+    values: &[f64],
+    area: Rect,
+    colors: &UiColors,
+    glyphs: &Glyphs,
+) {
+    let block = glyphs
+        .block(Borders::TOP)
+        .border_style(Style::default().fg(colors.border));
+
+    if values.len() < 2 {
+        let placeholder = Paragraph::new(Span::styled(
+            "Not enough history yet",
+            Style::default().add_modifier(Modifier::DIM),
+        ))
+        .block(block.title(format!(" {} ", label)));
+        frame.render_widget(placeholder, area);
+        return;
+    }
+
+    let first = values[0];
+    let last = values[values.len() - 1];
+    let low = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    // Only the most recent points fit; one column per point
+    let width = area.width as usize;
+    let data = sparkline_data(&values[values.len().saturating_sub(width)..]);
+
+    let sparkline = Sparkline::default()
+        .block(block.title(format!(
+            " {} ${:.2} (low ${:.2} / high ${:.2}) ",
+            label, last, low, high
+        )))
+        .data(&data)
+        .bar_set(glyphs.bars.clone())
+        .style(Style::default().fg(if last >= first {
+            colors.gain
+        } else {
+            colors.loss
+        }));
+
+    frame.render_widget(sparkline, area);
+}
+
+/// Scale a series onto 1..=100 for a sparkline.
+/// The floor is 1 rather than 0 so the lowest point still draws a bar.
+fn sparkline_data(values: &[f64]) -> Vec<u64> {
+    let low = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = high - low;
+
+    values
+        .iter()
+        .map(|v| {
+            if range > 0.0 {
+                1 + ((v - low) / range * 99.0).round() as u64
+            } else {
+                50
+            }
+        })
+        .collect()
+}
+
 /// Render the footer with keybindings.
 fn render_footer(frame: &mut Frame, app: &App, area: Rect, colors: &UiColors) {
-    let mode = if app.show_holdings {
-        "Holdings"
-    } else {
-        "Quotes"
+    if let Some(ref prompt) = app.prompt {
+        let line = Line::from(vec![
+            Span::styled(
+                format!(" {}", prompt.label()),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::raw(format!("{}_", prompt.buffer)),
+        ]);
+        frame.render_widget(
+            Paragraph::new(line).style(Style::default().bg(colors.header_bg)),
+            area,
+        );
+        return;
+    }
+
+    let mode = match app.active_view() {
+        View::Quotes => "Quotes",
+        View::Holdings => "Holdings",
+        View::Fundamentals => "Fundamentals",
     };
+    let glyphs = Glyphs::for_app(app);
+    let (sort_order, sort_direction) = app.active_sort();
     let sort_info = format!(
         "{} {}",
-        app.sort_order.header(),
-        match app.sort_direction {
-            crate::models::SortDirection::Ascending => "▲",
-            crate::models::SortDirection::Descending => "▼",
-        }
+        sort_order.header(),
+        glyphs.direction(sort_direction)
     );
+    let refresh_info = if app.is_fetching() {
+        format!("fetching{}", glyphs.ellipsis)
+    } else {
+        app.refresh_countdown()
+    };
+    let interval_info = format!("every {}s", app.refresh_interval.as_secs_f64());
 
+    // Says why rows are missing, when something's filtering them out
+    let filter_info = app
+        .filter_summary()
+        .map(|summary| format!(" | {}", summary))
+        .unwrap_or_default();
     let footer = Line::from(vec![
         Span::styled(" q", Style::default().fg(Color::Yellow)),
         Span::raw(":quit "),
@@ -339,8 +1081,8 @@ fn render_footer(frame: &mut Frame, app: &App, area: Rect, colors: &UiColors) {
         Span::styled("f", Style::default().fg(Color::Yellow)),
         Span::raw(":fundamentals "),
         Span::raw(format!(
-            "| {} | {} | Iter: {}",
-            mode, sort_info, app.iteration
+            "| {}{} | {} | Iter: {} | {}, {}",
+            mode, filter_info, sort_info, app.iteration, interval_info, refresh_info
         )),
     ]);
 
@@ -350,46 +1092,30 @@ fn render_footer(frame: &mut Frame, app: &App, area: Rect, colors: &UiColors) {
 }
 
 /// Render help overlay.
-fn render_help_overlay(frame: &mut Frame, colors: &UiColors) {
+fn render_help_overlay(frame: &mut Frame, colors: &UiColors, glyphs: &Glyphs) {
     let area = centered_rect(60, 70, frame.area());
 
-    let help_text = vec![
-        Line::from(Span::styled(
-            "STONKTOP HELP",
-            Style::default().add_modifier(Modifier::BOLD),
-        )),
-        Line::from(""),
-        Line::from("Navigation:"),
-        Line::from("  ↑/k       Move up"),
-        Line::from("  ↓/j       Move down"),
-        Line::from("  g/Home    Go to top"),
-        Line::from("  G/End     Go to bottom"),
-        Line::from("  PgUp      Page up"),
-        Line::from("  PgDn      Page down"),
-        Line::from(""),
-        Line::from("Sorting:"),
-        Line::from("  s         Cycle sort field"),
-        Line::from("  r         Reverse sort order"),
-        Line::from("  1-7       Sort by column"),
-        Line::from(""),
-        Line::from("Display:"),
-        Line::from("  H         Toggle holdings view"),
-        Line::from("  f         Toggle fundamentals"),
-        Line::from("  Tab       Cycle groups"),
-        Line::from(""),
-        Line::from("Actions:"),
-        Line::from("  Space/R   Force refresh"),
-        Line::from("  q/Esc     Quit"),
-        Line::from("  h/?       Toggle help"),
-        Line::from(""),
-        Line::from("Press any key to close"),
-    ];
+    let mut help_text = vec![Line::from(Span::styled(
+        "STONKTOP HELP",
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    let mut section = "";
+    for (heading, keys, description) in action::help_rows(glyphs.up_key, glyphs.down_key) {
+        if heading != section {
+            help_text.push(Line::from(""));
+            help_text.push(Line::from(format!("{}:", heading)));
+            section = heading;
+        }
+        help_text.push(Line::from(format!("  {:<10}{}", keys, description)));
+    }
+    help_text.push(Line::from(""));
+    help_text.push(Line::from("Press any key to close"));
 
     let help = Paragraph::new(help_text)
         .block(
-            Block::default()
+            glyphs
+                .block(Borders::ALL)
                 .title(" Help ")
-                .borders(Borders::ALL)
                 .border_style(Style::default().fg(colors.border)),
         )
         .wrap(Wrap { trim: false });
@@ -399,14 +1125,14 @@ fn render_help_overlay(frame: &mut Frame, colors: &UiColors) {
 }
 
 /// Render error message.
-fn render_error(frame: &mut Frame, error: &str, colors: &UiColors) {
+fn render_error(frame: &mut Frame, error: &str, colors: &UiColors, glyphs: &Glyphs) {
     let area = centered_rect(50, 20, frame.area());
 
     let error_widget = Paragraph::new(error)
         .block(
-            Block::default()
+            glyphs
+                .block(Borders::ALL)
                 .title(" Error ")
-                .borders(Borders::ALL)
                 .border_style(Style::default().fg(colors.loss)),
         )
         .style(Style::default().fg(colors.loss))
@@ -416,43 +1142,472 @@ fn render_error(frame: &mut Frame, error: &str, colors: &UiColors) {
     frame.render_widget(error_widget, area);
 }
 
-/// Create a centered rectangle.
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage((100 - percent_y) / 2),
-            Constraint::Percentage(percent_y),
-            Constraint::Percentage((100 - percent_y) / 2),
-        ])
-        .split(r);
-
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage((100 - percent_x) / 2),
-            Constraint::Percentage(percent_x),
-            Constraint::Percentage((100 - percent_x) / 2),
+/// Render the detail view for one quote. The name is never truncated here;
+/// that's what this view is for.
+fn render_detail_view(
+    frame: &mut Frame,
+    app: &App,
+    quote: &Quote,
+    colors: &UiColors,
+    glyphs: &Glyphs,
+) {
+    let area = centered_rect(60, 70, frame.area());
+    let change_style = Style::default().fg(change_color(quote.change_percent, colors));
+    let field = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{:<14}", label), Style::default().fg(Color::Yellow)),
+            Span::raw(value),
         ])
-        .split(popup_layout[1])[1]
-}
+    };
 
-/// Format price with appropriate precision.
-/// Penny stocks get more decimals because every fraction of a cent matters
-/// when you're hoping for that 10,000% gain.
-fn format_price(price: f64) -> String {
-    if price >= 1.0 {
-        // Normal prices get normal formatting
-        format!("${:.2}", price)
-    } else {
-        // Penny stocks and shitcoins need more precision
-        format!("${:.6}", price)
+    let mut lines = vec![
+        Line::from(Span::styled(
+            quote.name.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        field("Price", format_price(quote.price, quote.quote_type)),
+        Line::from(vec![
+            Span::styled(
+                format!("{:<14}", "Change"),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::styled(
+                glyphs.signed(
+                    app,
+                    quote.change,
+                    format!(
+                        "{:+.2} ({})",
+                        quote.change,
+                        format_percent(quote.change_percent, app.percent_precision).trim()
+                    ),
+                ),
+                change_style,
+            ),
+        ]),
+    ];
+    // Before the open, the gap to the last close is the number to watch
+    if let Some(gap) = gap_percent(quote) {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:<14}", "Pre-mkt gap"),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::styled(
+                glyphs.signed(
+                    app,
+                    gap,
+                    format_percent(gap, app.percent_precision)
+                        .trim()
+                        .to_string(),
+                ),
+                Style::default()
+                    .fg(change_color(gap, colors))
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]));
     }
-}
-
-/// Format volume with suffixes.
-fn format_volume(volume: u64) -> String {
-    if volume >= 1_000_000_000 {
+    if let Some(session) = app.session_change(quote) {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:<14}", "SESSION"),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::styled(
+                glyphs.signed(
+                    app,
+                    session,
+                    format_percent(session, app.percent_precision)
+                        .trim()
+                        .to_string(),
+                ),
+                Style::default().fg(change_color(session, colors)),
+            ),
+            Span::styled(
+                format!(
+                    "  since {}",
+                    format_price(app.session_open[&quote.symbol], quote.quote_type)
+                ),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+    }
+    if let Some(vwap) = vwap_proxy(quote) {
+        let (bias, color) = if quote.price >= vwap {
+            ("above", colors.gain)
+        } else {
+            ("below", colors.loss)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:<14}", "VWAP (approx)"),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::raw(format!("{}  ", format_price(vwap, quote.quote_type))),
+            Span::styled(format!("price {}", bias), Style::default().fg(color)),
+            Span::styled("  (H+L+last)/3", Style::default().fg(Color::DarkGray)),
+        ]));
+    }
+    lines.extend([
+        field(
+            "Prev close",
+            format_price(quote.previous_close, quote.quote_type),
+        ),
+        field("Open", format_price(quote.open, quote.quote_type)),
+        field(
+            "Day range",
+            format!(
+                "{} - {}",
+                format_price(quote.day_low, quote.quote_type),
+                format_price(quote.day_high, quote.quote_type)
+            ),
+        ),
+        field(
+            "52w range",
+            format!(
+                "{} - {}",
+                format_price(quote.year_low, quote.quote_type),
+                format_price(quote.year_high, quote.quote_type)
+            ),
+        ),
+        field(
+            "Volume",
+            format!(
+                "{} (avg {})",
+                format_volume(quote.volume, app.full_volume),
+                format_volume(quote.avg_volume, app.full_volume)
+            ),
+        ),
+        field("Market cap", format_market_cap(quote.market_cap)),
+        field("Exchange", quote.exchange.clone()),
+        field("Currency", quote.currency.clone()),
+        field("Type", quote.quote_type.to_string()),
+        field("Market", quote.market_state.to_string()),
+    ]);
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Indicators (per refresh)",
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+    let prices = app
+        .price_history
+        .get(&quote.symbol)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for (label, value) in indicator_fields(prices, quote.quote_type) {
+        lines.push(field(&label, value));
+    }
+    if let Some(holding) = app.holdings.get(&quote.symbol) {
+        lines.push(Line::from(""));
+        lines.push(field(
+            "Position",
+            format!(
+                "{} @ {} = {}",
+                holding.quantity,
+                format_price(holding.cost_basis, quote.quote_type),
+                format_money(holding.current_value(quote.price), app.rounding)
+            ),
+        ));
+    }
+
+    if let Some(note) = app.note(&quote.symbol) {
+        lines.push(Line::from(""));
+        lines.push(field("Note", note.to_string()));
+    }
+
+    let detail = Paragraph::new(lines)
+        .block(
+            glyphs
+                .block(Borders::ALL)
+                .title(format!(" {} ", quote.symbol))
+                .border_style(Style::default().fg(colors.border)),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(detail, area);
+}
+
+/// RSI, SMAs and MACD over a symbol's refresh-by-refresh prices, as
+/// label/value pairs. Each says how far along it is until there's enough
+/// history to compute it.
+fn indicator_fields(prices: &[f64], quote_type: QuoteType) -> Vec<(String, String)> {
+    let (fast, slow, signal) = MACD_PERIODS;
+    let warming_up = |needed: usize| format!("warming up ({}/{})", prices.len(), needed);
+
+    let rsi = calculate_rsi(prices, RSI_PERIOD)
+        .map(|rsi| format!("{:.1}", rsi))
+        .unwrap_or_else(|| warming_up(RSI_PERIOD + 1));
+    let mut fields = vec![(format!("RSI({})", RSI_PERIOD), rsi)];
+    for period in [20, 50] {
+        let sma = calculate_sma(prices, period)
+            .map(|sma| format_price(sma, quote_type))
+            .unwrap_or_else(|| warming_up(period));
+        fields.push((format!("SMA({})", period), sma));
+    }
+    let macd = calculate_macd(prices, fast, slow, signal)
+        .map(|m| format!("{:+.4} (signal {:+.4})", m.macd, m.signal))
+        .unwrap_or_else(|| warming_up(slow + signal - 1));
+    fields.push(("MACD".to_string(), macd));
+    fields
+}
+
+/// Render the offline banner: bigger than an error, and it stays until
+/// a refresh succeeds.
+fn render_offline(frame: &mut Frame, message: &str, colors: &UiColors, glyphs: &Glyphs) {
+    let area = centered_rect(60, 40, frame.area());
+
+    let banner = Paragraph::new(message)
+        .block(
+            glyphs
+                .block(Borders::ALL)
+                .title(" Offline - retrying ")
+                .border_style(
+                    Style::default()
+                        .fg(colors.loss)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        )
+        .style(Style::default().fg(colors.loss))
+        .wrap(Wrap { trim: true });
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(banner, area);
+}
+
+/// Render the panic warning across the whole screen. Hard to miss is the point.
+fn render_panic(
+    frame: &mut Frame,
+    app: &App,
+    day_change: f64,
+    threshold: f64,
+    colors: &UiColors,
+    glyphs: &Glyphs,
+) {
+    let area = frame.area();
+    let alarm = Style::default()
+        .fg(colors.loss)
+        .add_modifier(Modifier::BOLD);
+    let top_padding = area.height.saturating_sub(8) / 2;
+    let mut lines = vec![Line::from(""); top_padding as usize];
+    lines.extend([
+        Line::from(Span::styled(
+            format!("PORTFOLIO DOWN {:.2}% TODAY", day_change.abs()),
+            alarm,
+        )),
+        Line::from(""),
+        Line::from(format!(
+            "Today: {}   Value: ${}",
+            format_signed_money(app.today_portfolio_change(), app.rounding),
+            format_money(app.total_portfolio_value(), app.rounding)
+        )),
+        Line::from(format!("Panic threshold: {:.2}%", threshold)),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!(
+                "Any key dismisses. It comes back if the day gets another {} point worse.",
+                PANIC_REARM_STEP
+            ),
+            Style::default().fg(Color::DarkGray),
+        )),
+    ]);
+
+    let warning = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .block(
+            glyphs
+                .block(Borders::ALL)
+                .title(" Panic threshold ")
+                .border_style(alarm),
+        )
+        .wrap(Wrap { trim: true });
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(warning, area);
+}
+
+/// Background for a table row: selection first, then the zebra stripe on
+/// odd rows when enabled.
+fn row_style(is_selected: bool, row: usize, zebra: bool, colors: &UiColors) -> Style {
+    if is_selected {
+        Style::default().bg(colors.selected_bg)
+    } else if zebra && row % 2 == 1 {
+        Style::default().bg(colors.stripe_bg)
+    } else {
+        Style::default()
+    }
+}
+
+/// Base cell style for a quote's market state.
+/// Closed markets are dimmed so the eye goes to whatever is actually trading.
+fn market_state_style(state: MarketState) -> Style {
+    match state {
+        MarketState::Closed => Style::default().add_modifier(Modifier::DIM),
+        MarketState::Pre | MarketState::Regular | MarketState::Post => Style::default(),
+    }
+}
+
+/// Pick the gain/loss/neutral color for a signed value.
+fn change_color(value: f64, colors: &UiColors) -> Color {
+    if value > 0.0 {
+        colors.gain
+    } else if value < 0.0 {
+        colors.loss
+    } else {
+        colors.neutral
+    }
+}
+
+/// Create a centered rectangle.
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+/// Decimals for anything under a dollar.
+const PENNY_DECIMALS: usize = 6;
+
+/// Past this many decimals a coin is a rounding error with a logo.
+const MAX_CRYPTO_DECIMALS: usize = 10;
+
+/// Format a monetary amount to cents with the configured rounding, so the
+/// table, the header and every export agree on the last digit.
+/// Adding 0.0 turns a rounded -0.0 into 0.0; -0.00 is not a loss.
+pub(crate) fn format_money(value: f64, rounding: Rounding) -> String {
+    format!("{:.2}", rounding.round(value, 2) + 0.0)
+}
+
+/// [`format_money`] with an explicit sign.
+pub(crate) fn format_signed_money(value: f64, rounding: Rounding) -> String {
+    format!("{:+.2}", rounding.round(value, 2) + 0.0)
+}
+
+/// One quote as a single line of text, for pasting somewhere else:
+/// symbol, name, price and the day's change.
+pub(crate) fn quote_line(quote: &Quote, percent_precision: usize) -> String {
+    format!(
+        "{} {} {} {:+.2} ({})",
+        quote.symbol,
+        quote.name,
+        format_price(quote.price, quote.quote_type),
+        quote.change,
+        format_percent(quote.change_percent, percent_precision).trim()
+    )
+}
+
+/// Format price with appropriate precision.
+/// Penny stocks get more decimals because every fraction of a cent matters
+/// when you're hoping for that 10,000% gain.
+pub(crate) fn format_price(price: f64, quote_type: QuoteType) -> String {
+    format!("${:.*}", price_precision(price, quote_type), price)
+}
+
+/// Decimals for a price: cents from $1 up whatever it is, six places below.
+/// Crypto keeps four significant digits past that, because a coin at
+/// $0.00001234 is not $0.000012 to the people holding a billion of them.
+pub(crate) fn price_precision(price: f64, quote_type: QuoteType) -> usize {
+    if price >= 1.0 {
+        // Normal prices get normal formatting, BTC included
+        return 2;
+    }
+    if quote_type == QuoteType::Cryptocurrency && price > 0.0 {
+        let leading_zeros = (-price.log10()).floor() as usize;
+        return (leading_zeros + 4).clamp(PENNY_DECIMALS, MAX_CRYPTO_DECIMALS);
+    }
+    // Penny stocks and shitcoins need more precision
+    PENNY_DECIMALS
+}
+
+/// Pad formatted numbers so their decimal points line up: integer parts
+/// right-aligned to the widest, fractions left-aligned to the longest.
+fn align_decimal_points(values: Vec<String>) -> Vec<String> {
+    let split = |value: &str| -> (String, String) {
+        match value.split_once('.') {
+            Some((int, frac)) => (int.to_string(), frac.to_string()),
+            None => (value.to_string(), String::new()),
+        }
+    };
+    let parts: Vec<(String, String)> = values.iter().map(|v| split(v)).collect();
+    let int_width = parts.iter().map(|(int, _)| int.chars().count()).max();
+    let frac_width = parts.iter().map(|(_, frac)| frac.chars().count()).max();
+    let (Some(int_width), Some(frac_width)) = (int_width, frac_width) else {
+        return values;
+    };
+
+    parts
+        .into_iter()
+        .map(|(int, frac)| match (frac_width, frac.is_empty()) {
+            (0, _) => format!("{:>w$}", int, w = int_width),
+            // No point to line up; keep the column straight anyway
+            (_, true) => format!("{:>w$} {:f$}", int, "", w = int_width, f = frac_width),
+            _ => format!("{:>w$}.{:<f$}", int, frac, w = int_width, f = frac_width),
+        })
+        .collect()
+}
+
+/// Previous close for the PREV column. Before the open, once there's a
+/// pre-market trade, the gap rides along.
+fn format_prev_close(quote: &Quote, precision: usize) -> String {
+    if quote.previous_close <= 0.0 {
+        return String::new();
+    }
+    let close = format_price(quote.previous_close, quote.quote_type);
+    match gap_percent(quote) {
+        Some(gap) => format!("{} {}", close, format_percent(gap, precision)),
+        None => close,
+    }
+}
+
+/// Format a signed percentage, right-aligned to a width that only depends on
+/// the precision, so the column doesn't jitter as values change between refreshes.
+fn format_percent(value: f64, precision: usize) -> String {
+    format!(
+        "{:>width$}",
+        format!("{:+.*}%", precision, value),
+        width = percent_width(precision)
+    )
+}
+
+/// Width of a percent cell: sign, three integer digits, decimals and the '%'.
+fn percent_width(precision: usize) -> usize {
+    let decimals = if precision > 0 { precision + 1 } else { 0 };
+    1 + 3 + decimals + 1
+}
+
+/// Extra columns taken by a direction glyph and its space.
+fn glyph_width(app: &App) -> u16 {
+    if app.change_glyphs {
+        2
+    } else {
+        0
+    }
+}
+
+/// Table column width for percentages, never narrower than the "CHG% ▼" header.
+fn percent_column_width(precision: usize) -> u16 {
+    (percent_width(precision) + 2).max(10) as u16
+}
+
+/// Format volume with K/M/B suffixes, or as a full grouped integer.
+pub(crate) fn format_volume(volume: u64, full: bool) -> String {
+    if full {
+        volume.to_formatted_string(&Locale::en)
+    } else if volume >= 1_000_000_000 {
         format!("{:.2}B", volume as f64 / 1_000_000_000.0)
     } else if volume >= 1_000_000 {
         format!("{:.2}M", volume as f64 / 1_000_000.0)
@@ -464,7 +1619,7 @@ fn format_volume(volume: u64) -> String {
 }
 
 /// Format market cap with suffixes.
-fn format_market_cap(market_cap: Option<u64>) -> String {
+pub(crate) fn format_market_cap(market_cap: Option<u64>) -> String {
     match market_cap {
         Some(cap) if cap >= 1_000_000_000_000 => {
             format!("${:.2}T", cap as f64 / 1_000_000_000_000.0)
@@ -476,77 +1631,1023 @@ fn format_market_cap(market_cap: Option<u64>) -> String {
     }
 }
 
-/// Truncate string to max length.
-fn truncate_string(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else if max_len <= 3 {
-        ".".repeat(max_len)
-    } else {
-        let mut end = max_len.saturating_sub(3);
-        while !s.is_char_boundary(end) && end > 0 {
-            end -= 1;
-        }
-        format!("{}...", &s[..end])
+/// Truncate string to max length in characters, marking the cut with `ellipsis`.
+fn truncate_string(s: &str, max_len: usize, ellipsis: &str) -> String {
+    if s.chars().count() <= max_len {
+        return s.to_string();
+    }
+
+    let keep = max_len.saturating_sub(ellipsis.chars().count());
+    if keep == 0 {
+        return ellipsis.chars().take(max_len).collect();
     }
+    s.chars().take(keep).chain(ellipsis.chars()).collect()
+}
+
+/// Render batch mode output (non-interactive), limited to the `only`
+/// symbols when given.
+pub fn render_batch(app: &App, only: Option<&HashSet<String>>) {
+    print!("{}", format_batch(app, only));
 }
 
-/// Render batch mode output (non-interactive).
-pub fn render_batch(app: &App) {
+/// Build one batch-mode iteration as text.
+fn format_batch(app: &App, only: Option<&HashSet<String>>) -> String {
     use chrono::Local;
+    use std::fmt::Write;
 
-    println!(
+    let mut out = String::new();
+    let now = Utc::now();
+    // Batch output gets piped and parsed, so it stays ASCII whatever the TUI uses
+    let ellipsis = ASCII_GLYPHS.ellipsis;
+    let quotes: Vec<&Quote> = app
+        .display_quotes()
+        .into_iter()
+        .filter(|q| only.is_none_or(|only| only.contains(&q.symbol)))
+        .collect();
+
+    // Writing to a String can't fail, so the results are ignored throughout
+    let _ = writeln!(
+        out,
         "\n=== STONKTOP {} ===",
         Local::now().format("%Y-%m-%d %H:%M:%S")
     );
 
     if app.show_holdings {
-        println!(
+        let _ = writeln!(
+            out,
             "{:<10} {:<15} {:>10} {:>10} {:>12} {:>12} {:>10} {:>10}",
             "SYMBOL", "NAME", "PRICE", "QTY", "VALUE", "COST", "P/L", "P/L%"
         );
-        println!("{}", "-".repeat(100));
+        let _ = writeln!(out, "{}", "-".repeat(100));
 
-        for quote in &app.quotes {
+        for quote in &quotes {
             if let Some(holding) = app.holdings.get(&quote.symbol) {
                 let value = holding.current_value(quote.price);
                 let cost = holding.total_cost();
                 let pnl = holding.profit_loss(quote.price);
                 let pnl_pct = holding.profit_loss_percent(quote.price);
 
-                println!(
-                    "{:<10} {:<15} {:>10.2} {:>10.4} {:>12.2} {:>12.2} {:>+10.2} {:>+9.2}%",
+                let _ = writeln!(
+                    out,
+                    "{:<10} {:<15} {:>10.2} {:>10.4} {:>12} {:>12} {:>10} {:>10}",
                     quote.symbol,
-                    truncate_string(&quote.name, 15),
+                    truncate_string(&quote.name, 15, ellipsis),
                     quote.price,
                     holding.quantity,
-                    value,
-                    cost,
-                    pnl,
-                    pnl_pct
+                    format_money(value, app.rounding),
+                    format_money(cost, app.rounding),
+                    format_signed_money(pnl, app.rounding),
+                    format_percent(pnl_pct, app.percent_precision)
                 );
             }
         }
+
+        let weights = format_group_weights(&app.group_weights());
+        if !weights.is_empty() {
+            let _ = writeln!(out, "\nExposure:{}", weights);
+        }
     } else {
-        println!(
+        let _ = write!(
+            out,
             "{:<10} {:<20} {:>12} {:>10} {:>10} {:>12} {:>12}",
             "SYMBOL", "NAME", "PRICE", "CHANGE", "CHG%", "VOLUME", "MKT CAP"
         );
-        println!("{}", "-".repeat(90));
+        if app.verbose {
+            let [exch, ccy, kind, age] = VERBOSE_HEADERS;
+            let _ = write!(
+                out,
+                " {:<8} {:<5} {:<14} {:>6} {:<20}",
+                exch, ccy, kind, age, "TIME"
+            );
+        }
+        let _ = writeln!(out);
+        let _ = writeln!(out, "{}", "-".repeat(if app.verbose { 148 } else { 90 }));
 
-        for quote in &app.quotes {
-            println!(
-                "{:<10} {:<20} {:>12} {:>+10.2} {:>+9.2}% {:>12} {:>12}",
+        for quote in &quotes {
+            let _ = write!(
+                out,
+                "{:<10} {:<20} {:>12} {:>+10.2} {:>10} {:>12} {:>12}",
                 quote.symbol,
-                truncate_string(&quote.name, 20),
-                format_price(quote.price),
+                truncate_string(&quote.name, 20, ellipsis),
+                format_price(quote.price, quote.quote_type),
                 quote.change,
-                quote.change_percent,
-                format_volume(quote.volume),
+                format_percent(quote.change_percent, app.percent_precision),
+                format_volume(quote.volume, app.full_volume),
                 format_market_cap(quote.market_cap)
             );
+            if app.verbose {
+                let [exch, ccy, kind, age] = verbose_fields(quote, now);
+                let _ = write!(
+                    out,
+                    " {:<8} {:<5} {:<14} {:>6} {:<20}",
+                    exch,
+                    ccy,
+                    kind,
+                    age,
+                    quote.timestamp.format("%Y-%m-%dT%H:%M:%SZ")
+                );
+            }
+            let _ = writeln!(out);
+        }
+    }
+
+    if app.verbose && !app.failures.is_empty() {
+        let _ = writeln!(out, "\nFailed ({}):", app.failures.len());
+        for failure in &app.failures {
+            let _ = writeln!(out, "  {:<10} {}", failure.symbol, failure.reason);
+        }
+    }
+    // Worth saying even when quiet: these rows are old prices
+    let no_data = app.no_data_symbols();
+    if !no_data.is_empty() {
+        let _ = writeln!(out, "\nNo data ({}):", no_data.len());
+        for symbol in no_data {
+            let _ = writeln!(out, "  {}", symbol);
+        }
+    }
+    if app.verbose && !app.warnings.is_empty() {
+        let _ = writeln!(out, "\nWarnings ({}):", app.warnings.len());
+        for warning in &app.warnings {
+            let _ = writeln!(out, "  {}", warning);
+        }
+    }
+
+    let _ = writeln!(out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn test_format_volume_styles() {
+        assert_eq!(format_volume(999, false), "999");
+        assert_eq!(format_volume(1_500, false), "1.50K");
+        assert_eq!(format_volume(2_345_678, false), "2.35M");
+        assert_eq!(format_volume(7_000_000_000, false), "7.00B");
+
+        assert_eq!(format_volume(999, true), "999");
+        assert_eq!(format_volume(1_500, true), "1,500");
+        assert_eq!(format_volume(2_345_678, true), "2,345,678");
+        assert_eq!(format_volume(7_000_000_000, true), "7,000,000,000");
+    }
+
+    #[test]
+    fn test_price_precision_by_type() {
+        // BTC is just a big number: cents are fine
+        assert_eq!(
+            format_price(67_123.456, QuoteType::Cryptocurrency),
+            "$67123.46"
+        );
+        assert_eq!(format_price(0.5, QuoteType::Cryptocurrency), "$0.500000");
+        // A sub-cent coin keeps its significant digits instead of rounding away
+        assert_eq!(
+            format_price(0.000_012_34, QuoteType::Cryptocurrency),
+            "$0.00001234"
+        );
+        assert_eq!(price_precision(1e-15, QuoteType::Cryptocurrency), 10);
+        // Stocks keep the $1 heuristic
+        assert_eq!(format_price(0.000_012_34, QuoteType::Equity), "$0.000012");
+        assert_eq!(format_price(12.5, QuoteType::Equity), "$12.50");
+        assert_eq!(format_price(0.0, QuoteType::Cryptocurrency), "$0.000000");
+
+        // And the batch table goes through the same decision
+        let mut app = batch_app(false);
+        app.quotes[0].price = 0.000_012_34;
+        app.quotes[0].quote_type = QuoteType::Cryptocurrency;
+        let out = format_batch(&app, None);
+        let row = out.lines().find(|l| l.starts_with("AAA")).unwrap();
+        assert!(row.contains("$0.00001234"), "{row}");
+    }
+
+    #[test]
+    fn test_format_money_rounding_modes() {
+        assert_eq!(format_money(1.005, Rounding::HalfUp), "1.01");
+        assert_eq!(format_money(1.005, Rounding::HalfEven), "1.00");
+        assert_eq!(format_money(1.005, Rounding::Truncate), "1.00");
+        assert_eq!(format_signed_money(2.675, Rounding::HalfUp), "+2.68");
+        assert_eq!(format_signed_money(-2.675, Rounding::HalfEven), "-2.68");
+        assert_eq!(format_signed_money(-0.004, Rounding::HalfUp), "+0.00");
+        assert_eq!(format_money(-0.009, Rounding::Truncate), "0.00");
+    }
+
+    #[test]
+    fn test_format_percent_precision() {
+        assert_eq!(format_percent(1.23456, 2).trim(), "+1.23%");
+        assert_eq!(format_percent(1.23456, 3).trim(), "+1.235%");
+        assert_eq!(format_percent(-1.5, 0).trim(), "-2%");
+    }
+
+    #[test]
+    fn test_closed_market_rows_are_dimmed() {
+        assert!(market_state_style(MarketState::Closed)
+            .add_modifier
+            .contains(Modifier::DIM));
+        for state in [MarketState::Pre, MarketState::Regular, MarketState::Post] {
+            assert!(!market_state_style(state)
+                .add_modifier
+                .contains(Modifier::DIM));
         }
     }
 
-    println!();
+    #[test]
+    fn test_sparkline_data_scales_to_range() {
+        assert_eq!(sparkline_data(&[100.0, 150.0, 200.0]), vec![1, 51, 100]);
+        assert_eq!(sparkline_data(&[5.0, 5.0]), vec![50, 50]);
+        assert!(sparkline_data(&[]).is_empty());
+    }
+
+    fn spaced_app(selected: usize) -> App {
+        use crate::cli::Args;
+        use crate::config::Config;
+        use crate::models::Quote;
+        use clap::Parser;
+
+        let args = Args::parse_from(["stonktop", "--row-spacing", "1"]);
+        let mut app = App::new(&args, &Config::default()).unwrap();
+        app.quotes = ["AAA", "BBB", "CCC", "DDD", "EEE"]
+            .iter()
+            .map(|symbol| Quote {
+                symbol: symbol.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        app.selected = selected;
+        app
+    }
+
+    fn row_text(buffer: &ratatui::buffer::Buffer, y: u16) -> String {
+        (0..buffer.area.width)
+            .map(|x| buffer[(x, y)].symbol())
+            .collect()
+    }
+
+    /// Draw into a `width` x `height` test terminal and read back every line.
+    fn draw_rows(width: u16, height: u16, draw: impl FnOnce(&mut Frame)) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(draw).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height).map(|y| row_text(buffer, y)).collect()
+    }
+
+    /// The quotes table as text, one string per line.
+    fn render_rows(app: &App, width: u16, height: u16) -> Vec<String> {
+        draw_rows(width, height, |f| {
+            render_quotes_table(f, app, f.area(), &UiColors::default())
+        })
+    }
+
+    #[test]
+    fn test_row_spacing_keeps_selection_aligned() {
+        let app = spaced_app(2);
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+        terminal
+            .draw(|f| render_quotes_table(f, &app, f.area(), &UiColors::default()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+
+        // Header on line 0, then each row takes two lines
+        assert!(row_text(buffer, 1).contains("AAA"));
+        assert!(row_text(buffer, 2).trim().is_empty());
+        assert!(row_text(buffer, 5).contains("CCC"));
+
+        // The highlight lands on the selected symbol, not the spacer before it
+        let highlighted = |y: u16| buffer[(2, y)].modifier.contains(Modifier::REVERSED);
+        assert!(highlighted(5));
+        assert!(!highlighted(3));
+        assert!(!highlighted(1));
+    }
+
+    #[test]
+    fn test_row_spacing_scrolls_to_selection() {
+        let app = spaced_app(4);
+        // Room for the header and two spaced rows only
+        let visible = render_rows(&app, 80, 5)[1..].concat();
+        assert!(visible.contains("EEE"));
+        assert!(!visible.contains("AAA"));
+    }
+
+    #[test]
+    fn test_zebra_stripes_alternate_and_selection_wins() {
+        let mut app = spaced_app(3);
+        app.row_spacing = 0;
+        app.zebra = true;
+        let colors = UiColors::default();
+        let mut terminal = Terminal::new(TestBackend::new(80, 7)).unwrap();
+        terminal
+            .draw(|f| render_quotes_table(f, &app, f.area(), &colors))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+
+        // Rows start on line 1; sample a symbol cell on each
+        let backgrounds: Vec<Color> = (1..6).map(|y| buffer[(2, y)].bg).collect();
+        assert_eq!(
+            backgrounds,
+            vec![
+                Color::Reset,
+                colors.stripe_bg,
+                Color::Reset,
+                colors.selected_bg,
+                Color::Reset,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_percent_width_is_stable() {
+        for precision in 0..=4 {
+            let small = format_percent(0.1, precision);
+            let large = format_percent(-42.0, precision);
+            assert_eq!(small.len(), percent_width(precision));
+            assert_eq!(small.len(), large.len());
+        }
+    }
+
+    fn batch_app(verbose: bool) -> App {
+        let mut app = spaced_app(0);
+        app.verbose = verbose;
+        app.quotes.truncate(1);
+        app.quotes[0].exchange = "NMS".to_string();
+        app.quotes[0].currency = "USD".to_string();
+        app.quotes[0].timestamp = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        app.failures = vec![crate::models::FetchFailure {
+            symbol: "NOPE".to_string(),
+            reason: "HTTP 404".to_string(),
+        }];
+        app
+    }
+
+    #[test]
+    fn test_verbose_batch_shows_details_and_failures() {
+        let out = format_batch(&batch_app(true), None);
+
+        assert!(out.contains("EXCH"));
+        assert!(out.contains("TIME"));
+        let row = out.lines().find(|l| l.starts_with("AAA")).unwrap();
+        assert!(row.contains("NMS"));
+        assert!(row.contains("USD"));
+        assert!(row.contains("2023-11-14T22:13:20Z"));
+        assert!(out.contains("Failed (1):"));
+        assert!(out.contains("NOPE"));
+        assert!(out.contains("HTTP 404"));
+    }
+
+    #[test]
+    fn test_batch_prints_only_given_rows() {
+        let app = spaced_app(0);
+        let only: HashSet<String> = ["BBB".to_string(), "DDD".to_string()].into();
+        let out = format_batch(&app, Some(&only));
+        let rows: Vec<&str> = out
+            .lines()
+            .filter_map(|l| l.split_whitespace().next())
+            .filter(|s| s.len() == 3 && s.chars().all(|c| c.is_ascii_uppercase()))
+            .collect();
+        assert_eq!(rows, vec!["BBB", "DDD"]);
+    }
+
+    #[test]
+    fn test_quiet_batch_hides_details_and_failures() {
+        let out = format_batch(&batch_app(false), None);
+
+        assert!(out.lines().any(|l| l.starts_with("AAA")));
+        assert!(!out.contains("EXCH"));
+        assert!(!out.contains("NMS"));
+        assert!(!out.contains("NOPE"));
+    }
+
+    #[test]
+    fn test_data_age_never_negative() {
+        let now = DateTime::from_timestamp(1_700_000_100, 0).unwrap();
+        let mut quote = Quote {
+            timestamp: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(data_age_secs(&quote, now), 100);
+
+        quote.timestamp = now + chrono::Duration::seconds(5);
+        assert_eq!(data_age_secs(&quote, now), 0);
+    }
+
+    #[test]
+    fn test_truncate_string_profiles() {
+        assert_eq!(truncate_string("Apple", 10, "..."), "Apple");
+        assert_eq!(truncate_string("Alphabet Inc.", 10, "..."), "Alphabe...");
+        assert_eq!(truncate_string("Alphabet Inc.", 10, "…"), "Alphabet …");
+        assert_eq!(truncate_string("Alphabet Inc.", 2, "..."), "..");
+        // Counts characters, not bytes
+        assert_eq!(truncate_string("Société Générale", 8, "..."), "Socié...");
+    }
+
+    #[test]
+    fn test_ascii_profile_renders_only_ascii() {
+        let mut app = spaced_app(0);
+        app.unicode = false;
+        app.show_help = true;
+        app.quotes[0].name = "A Very Long Company Name Indeed".to_string();
+
+        let text = draw_rows(100, 40, |f| render(f, &app)).concat();
+
+        assert!(text.is_ascii(), "non-ASCII glyph in: {}", text);
+        assert!(text.contains("Up/k"));
+
+        let batch = format_batch(&app, None);
+        assert!(batch.contains("A Very Long Compa..."));
+    }
+
+    #[test]
+    fn test_unicode_profile_keeps_glyphs() {
+        let mut app = spaced_app(0);
+        app.show_help = true;
+
+        let text = draw_rows(100, 40, |f| render(f, &app)).concat();
+
+        assert!(text.contains('▼'));
+        assert!(text.contains("↑/k"));
+
+        // Batch output is for pipes, not terminals: the ellipsis stays ASCII
+        app.quotes[0].name = "A Very Long Company Name Indeed".to_string();
+        let batch = format_batch(&app, None);
+        assert!(batch.contains("A Very Long Compa..."));
+        assert!(!batch.contains('…'));
+    }
+
+    #[test]
+    fn test_ascii_sparkline_bars() {
+        let text = draw_rows(20, 3, |f| {
+            render_value_sparkline(
+                f,
+                "Test",
+                &[1.0, 2.0, 3.0, 4.0, 5.0],
+                f.area(),
+                &UiColors::default(),
+                &ASCII_GLYPHS,
+            )
+        })
+        .concat();
+
+        assert!(text.is_ascii());
+        assert!(text.contains('#'));
+    }
+
+    #[test]
+    fn test_format_group_weights() {
+        let weights = vec![("Tech".to_string(), 62.4), ("Other".to_string(), 37.6)];
+        assert_eq!(format_group_weights(&weights), "  Tech 62%, Other 38%");
+        assert_eq!(format_group_weights(&weights[..1]), "");
+    }
+
+    #[test]
+    fn test_name_width_truncates_table_but_not_detail() {
+        let long_name = "iShares Core MSCI Total International Stock ETF";
+        let mut app = spaced_app(0);
+        app.name_width = 10;
+        app.quotes[0].name = long_name.to_string();
+
+        let table = render_rows(&app, 120, 12).concat();
+        assert!(table.contains("iShares C…"));
+        assert!(!table.contains("iShares Co"));
+
+        app.show_detail = true;
+        let screen = draw_rows(120, 40, |f| render(f, &app)).concat();
+        assert!(screen.contains(long_name));
+    }
+
+    #[test]
+    fn test_notes_marked_in_table_and_shown_in_detail() {
+        let mut app = spaced_app(1);
+        app.row_spacing = 0;
+        app.notes
+            .insert("BBB".to_string(), "Bought the dip".to_string());
+
+        let table = |app: &App| {
+            let rows = render_rows(app, 120, 8);
+            (rows[1].clone(), rows[2].clone())
+        };
+        let (aaa, bbb) = table(&app);
+        assert!(bbb.contains("📝"), "{}", bbb);
+        assert!(!aaa.contains("📝"), "{}", aaa);
+        app.unicode = false;
+        assert!(table(&app).1.contains("n BBB"));
+
+        app.show_detail = true;
+        let screen = draw_rows(120, 40, |f| render(f, &app)).concat();
+        assert!(screen.contains("Bought the dip"));
+    }
+
+    #[test]
+    fn test_detail_view_shows_premarket_gap() {
+        let mut app = spaced_app(0);
+        app.show_detail = true;
+        app.quotes[0].price = 100.0;
+        app.quotes[0].previous_close = 100.0;
+        app.quotes[0].extended_price = Some(103.0);
+
+        let screen = |app: &App| draw_rows(120, 40, |f| render(f, app)).concat();
+
+        assert!(!screen(&app).contains("Pre-mkt gap"));
+        app.quotes[0].market_state = MarketState::Pre;
+        app.quotes[0].extended_price = None;
+        // No pre-market trade yet: nothing to show
+        assert!(!screen(&app).contains("Pre-mkt gap"));
+        app.quotes[0].extended_price = Some(103.0);
+        let text = screen(&app);
+        assert!(text.contains("Pre-mkt gap"));
+        assert!(text.contains("+3.00%"));
+    }
+
+    #[test]
+    fn test_change_glyphs_prefix_gains_and_losses() {
+        let mut app = spaced_app(0);
+        app.change_glyphs = true;
+        for (quote, change) in app.quotes.iter_mut().zip([1.5, -2.0, 0.0]) {
+            quote.change = change;
+            quote.change_percent = change;
+        }
+
+        let rows = |app: &App| {
+            let rows = render_rows(app, 120, 8);
+            [1, 3, 5].map(|y| rows[y].clone())
+        };
+
+        let [gain, loss, flat] = rows(&app);
+        // Both CHANGE and CHG% carry the glyph
+        assert!(gain.contains("▲ +1.50") && gain.matches('▲').count() == 2);
+        assert!(loss.contains("▼ -2.00") && loss.matches('▼').count() == 2);
+        assert!(flat.contains("= +0.00") && flat.matches('=').count() == 2);
+
+        app.unicode = false;
+        let [gain, loss, _] = rows(&app);
+        assert!(gain.contains("^ +1.50"));
+        assert!(loss.contains("v -2.00"));
+
+        app.change_glyphs = false;
+        let [gain, _, _] = rows(&app);
+        assert!(!gain.contains("^ "));
+    }
+
+    #[test]
+    fn test_primary_change_column() {
+        use crate::models::PrimaryChange;
+
+        let mut app = spaced_app(0);
+        app.sort_order = SortOrder::Symbol;
+        app.quotes[0].change = 1.25;
+        app.quotes[0].change_percent = 3.5;
+
+        let table = |app: &App| {
+            let rows = render_rows(app, 120, 4);
+            (rows[0].clone(), rows[1].clone())
+        };
+
+        let (header, row) = table(&app);
+        assert!(header.contains("CHANGE") && header.contains("CHG%"));
+        assert!(row.contains("+1.25") && row.contains("+3.50%"));
+
+        app.primary_change = PrimaryChange::Change;
+        let (header, row) = table(&app);
+        assert!(header.contains("CHANGE") && !header.contains("CHG%"));
+        assert!(row.contains("+1.25") && !row.contains("+3.50%"));
+        // The volume column moves up rather than leaving a hole
+        assert!(header.contains("CHANGE     VOLUME"));
+
+        app.primary_change = PrimaryChange::ChangePercent;
+        let (header, row) = table(&app);
+        assert!(!header.contains("CHANGE") && header.contains("CHG%"));
+        assert!(!row.contains("+1.25") && row.contains("+3.50%"));
+
+        // Verbose brings the other one back
+        app.verbose = true;
+        let (header, _) = table(&app);
+        assert!(header.contains("CHANGE") && header.contains("CHG%"));
+    }
+
+    #[test]
+    fn test_composite_in_header() {
+        let mut app = spaced_app(0);
+        let title = |app: &App| {
+            draw_rows(100, 3, |f| {
+                render_header(f, app, f.area(), &UiColors::default())
+            })
+            .swap_remove(0)
+        };
+        assert!(!title(&app).contains("My market"));
+
+        app.composite_index = Some(crate::models::Weighting::Equal);
+        for quote in &mut app.quotes {
+            quote.change_percent = 1.5;
+        }
+        assert!(title(&app).contains("My market +1.50%"), "{}", title(&app));
+
+        app.price_history
+            .insert(COMPOSITE_KEY.to_string(), vec![0.5, 1.0, 1.5]);
+        assert!(
+            title(&app).contains("My market +1.50% ▁▅█"),
+            "{}",
+            title(&app)
+        );
+    }
+
+    #[test]
+    fn test_net_change_follows_rounding() {
+        let mut app = spaced_app(0);
+        for quote in &mut app.quotes {
+            quote.change = -1.005;
+        }
+        let summary = |app: &App| {
+            draw_rows(100, 3, |f| {
+                render_header(f, app, f.area(), &UiColors::default())
+            })
+            .swap_remove(1)
+        };
+
+        app.rounding = Rounding::HalfUp;
+        assert!(summary(&app).contains("Net: -$1.01"), "{}", summary(&app));
+        app.rounding = Rounding::Truncate;
+        assert!(summary(&app).contains("Net: -$1.00"), "{}", summary(&app));
+    }
+
+    #[test]
+    fn test_portfolio_header_without_holdings() {
+        let mut app = spaced_app(0);
+        app.show_holdings = true;
+        let header = |app: &App| {
+            draw_rows(80, 3, |f| {
+                render_header(f, app, f.area(), &UiColors::default())
+            })
+            .swap_remove(1)
+        };
+
+        assert!(app.holdings.is_empty());
+        let summary = header(&app);
+        assert!(
+            summary.contains("No holdings configured — add [[holdings]] to config"),
+            "{}",
+            summary
+        );
+        assert!(!summary.contains("Value:"));
+
+        app.unicode = false;
+        assert!(header(&app).contains("No holdings configured - add"));
+
+        app.holdings.insert(
+            "AAA".to_string(),
+            crate::models::Holding {
+                symbol: "AAA".to_string(),
+                quantity: 1.0,
+                cost_basis: 100.0,
+                purchase_date: None,
+            },
+        );
+        assert!(header(&app).contains("Value: $"));
+    }
+
+    #[test]
+    fn test_spinner_advances_while_fetching() {
+        let mut app = spaced_app(0);
+        let header = |app: &App| {
+            draw_rows(80, 3, |f| {
+                render_header(f, app, f.area(), &UiColors::default())
+            })
+            .swap_remove(0)
+        };
+
+        let started = Instant::now();
+        let at = |ms: u64| started + std::time::Duration::from_millis(ms);
+
+        // Idle: no spinner
+        assert_eq!(app.spinner_step(started), None);
+        assert_eq!(activity_span(&app, started).content, "");
+        assert!(!header(&app).contains(UNICODE_GLYPHS.spinner[0]));
+
+        // Frames follow the clock, not how many times the screen was drawn
+        app.fetching_since = Some(started);
+        let frame = |ms: u64| activity_span(&app, at(ms)).content.trim().to_string();
+        for _ in 0..5 {
+            assert_eq!(frame(0), UNICODE_GLYPHS.spinner[0]);
+        }
+        assert_eq!(frame(99), UNICODE_GLYPHS.spinner[0]);
+        assert_eq!(frame(100), UNICODE_GLYPHS.spinner[1]);
+        assert_eq!(app.spinner_step(at(350)), Some(3));
+        // A clock that looks earlier than the start doesn't underflow
+        app.fetching_since = Some(started + std::time::Duration::from_secs(1));
+        assert_eq!(app.spinner_step(started), Some(0));
+
+        // Wraps around after the last frame
+        let len = UNICODE_GLYPHS.spinner.len();
+        assert_eq!(UNICODE_GLYPHS.spinner_frame(len), UNICODE_GLYPHS.spinner[0]);
+        assert_eq!(ASCII_GLYPHS.spinner_frame(5), "/");
+    }
+
+    #[test]
+    fn test_holdings_highlight_follows_selection() {
+        use crate::models::Holding;
+
+        let mut app = spaced_app(0);
+        app.row_spacing = 0;
+        app.quotes.truncate(3);
+        for symbol in ["AAA", "CCC"] {
+            app.holdings.insert(
+                symbol.to_string(),
+                Holding {
+                    symbol: symbol.to_string(),
+                    quantity: 1.0,
+                    cost_basis: 1.0,
+                    purchase_date: None,
+                },
+            );
+        }
+        app.set_view(View::Holdings);
+
+        // BBB isn't held, so the second row (and the second selection) is CCC
+        app.select_down();
+        assert_eq!(app.selected_quote().unwrap().symbol, "CCC");
+        app.select_down();
+        assert_eq!(app.selected, 1);
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 4)).unwrap();
+        terminal
+            .draw(|f| render_holdings_table(f, &app, f.area(), &UiColors::default()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let selected_bg = UiColors::default().selected_bg;
+
+        assert!(row_text(buffer, 1).contains("AAA"));
+        assert!(row_text(buffer, 2).contains("CCC"));
+        assert!(!row_text(buffer, 3).contains("BBB"));
+        assert_ne!(buffer[(0, 1)].bg, selected_bg);
+        assert_eq!(buffer[(0, 2)].bg, selected_bg);
+    }
+
+    #[test]
+    fn test_holdings_hotkeys_sort_the_column_they_name() {
+        let mut app = spaced_app(0);
+        app.set_view(View::Holdings);
+
+        for column in 1..=9u8 {
+            let before = app.holdings_sort_order;
+            app.sort_by_column(column);
+            let expected = HOLDINGS_COLUMNS[column as usize - 1].1;
+            assert_eq!(app.holdings_sort_order, expected.unwrap_or(before));
+
+            let header = draw_rows(140, 3, |f| {
+                render_holdings_table(f, &app, f.area(), &UiColors::default())
+            })
+            .swap_remove(0);
+            let labels: Vec<&str> = header.split_whitespace().collect();
+
+            // The arrow sits right after the nth header, or the sorted one
+            // stays marked when the nth column doesn't sort
+            let arrow = Glyphs::for_app(&app).direction(app.holdings_sort_direction);
+            let marked = labels.iter().position(|l| *l == arrow).unwrap() - 1;
+            assert_eq!(labels[marked], app.holdings_sort_order.header());
+            if expected.is_some() {
+                assert_eq!(marked, column as usize - 1, "{header}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_since_launch_column() {
+        let mut app = spaced_app(0);
+        app.row_spacing = 0;
+        app.quotes.truncate(2);
+        app.quotes[0].price = 110.0;
+        app.quotes[1].price = 50.0;
+        app.session_open.insert("AAA".to_string(), 100.0);
+
+        let rows = |app: &App| {
+            let [head, first, second] = render_rows(app, 140, 3).try_into().unwrap();
+            (head, first, second)
+        };
+        assert!(!rows(&app).0.contains("SESS%"));
+
+        app.show_since_launch = true;
+        let (head, aaa, bbb) = rows(&app);
+        assert!(head.contains("SESS%"));
+        assert!(aaa.contains("+10.00%"), "{aaa}");
+        // No baseline yet, nothing to show past CHG%
+        assert_eq!(bbb.matches('%').count(), 1, "{bbb}");
+    }
+
+    #[test]
+    fn test_extended_hours_column_by_market_state() {
+        let mut app = spaced_app(0);
+        app.row_spacing = 0;
+        app.quotes.truncate(2);
+        app.show_extended_hours = true;
+        for quote in &mut app.quotes {
+            quote.price = 100.0;
+            quote.extended_price = Some(97.0);
+            quote.market_state = MarketState::Regular;
+        }
+
+        let rows = |app: &App| {
+            let [head, first, second] = render_rows(app, 140, 3).try_into().unwrap();
+            (head, first, second)
+        };
+        // Regular hours: no column at all
+        assert!(!rows(&app).0.contains("EXT%"));
+
+        app.quotes[0].market_state = MarketState::Post;
+        let (head, aaa, bbb) = rows(&app);
+        assert!(head.contains("EXT%"));
+        assert!(aaa.contains("-3.00%"), "{aaa}");
+        // Still in the regular session, so only CHG% has a percent
+        assert_eq!(bbb.matches('%').count(), 1, "{bbb}");
+
+        app.show_extended_hours = false;
+        assert!(!rows(&app).0.contains("EXT%"));
+    }
+
+    #[test]
+    fn test_indicator_fields_warm_up() {
+        let prices: Vec<f64> = (0..10).map(|i| 100.0 + i as f64).collect();
+        let fields = indicator_fields(&prices, QuoteType::Equity);
+        let labels: Vec<&str> = fields.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, ["RSI(14)", "SMA(20)", "SMA(50)", "MACD"]);
+        assert_eq!(fields[0].1, "warming up (10/15)");
+        assert_eq!(fields[1].1, "warming up (10/20)");
+        assert_eq!(fields[2].1, "warming up (10/50)");
+        assert_eq!(fields[3].1, "warming up (10/34)");
+
+        // Thirty rising prices: RSI and SMA(20) are ready, the rest still waiting
+        let prices: Vec<f64> = (0..30).map(|i| 100.0 + i as f64).collect();
+        let fields = indicator_fields(&prices, QuoteType::Equity);
+        assert_eq!(fields[0].1, "100.0");
+        assert_eq!(fields[1].1, "$119.50");
+        assert_eq!(fields[2].1, "warming up (30/50)");
+        assert_eq!(fields[3].1, "warming up (30/34)");
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#ff8000"), Some(Color::Rgb(255, 128, 0)));
+        assert_eq!(parse_hex_color(" #00FF7f "), Some(Color::Rgb(0, 255, 127)));
+        assert_eq!(parse_hex_color("ff8000"), None);
+        assert_eq!(parse_hex_color("#fff"), None);
+        assert_eq!(parse_hex_color("#gg0000"), None);
+        assert_eq!(parse_hex_color("#ff80é"), None);
+    }
+
+    #[test]
+    fn test_symbol_color_override() {
+        let mut app = spaced_app(0);
+        app.row_spacing = 0;
+        app.quotes[0].change_percent = -3.0;
+        app.symbol_colors
+            .insert("AAA".to_string(), "#ff00ff".to_string());
+        app.symbol_colors
+            .insert("BBB".to_string(), "not a color".to_string());
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 4)).unwrap();
+        terminal
+            .draw(|f| render_quotes_table(f, &app, f.area(), &UiColors::default()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let symbol_fg = |y: u16| {
+            let x = row_text(buffer, y).find(['A', 'B']).unwrap() as u16;
+            buffer[(x, y)].fg
+        };
+        // Overrides the red of a down day; a bad color is just ignored
+        assert_eq!(symbol_fg(1), Color::Rgb(255, 0, 255));
+        assert_eq!(symbol_fg(2), Color::Reset);
+    }
+
+    #[test]
+    fn test_align_decimal_points() {
+        let aligned = align_decimal_points(vec![
+            "$150.00".to_string(),
+            "$0.000123".to_string(),
+            "$64000.00".to_string(),
+        ]);
+        assert_eq!(aligned, ["  $150.00    ", "    $0.000123", "$64000.00    "]);
+        assert!(align_decimal_points(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_aligned_price_column_snapshot() {
+        let mut app = spaced_app(0);
+        app.row_spacing = 0;
+        app.quotes.truncate(2);
+        app.quotes[0].price = 150.0;
+        app.quotes[1].price = 0.000123;
+
+        let price_cells = |app: &App| {
+            let rows = render_rows(app, 140, 3);
+            let start = rows[0].find("PRICE").unwrap();
+            [1, 2].map(|y| rows[y][start..start + 13].to_string())
+        };
+        assert_eq!(price_cells(&app), ["$150.00      ", "$0.000123    "]);
+
+        app.align_decimals = true;
+        assert_eq!(price_cells(&app), ["$150.00      ", "  $0.000123  "]);
+    }
+
+    #[test]
+    fn test_prev_close_column() {
+        let mut quote = Quote {
+            symbol: "AAPL".to_string(),
+            price: 150.0,
+            previous_close: 150.0,
+            extended_price: Some(153.0),
+            market_state: MarketState::Regular,
+            ..Default::default()
+        };
+        assert_eq!(format_prev_close(&quote, 2), "$150.00");
+        // Before the open, the overnight gap comes along
+        quote.market_state = MarketState::Pre;
+        assert_eq!(format_prev_close(&quote, 2), "$150.00   +2.00%");
+        // ...once there's been a pre-market trade
+        quote.extended_price = None;
+        assert_eq!(format_prev_close(&quote, 2), "$150.00");
+        // No close, no column noise
+        quote.previous_close = 0.0;
+        assert_eq!(format_prev_close(&quote, 2), "");
+
+        let mut app = spaced_app(0);
+        app.quotes.truncate(1);
+        app.quotes[0].price = 10.5;
+        app.quotes[0].previous_close = 10.0;
+        let header = |app: &App| {
+            let rows = render_rows(app, 140, 3);
+            (rows[0].clone(), rows[1].clone())
+        };
+        assert!(!header(&app).0.contains("PREV"));
+        app.show_prev_close = true;
+        let (head, row) = header(&app);
+        assert!(head.contains("PREV"));
+        assert!(row.contains("$10.00"), "{row}");
+    }
+
+    #[test]
+    fn test_grid_draws_column_separators() {
+        let mut app = spaced_app(0);
+        app.row_spacing = 0;
+        app.quotes.truncate(2);
+
+        let plain = render_rows(&app, 140, 5);
+        assert!(plain.iter().all(|line| !line.contains('│')));
+        assert!(plain[4].trim().is_empty());
+
+        app.grid = true;
+        let grid = render_rows(&app, 140, 5);
+        // Header and every row have a rule between each pair of columns
+        for line in &grid[..3] {
+            assert_eq!(line.matches('│').count(), 7, "{}", line);
+        }
+        assert!(grid[0].contains("│ SYMBOL"));
+        assert!(grid[1].contains("│ AAA"));
+        // And a rule underneath
+        assert!(grid[4].starts_with("────"));
+
+        app.unicode = false;
+        let ascii = render_rows(&app, 140, 5);
+        assert!(ascii[1].contains("| AAA"));
+    }
+
+    #[test]
+    fn test_highlight_match_splits_around_match() {
+        let emphasis = Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+        let spans = |line: Line<'static>| -> Vec<(String, Style)> {
+            line.spans
+                .into_iter()
+                .map(|s| (s.content.into_owned(), s.style))
+                .collect()
+        };
+
+        assert_eq!(
+            spans(highlight_match("Apple Inc.", Some("PL"))),
+            vec![
+                ("Ap".to_string(), Style::default()),
+                ("pl".to_string(), emphasis),
+                ("e Inc.".to_string(), Style::default()),
+            ]
+        );
+        // Matches at either end don't leave empty spans behind
+        assert_eq!(
+            spans(highlight_match("AAPL", Some("aa"))),
+            vec![
+                ("AA".to_string(), emphasis),
+                ("PL".to_string(), Style::default())
+            ]
+        );
+        assert_eq!(
+            spans(highlight_match("AAPL", Some("pl"))),
+            vec![
+                ("AA".to_string(), Style::default()),
+                ("PL".to_string(), emphasis)
+            ]
+        );
+        // No query or no match: the text as is
+        assert_eq!(spans(highlight_match("AAPL", None)).len(), 1);
+        assert_eq!(spans(highlight_match("AAPL", Some("xyz"))).len(), 1);
+        // Non-ASCII text doesn't trip over char boundaries
+        assert_eq!(
+            spans(highlight_match("Société Générale", Some("gén")))[1].0,
+            "Gén"
+        );
+    }
 }
@@ -56,6 +56,19 @@ fn test_invalid_delay() {
     assert!(!output.status.success());
 }
 
+#[test]
+fn test_invalid_pasted_symbols() {
+    let output = stonktop_bin()
+        .args(["-s", "aapl; goog$l", "--check"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"GOOG$L\""), "{}", stderr);
+    assert!(!stderr.contains("\"AAPL\""), "{}", stderr);
+}
+
 /// Test batch mode with network access.
 /// This test is ignored by default as it requires network access.
 /// Run with: cargo test -- --ignored
@@ -143,3 +156,177 @@ fn test_env_vars_documented() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("STONKTOP_SYMBOLS") || stdout.contains("env"));
 }
+
+/// Batch mode against a fixture file: no network, fully deterministic.
+#[test]
+fn test_batch_mode_with_fixture() {
+    let dir = std::env::temp_dir().join(format!("stonktop-fixture-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let fixture = dir.join("quotes.json");
+    std::fs::write(
+        &fixture,
+        r#"[
+            {"symbol": "AAPL", "name": "Apple Inc.", "price": 110.0,
+             "previous_close": 100.0, "volume": 1500000, "step": 1.0},
+            {"symbol": "MSFT", "name": "Microsoft", "price": 95.0,
+             "previous_close": 100.0, "volume": 2000}
+        ]"#,
+    )
+    .unwrap();
+    // Empty config so a local ~/.config/stonktop doesn't leak in
+    let config = dir.join("config.toml");
+    std::fs::write(&config, "").unwrap();
+
+    let output = stonktop_bin()
+        .args(["-b", "-n", "2", "-d", "1", "-o", "change-percent"])
+        .arg("--fixture")
+        .arg(&fixture)
+        .env_remove("STONKTOP_SYMBOLS")
+        .env("STONKTOP_CONFIG", &config)
+        .output()
+        .expect("Failed to execute command");
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let rows: Vec<&str> = stdout
+        .lines()
+        .filter(|l| l.starts_with("AAPL") || l.starts_with("MSFT"))
+        .collect();
+
+    // Two iterations, biggest gainer first each time
+    assert_eq!(rows.len(), 4, "{}", stdout);
+    assert!(rows[0].starts_with("AAPL"));
+    assert!(rows[0].contains("$110.00"));
+    assert!(rows[0].contains("+10.00%"));
+    assert!(rows[0].contains("1.50M"));
+    assert!(rows[1].starts_with("MSFT"));
+    assert!(rows[1].contains("-5.00%"));
+    // Prices step between iterations
+    assert!(rows[2].contains("$111.00"));
+}
+
+/// `--check` resolves a config without fetching anything, and fails on problems.
+#[test]
+fn test_check_reports_resolved_config() {
+    let dir = std::env::temp_dir().join(format!("stonktop-check-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("config.toml");
+    std::fs::write(
+        &config,
+        r#"
+        [watchlist]
+        symbols = ["aapl", "btc"]
+
+        [[holdings]]
+        symbol = "MSFT"
+        quantity = 10
+        cost_basis = 300.0
+
+        [alerts]
+        notify_on_move_pct = 5.0
+        quiet_start = "22:00"
+        quiet_end = "07:00"
+
+        [[alerts.rules]]
+        symbol = "AAPL"
+        condition = { change_percent_below = -5.0 }
+
+        [groups]
+        crypto = ["btc", "eth"]
+        "#,
+    )
+    .unwrap();
+    let broken = dir.join("broken.toml");
+    std::fs::write(
+        &broken,
+        "[watchlist]\nsymbols = [\"AAPL\", \"NOT/ASYMBOL\"]\n",
+    )
+    .unwrap();
+
+    let run = |path: &std::path::Path| {
+        stonktop_bin()
+            .arg("--check")
+            .env_remove("STONKTOP_SYMBOLS")
+            .env("STONKTOP_CONFIG", path)
+            .output()
+            .expect("Failed to execute command")
+    };
+    let output = run(&config);
+    let failed = run(&broken);
+    std::fs::remove_dir_all(&dir).ok();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    // Shortcuts are expanded, holdings and groups are pulled in
+    assert!(stdout.contains("Symbols (4):"), "{}", stdout);
+    for expected in ["BTC-USD", "MSFT", "ETH-USD", "crypto: BTC-USD, ETH-USD"] {
+        assert!(stdout.contains(expected), "{}", stdout);
+    }
+    assert!(stdout.contains("change_percent_below -5"), "{}", stdout);
+    assert!(stdout.contains("quiet 22:00 - 07:00"), "{}", stdout);
+    assert!(stdout.trim_end().ends_with("OK"), "{}", stdout);
+
+    let stdout = String::from_utf8_lossy(&failed.stdout);
+    assert!(!failed.status.success());
+    assert!(
+        stdout.contains("Invalid symbol: \"NOT/ASYMBOL\""),
+        "{}",
+        stdout
+    );
+}
+
+/// `--check --probe` fetches each symbol once and reports the ones that
+/// don't come back; plain `--check` still doesn't fetch.
+#[test]
+fn test_check_probe_reports_missing_symbols() {
+    let dir = std::env::temp_dir().join(format!("stonktop-probe-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let fixture = dir.join("quotes.json");
+    std::fs::write(&fixture, r#"[{"symbol": "AAPL", "price": 150.0}]"#).unwrap();
+    let config = dir.join("config.toml");
+    std::fs::write(&config, "[watchlist]\nsymbols = [\"AAPL\", \"APPL\"]\n").unwrap();
+
+    let run = |extra: &[&str]| {
+        stonktop_bin()
+            .arg("--check")
+            .args(extra)
+            .arg("--fixture")
+            .arg(&fixture)
+            .env_remove("STONKTOP_SYMBOLS")
+            .env("STONKTOP_CONFIG", &config)
+            .output()
+            .expect("Failed to execute command")
+    };
+    let unprobed = run(&[]);
+    let probed = run(&["--probe"]);
+    std::fs::remove_dir_all(&dir).ok();
+
+    let stdout = String::from_utf8_lossy(&unprobed.stdout);
+    assert!(unprobed.status.success(), "{}", stdout);
+    assert!(!stdout.contains("Probe:"), "{}", stdout);
+
+    let stdout = String::from_utf8_lossy(&probed.stdout);
+    assert!(!probed.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("Probe: 1 of 2 symbols fetched"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("Couldn't fetch APPL: Not in fixture"),
+        "{}",
+        stdout
+    );
+
+    // A probe is part of a check, not a mode of its own
+    let output = stonktop_bin()
+        .args(["--probe", "-s", "AAPL"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+}